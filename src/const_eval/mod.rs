@@ -1,5 +1,679 @@
-use crate::parser::ast::AstNode;
+use crate::diagnostics::builder::DiagnosticBuilder;
+use crate::diagnostics::source::SourceFile;
+use crate::diagnostics::span::Span;
+use crate::lexer::token::BinOp;
+use crate::parser::attrs::Constness;
+use crate::parser::ast::{ArrayInst, AstNode, Block, CallExprNode, ConstValNode, FunctionNode, LocalAssign, NumberType, OwnedTy, StmtKind, StructDef, Ty, TyKind};
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+use std::rc::Rc;
 
-pub fn eval_usize(val: AstNode) -> anyhow::Result<usize> {
-    todo!()
-}
\ No newline at end of file
+// consts get evaluated by interpreting their values directly on the ast:
+// `const AREA: u32 = SIDE * SIDE;` evaluates `SIDE` first (which is cached afterwards) and multiplies it with itself.
+// integer literals don't have a type of their own, they take the type which is expected where they are used,
+// so `200 + 100` is evaluated as `u8` in `const LIMIT: u8 = 200 + 100;` and overflows.
+// every operation is checked, overflows and divisions by zero are reported pointing at the expression they occur in
+// and consts which depend on themselves (`const A: u32 = B; const B: u32 = A;`) are reported as cycles.
+// calls of `const fn`s (and of functions which were inferred to be const) get executed by evaluating their bodies with the args as locals, to make sure the evaluation
+// terminates, the number of evaluated expressions and nested calls is limited
+
+/// the maximum number of elements an array created by `[val; amount]` may have
+const MAX_ARRAY_LEN: usize = 1 << 16;
+/// the maximum number of expressions a single evaluation may evaluate
+const MAX_STEPS: usize = 1 << 20;
+/// the maximum number of nested calls of `const fn`s
+const MAX_CALL_DEPTH: usize = 64;
+
+/// the value of a constant expression
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConstValue {
+    Unit,
+    Number(NumberType),
+    Bool(bool),
+    Str(String),
+    Struct {
+        name: String,
+        fields: Vec<(String, ConstValue)>,
+    },
+    Array(Vec<ConstValue>),
+}
+
+/// the value as it would be written in the source, numbers are suffixed by their type, e.g. `200_u8`
+impl Display for ConstValue {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ConstValue::Unit => f.write_str("()"),
+            ConstValue::Number(number) => write!(f, "{}_{}", number, number_ty_name(number)),
+            ConstValue::Bool(val) => write!(f, "{}", val),
+            ConstValue::Str(content) => write!(f, "\"{}\"", content),
+            ConstValue::Struct { name, fields } => {
+                let fields = fields.iter().map(|(name, val)| format!("{}: {}", name, val)).collect::<Vec<_>>();
+                write!(f, "{} {{ {} }}", name, fields.join(", "))
+            }
+            ConstValue::Array(vals) => write!(f, "[{}]", vals.iter().map(|val| val.to_string()).collect::<Vec<_>>().join(", ")),
+        }
+    }
+}
+
+impl ConstValue {
+
+    /// the value as a length or an index, only unsigned integers which fit into a `usize` are valid
+    pub fn as_usize(&self) -> Option<usize> {
+        match *self {
+            ConstValue::Number(NumberType::USize(val)) => Some(val),
+            ConstValue::Number(NumberType::U8(val)) => Some(val as usize),
+            ConstValue::Number(NumberType::U16(val)) => Some(val as usize),
+            ConstValue::Number(NumberType::U32(val)) => usize::try_from(val).ok(),
+            ConstValue::Number(NumberType::U64(val)) => usize::try_from(val).ok(),
+            ConstValue::Number(NumberType::U128(val)) => usize::try_from(val).ok(),
+            _ => None,
+        }
+    }
+
+    fn ty(&self) -> Option<Ty> {
+        match self {
+            ConstValue::Number(number) => Some(named_ty(number_ty_name(number))),
+            ConstValue::Bool(_) => Some(named_ty("bool")),
+            _ => None,
+        }
+    }
+
+}
+
+/// the constness of a function without a `const` or `runtime` modifier, it can be called
+/// in constants if its body only contains operations which can be executed at compile time
+#[derive(Debug, Clone, PartialEq)]
+pub enum InferredConstness {
+    Const,
+    /// the notes explain why the function can only be called at runtime
+    Runtime(Vec<String>),
+}
+
+/// the reasons for which the evaluation of a constant expression failed
+enum EvalError {
+    /// `at` is the expression (as written in the source) the error occurred at and `span` its span
+    Error {
+        msg: String,
+        at: String,
+        span: Span,
+        notes: Vec<String>,
+    },
+    /// the const which was evaluated depends on itself, the consts leading back to it are listed in order
+    Cycle(Vec<String>),
+    /// the error occurred in another const and was already reported there
+    Reported,
+}
+
+fn error(msg: String, at: &AstNode) -> EvalError {
+    EvalError::Error {
+        msg,
+        at: at.to_string(),
+        span: at.span(),
+        notes: vec![],
+    }
+}
+
+/// evaluates consts and the other values which have to be known at compile time (like the lengths of arrays)
+#[derive(Default)]
+pub struct ConstEvaluator {
+    consts: HashMap<String, ConstValNode>,
+    structs: HashMap<String, StructDef>,
+    // the values of the consts which were evaluated already, `Err` if their evaluation failed (and was reported)
+    results: HashMap<String, Result<ConstValue, ()>>,
+    // the consts which are currently being evaluated
+    stack: Vec<String>,
+    fns: HashMap<String, FunctionNode>,
+    // the constness of the functions without a `const` or `runtime` modifier
+    inferred: HashMap<String, InferredConstness>,
+    // the locals of the blocks which are currently being evaluated
+    locals: Vec<HashMap<String, ConstValue>>,
+    // the number of expressions evaluated and the number of nested calls of the current evaluation
+    steps: usize,
+    depth: usize,
+    // the file the consts were parsed from, errors in consts point into it
+    source: Rc<SourceFile>,
+}
+
+impl ConstEvaluator {
+
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn set_source(&mut self, source: Rc<SourceFile>) {
+        self.source = source;
+    }
+
+    pub fn insert_const(&mut self, val: &ConstValNode) {
+        self.consts.insert(val.left().clone(), val.clone());
+    }
+
+    /// whether `name` refers to a const item
+    pub fn is_const(&self, name: &String) -> bool {
+        self.consts.contains_key(name)
+    }
+
+    pub fn insert_fn(&mut self, func: &FunctionNode) {
+        self.fns.insert(func.header.name.clone(), func.clone());
+    }
+
+    pub fn insert_inferred_constness(&mut self, name: String, constness: InferredConstness) {
+        self.inferred.insert(name, constness);
+    }
+
+    /// the constness of the function `name` if it has neither a `const` nor a `runtime` modifier
+    pub fn inferred_constness(&self, name: &String) -> Option<&InferredConstness> {
+        self.inferred.get(name)
+    }
+
+    pub fn insert_struct(&mut self, def: &StructDef) {
+        self.structs.insert(def.name.clone(), def.clone());
+    }
+
+    /// evaluates the const `name`, errors are only reported the first time the const gets evaluated
+    pub fn eval_const(&mut self, name: &String, diagnostics: &mut DiagnosticBuilder) -> Option<ConstValue> {
+        self.steps = 0;
+        self.const_value(name, diagnostics).ok()
+    }
+
+    /// evaluates a value which is expected to be of type `ty` (if it's known), e.g. a const generic arg,
+    /// `input` is the source the value is a part of, e.g. `[u8; 1 / 0]`, it's shown for errors which don't point into the source
+    pub fn eval(&mut self, node: &AstNode, ty: Option<&Ty>, input: String, diagnostics: &mut DiagnosticBuilder) -> Option<ConstValue> {
+        self.steps = 0;
+        match self.eval_node(node, ty, diagnostics) {
+            Ok(val) => Some(val),
+            Err(err) => {
+                self.report(err, (input, Span::NONE), diagnostics);
+                None
+            }
+        }
+    }
+
+    /// evaluates a value of type `usize` like the length of an array, see `eval`
+    pub fn eval_usize(&mut self, node: &AstNode, input: String, diagnostics: &mut DiagnosticBuilder) -> Option<usize> {
+        self.eval(node, Some(&named_ty("usize")), input, diagnostics)?.as_usize()
+    }
+
+    fn const_value(&mut self, name: &String, diagnostics: &mut DiagnosticBuilder) -> Result<ConstValue, EvalError> {
+        if let Some(result) = self.results.get(name) {
+            return result.clone().map_err(|_| EvalError::Reported);
+        }
+        if let Some(idx) = self.stack.iter().position(|other| other == name) {
+            return Err(EvalError::Cycle(self.stack[idx..].to_vec()));
+        }
+        let def = self.consts[name].clone();
+        let val = match &def.val {
+            AstNode::BinaryExpr(assign) => &assign.rhs,
+            val => val,
+        };
+        // the value of a const doesn't depend on the locals of the block it is used in
+        let outer_locals = std::mem::take(&mut self.locals);
+        self.stack.push(name.clone());
+        let result = self.eval_node(val, Some(&def.ty), diagnostics);
+        self.stack.pop();
+        self.locals = outer_locals;
+
+        self.results.insert(name.clone(), result.as_ref().map(|val| val.clone()).map_err(|_| ()));
+        match result {
+            // the cycle gets reported once it's completed
+            Err(EvalError::Cycle(cycle)) if &cycle[0] != name => Err(EvalError::Cycle(cycle)),
+            Err(err) => {
+                // consts generated by macros don't have a span, they are shown as they would be written instead
+                let item = if self.source.contains(def.span) {
+                    self.source.line(def.span)
+                } else {
+                    (format!("const {}: {} = {};", name, def.ty.to_string(), val), Span::NONE)
+                };
+                self.report(err, item, diagnostics);
+                Err(EvalError::Reported)
+            }
+            Ok(val) => Ok(val),
+        }
+    }
+
+    fn eval_node(&mut self, node: &AstNode, ty: Option<&Ty>, diagnostics: &mut DiagnosticBuilder) -> Result<ConstValue, EvalError> {
+        self.steps += 1;
+        if self.steps > MAX_STEPS {
+            return Err(EvalError::Error {
+                msg: "constant evaluation is taking too long".to_string(),
+                at: node.to_string(),
+                span: node.span(),
+                notes: vec![format!("the evaluation exceeded the limit of {} steps", MAX_STEPS)],
+            });
+        }
+        match node {
+            AstNode::Number(number) => literal(node, number.val, ty),
+            AstNode::BoolLit(val) => Ok(ConstValue::Bool(*val)),
+            AstNode::StrLit(content) => Ok(ConstValue::Str(content.clone())),
+            AstNode::Ident(name) => {
+                if let Some(val) = self.locals.iter().rev().find_map(|locals| locals.get(name)) {
+                    return Ok(val.clone());
+                }
+                if self.consts.contains_key(name) {
+                    return self.const_value(name, diagnostics);
+                }
+                // unknown names were already reported by the type checker
+                Err(EvalError::Reported)
+            }
+            AstNode::BinaryExpr(expr) if expr.op.is_assign() => {
+                let name = match &expr.lhs {
+                    AstNode::Ident(name) if self.locals.iter().any(|locals| locals.contains_key(name)) => name.clone(),
+                    _ => return Err(error("only locals can be assigned to in constant expressions".to_string(), node)),
+                };
+                let val = match expr.op {
+                    BinOp::Eq => {
+                        let ty = self.locals.iter().rev().find_map(|locals| locals.get(&name)).and_then(|val| val.ty());
+                        self.eval_node(&expr.rhs, ty.as_ref(), diagnostics)?
+                    }
+                    op => {
+                        let op = match op {
+                            BinOp::AddEq => BinOp::Add,
+                            BinOp::SubEq => BinOp::Sub,
+                            BinOp::MulEq => BinOp::Mul,
+                            BinOp::DivEq => BinOp::Div,
+                            _ => return Err(error(format!("`{}` can't be evaluated at compile time", op.to_string()), node)),
+                        };
+                        self.eval_bin_op(node, op, &expr.lhs, &expr.rhs, None, diagnostics)?
+                    }
+                };
+                let locals = self.locals.iter_mut().rev().find(|locals| locals.contains_key(&name)).unwrap();
+                locals.insert(name, val);
+                Ok(ConstValue::Unit)
+            }
+            AstNode::BinaryExpr(expr) => self.eval_bin_op(node, expr.op, &expr.lhs, &expr.rhs, ty, diagnostics),
+            AstNode::CallExpr(call) => self.eval_call(node, call, diagnostics),
+            AstNode::Block(block) => self.eval_block(block, ty, diagnostics),
+            AstNode::StructConstructor(constructor) => {
+                let def = match self.structs.get(&constructor.name) {
+                    Some(def) => def.clone(),
+                    // unknown structs were already reported by the type checker
+                    None => return Err(EvalError::Reported),
+                };
+                let base = match &constructor.base {
+                    Some(base) => match self.eval_node(base, None, diagnostics)? {
+                        ConstValue::Struct { fields, .. } => fields,
+                        _ => return Err(EvalError::Reported),
+                    },
+                    None => vec![],
+                };
+                let mut fields = vec![];
+                for field in &*def.fields {
+                    let val = match constructor.fields.iter().find(|(name, _)| name == &field.name) {
+                        Some((_, val)) => self.eval_node(val, Some(&field.ty), diagnostics)?,
+                        None => match base.iter().find(|(name, _)| name == &field.name) {
+                            Some((_, val)) => val.clone(),
+                            // missing fields were already reported by the type checker
+                            None => return Err(EvalError::Reported),
+                        },
+                    };
+                    fields.push((field.name.clone(), val));
+                }
+                Ok(ConstValue::Struct {
+                    name: def.name.clone(),
+                    fields,
+                })
+            }
+            AstNode::ArrayInst(array) => {
+                let elem_ty = match ty.map(|ty| &ty.kind) {
+                    Some(TyKind::Array(array)) => Some(&array.ty),
+                    _ => None,
+                };
+                match array {
+                    ArrayInst::List(list) => {
+                        let mut vals = vec![];
+                        for val in &*list.vals {
+                            // the first element determines the type of the ones following it
+                            let ty = elem_ty.cloned().or_else(|| vals.first().and_then(ConstValue::ty));
+                            vals.push(self.eval_node(val, ty.as_ref(), diagnostics)?);
+                        }
+                        Ok(ConstValue::Array(vals))
+                    }
+                    ArrayInst::Short(short) => {
+                        let amount = match self.eval_node(&short.amount, Some(&named_ty("usize")), diagnostics)?.as_usize() {
+                            Some(amount) => amount,
+                            None => return Err(EvalError::Reported),
+                        };
+                        if amount > MAX_ARRAY_LEN {
+                            return Err(error(format!("arrays with more than {} elements can't be evaluated at compile time", MAX_ARRAY_LEN), node));
+                        }
+                        let val = self.eval_node(&short.val, elem_ty, diagnostics)?;
+                        Ok(ConstValue::Array(vec![val; amount]))
+                    }
+                }
+            }
+            AstNode::Closure(_) => Err(error("closures can't be evaluated at compile time".to_string(), node)),
+            // the expression was already reported while parsing
+            AstNode::Error => Err(EvalError::Reported),
+        }
+    }
+
+    fn eval_block(&mut self, block: &Block, ty: Option<&Ty>, diagnostics: &mut DiagnosticBuilder) -> Result<ConstValue, EvalError> {
+        self.locals.push(HashMap::new());
+        let result = (|| {
+            let mut ret = ConstValue::Unit;
+            for stmt in &*block.stmts {
+                ret = ConstValue::Unit;
+                match stmt {
+                    StmtKind::LocalAssign(LocalAssign::DecAssign(assign)) => {
+                        let val = self.eval_node(&assign.val.val, assign.ty.as_ref(), diagnostics)?;
+                        self.locals.last_mut().unwrap().insert(assign.val.name.clone(), val);
+                    }
+                    StmtKind::LocalAssign(LocalAssign::Assign(assign)) => {
+                        let ty = self.locals.iter().rev().find_map(|locals| locals.get(&assign.name)).and_then(|val| val.ty());
+                        let val = self.eval_node(&assign.val, ty.as_ref(), diagnostics)?;
+                        match self.locals.iter_mut().rev().find(|locals| locals.contains_key(&assign.name)) {
+                            Some(locals) => locals.insert(assign.name.clone(), val),
+                            None => return Err(error("only locals can be assigned to in constant expressions".to_string(), &assign.val)),
+                        };
+                    }
+                    StmtKind::Expr(expr) => ret = self.eval_node(expr, ty, diagnostics)?,
+                    StmtKind::Semi(expr) => {
+                        self.eval_node(expr, None, diagnostics)?;
+                    }
+                    StmtKind::Item(_) | StmtKind::Empty => {}
+                }
+            }
+            Ok(ret)
+        })();
+        self.locals.pop();
+        result
+    }
+
+    /// executes the call of a `const fn`, errors inside of it are reported at the call
+    fn eval_call(&mut self, node: &AstNode, call: &CallExprNode, diagnostics: &mut DiagnosticBuilder) -> Result<ConstValue, EvalError> {
+        let func = match self.fns.get(&call.callee) {
+            Some(func) => func.clone(),
+            // calls of locals and unknown functions
+            None => return Err(error(format!("cannot call `{}` in constants", call.callee), node)),
+        };
+        let why_runtime = match (func.modifiers.constness, self.inferred.get(&call.callee)) {
+            (Constness::Const, _) | (Constness::Undefined, Some(InferredConstness::Const)) => None,
+            (Constness::Rt, _) => Some(vec![format!("`{}` is declared as `runtime fn`", call.callee)]),
+            (Constness::Undefined, Some(InferredConstness::Runtime(notes))) => Some(notes.clone()),
+            (Constness::Undefined, None) => Some(vec![]),
+        };
+        if let Some(mut notes) = why_runtime {
+            notes.insert(0, "calls in constants are limited to constant functions".to_string());
+            return Err(EvalError::Error {
+                msg: format!("cannot call non-const fn `{}` in constants", call.callee),
+                at: node.to_string(),
+                span: node.span(),
+                notes,
+            });
+        }
+        if self.depth == MAX_CALL_DEPTH {
+            return Err(EvalError::Error {
+                msg: format!("reached the limit of {} nested calls while evaluating `{}`", MAX_CALL_DEPTH, call.callee),
+                at: node.to_string(),
+                span: node.span(),
+                notes: vec![format!("`{}` probably calls itself endlessly", call.callee)],
+            });
+        }
+        let mut args = HashMap::new();
+        for ((name, ty), arg) in func.header.args.iter().zip(call.args.iter()) {
+            args.insert(name.clone(), self.eval_node(arg, Some(ty), diagnostics)?);
+        }
+        // the body of the function can only see its own args
+        let outer_locals = std::mem::replace(&mut self.locals, vec![args]);
+        self.depth += 1;
+        let result = self.eval_block(&func.body, func.header.ret.as_ref(), diagnostics);
+        self.depth -= 1;
+        self.locals = outer_locals;
+        result.map_err(|err| match err {
+            // the error gets reported at the call, the expression inside of the function it occurred at is noted
+            EvalError::Error { msg, at, span, mut notes } if span != node.span() || at != node.to_string() => {
+                notes.insert(0, format!("inside `{}` at `{}`", call.callee, at));
+                EvalError::Error {
+                    msg,
+                    at: node.to_string(),
+                    span: node.span(),
+                    notes,
+                }
+            }
+            err => err,
+        })
+    }
+
+    fn eval_bin_op(&mut self, node: &AstNode, op: BinOp, lhs: &AstNode, rhs: &AstNode, ty: Option<&Ty>, diagnostics: &mut DiagnosticBuilder) -> Result<ConstValue, EvalError> {
+        if matches!(op, BinOp::AndAnd | BinOp::OrOr) {
+            let bool_ty = named_ty("bool");
+            let lhs = self.eval_node(lhs, Some(&bool_ty), diagnostics)?;
+            // the rhs only gets evaluated if it's needed
+            return match (op, lhs) {
+                (BinOp::AndAnd, ConstValue::Bool(false)) => Ok(ConstValue::Bool(false)),
+                (BinOp::OrOr, ConstValue::Bool(true)) => Ok(ConstValue::Bool(true)),
+                (_, ConstValue::Bool(_)) => self.eval_node(rhs, Some(&bool_ty), diagnostics),
+                _ => Err(EvalError::Reported),
+            };
+        }
+        // the operands of comparisons don't have to be of the type which is expected for the result
+        let ty = if op.is_comparison() { None } else { ty };
+        // literals take the type of the other operand
+        let (lhs, rhs) = if ty.is_none() && matches!(lhs, AstNode::Number(_)) && !matches!(rhs, AstNode::Number(_)) {
+            let rhs = self.eval_node(rhs, None, diagnostics)?;
+            (self.eval_node(lhs, rhs.ty().as_ref(), diagnostics)?, rhs)
+        } else {
+            let lhs = self.eval_node(lhs, ty, diagnostics)?;
+            let rhs_ty = lhs.ty();
+            (lhs, self.eval_node(rhs, rhs_ty.as_ref().or(ty), diagnostics)?)
+        };
+        if op.is_comparison() {
+            return compare(op, &lhs, &rhs)
+                .map(ConstValue::Bool)
+                .ok_or_else(|| error(format!("`{}` can't be applied to `{}` and `{}` at compile time", op.to_string(), lhs, rhs), node));
+        }
+        match (&lhs, &rhs) {
+            (ConstValue::Number(lhs_num), ConstValue::Number(rhs_num)) => arith(op, *lhs_num, *rhs_num).map(ConstValue::Number).map_err(|err| {
+                let msg = match err {
+                    ArithError::Overflow => format!("attempt to compute `{} {} {}`, which would overflow", lhs, op.to_string(), rhs),
+                    ArithError::DivByZero if op == BinOp::Mod => format!("attempt to calculate the remainder of `{}` with a divisor of zero", lhs),
+                    ArithError::DivByZero => format!("attempt to divide `{}` by zero", lhs),
+                    ArithError::Mismatch => format!("`{}` can't be applied to `{}` and `{}`", op.to_string(), lhs, rhs),
+                };
+                error(msg, node)
+            }),
+            _ => Err(error(format!("`{}` can't be applied to `{}` and `{}` at compile time", op.to_string(), lhs, rhs), node)),
+        }
+    }
+
+    /// reports `err` pointing at the expression it occurred at, cycles and errors in expressions without a span
+    /// (like the ones produced by macros) point at `item` instead, the source of the item they occurred in and its span
+    fn report(&self, err: EvalError, item: (String, Span), diagnostics: &mut DiagnosticBuilder) {
+        match err {
+            EvalError::Error { msg, span, notes, .. } => {
+                let (input, span) = if self.source.contains(span) {
+                    self.source.line(span)
+                } else {
+                    item
+                };
+                let mut diagnostic = diagnostics.diagnostic(input);
+                diagnostic.error_spanned(msg, span);
+                for note in notes {
+                    diagnostic.note(note);
+                }
+                diagnostic.build();
+            }
+            EvalError::Cycle(cycle) => {
+                let (input, span) = item;
+                let mut diagnostic = diagnostics.diagnostic(input);
+                diagnostic.error_spanned(format!("cycle detected when evaluating the const `{}`", cycle[0]), span);
+                for name in &cycle[1..] {
+                    diagnostic.note(format!("...which requires evaluating the const `{}`...", name));
+                }
+                diagnostic.note(format!("...which again requires evaluating the const `{}`, completing the cycle", cycle[0]));
+                diagnostic.build();
+            }
+            EvalError::Reported => {}
+        }
+    }
+
+}
+
+fn named_ty(name: &str) -> Ty {
+    Ty {
+        kind: TyKind::Owned(Box::new(OwnedTy {
+            name: name.to_string(),
+            generics: Box::new([]),
+        })),
+    }
+}
+
+fn number_ty_name(number: &NumberType) -> &'static str {
+    match number {
+        NumberType::F32(_) => "f32",
+        NumberType::F64(_) => "f64",
+        NumberType::U8(_) => "u8",
+        NumberType::U16(_) => "u16",
+        NumberType::U32(_) => "u32",
+        NumberType::U64(_) => "u64",
+        NumberType::U128(_) => "u128",
+        NumberType::I8(_) => "i8",
+        NumberType::I16(_) => "i16",
+        NumberType::I32(_) => "i32",
+        NumberType::I64(_) => "i64",
+        NumberType::I128(_) => "i128",
+        NumberType::USize(_) => "usize",
+        NumberType::ISize(_) => "isize",
+    }
+}
+
+/// converts the literal `number` to the type `ty` which is expected for it, integers default to `i32`
+fn literal(node: &AstNode, number: NumberType, ty: Option<&Ty>) -> Result<ConstValue, EvalError> {
+    let name = match ty.and_then(|ty| ty.kind.get_owned()) {
+        Some(owned) if owned.generics.is_empty() => owned.name.as_str(),
+        _ => match number {
+            NumberType::F32(_) | NumberType::F64(_) => "f64",
+            _ => "i32",
+        },
+    };
+    let out_of_range = || EvalError::Error {
+        msg: format!("literal out of range for `{}`", name),
+        at: node.to_string(),
+        span: node.span(),
+        notes: vec![format!("the literal `{}` does not fit into the type `{}`", number.to_string(), name)],
+    };
+    let val = match number {
+        NumberType::U128(val) => val,
+        NumberType::F64(val) => return Ok(ConstValue::Number(match name {
+            "f32" => NumberType::F32(val as f32),
+            _ => NumberType::F64(val),
+        })),
+        number => return Ok(ConstValue::Number(number)),
+    };
+    Ok(ConstValue::Number(match name {
+        "u8" => NumberType::U8(val.try_into().map_err(|_| out_of_range())?),
+        "u16" => NumberType::U16(val.try_into().map_err(|_| out_of_range())?),
+        "u32" => NumberType::U32(val.try_into().map_err(|_| out_of_range())?),
+        "u64" => NumberType::U64(val.try_into().map_err(|_| out_of_range())?),
+        "usize" => NumberType::USize(val.try_into().map_err(|_| out_of_range())?),
+        "u128" => NumberType::U128(val),
+        "i8" => NumberType::I8(val.try_into().map_err(|_| out_of_range())?),
+        "i16" => NumberType::I16(val.try_into().map_err(|_| out_of_range())?),
+        "i64" => NumberType::I64(val.try_into().map_err(|_| out_of_range())?),
+        "isize" => NumberType::ISize(val.try_into().map_err(|_| out_of_range())?),
+        "i128" => NumberType::I128(val.try_into().map_err(|_| out_of_range())?),
+        "f32" => NumberType::F32(val as f32),
+        "f64" => NumberType::F64(val as f64),
+        _ => NumberType::I32(val.try_into().map_err(|_| out_of_range())?),
+    }))
+}
+
+enum ArithError {
+    Overflow,
+    DivByZero,
+    // the operands are of different types
+    Mismatch,
+}
+
+macro_rules! checked_int_op {
+    ($op:expr, $lhs:expr, $rhs:expr, $variant:ident) => {
+        match $op {
+            BinOp::Div | BinOp::Mod if $rhs == 0 => Err(ArithError::DivByZero),
+            BinOp::Add => $lhs.checked_add($rhs).ok_or(ArithError::Overflow),
+            BinOp::Sub => $lhs.checked_sub($rhs).ok_or(ArithError::Overflow),
+            BinOp::Mul => $lhs.checked_mul($rhs).ok_or(ArithError::Overflow),
+            BinOp::Div => $lhs.checked_div($rhs).ok_or(ArithError::Overflow),
+            BinOp::Mod => $lhs.checked_rem($rhs).ok_or(ArithError::Overflow),
+            _ => Err(ArithError::Mismatch),
+        }.map(NumberType::$variant)
+    };
+}
+
+macro_rules! float_op {
+    ($op:expr, $lhs:expr, $rhs:expr, $variant:ident) => {
+        match $op {
+            BinOp::Add => Ok($lhs + $rhs),
+            BinOp::Sub => Ok($lhs - $rhs),
+            BinOp::Mul => Ok($lhs * $rhs),
+            BinOp::Div => Ok($lhs / $rhs),
+            BinOp::Mod => Ok($lhs % $rhs),
+            _ => Err(ArithError::Mismatch),
+        }.map(NumberType::$variant)
+    };
+}
+
+/// applies the arithmetic operator `op` to two numbers of the same type
+fn arith(op: BinOp, lhs: NumberType, rhs: NumberType) -> Result<NumberType, ArithError> {
+    match (lhs, rhs) {
+        (NumberType::U8(lhs), NumberType::U8(rhs)) => checked_int_op!(op, lhs, rhs, U8),
+        (NumberType::U16(lhs), NumberType::U16(rhs)) => checked_int_op!(op, lhs, rhs, U16),
+        (NumberType::U32(lhs), NumberType::U32(rhs)) => checked_int_op!(op, lhs, rhs, U32),
+        (NumberType::U64(lhs), NumberType::U64(rhs)) => checked_int_op!(op, lhs, rhs, U64),
+        (NumberType::U128(lhs), NumberType::U128(rhs)) => checked_int_op!(op, lhs, rhs, U128),
+        (NumberType::I8(lhs), NumberType::I8(rhs)) => checked_int_op!(op, lhs, rhs, I8),
+        (NumberType::I16(lhs), NumberType::I16(rhs)) => checked_int_op!(op, lhs, rhs, I16),
+        (NumberType::I32(lhs), NumberType::I32(rhs)) => checked_int_op!(op, lhs, rhs, I32),
+        (NumberType::I64(lhs), NumberType::I64(rhs)) => checked_int_op!(op, lhs, rhs, I64),
+        (NumberType::I128(lhs), NumberType::I128(rhs)) => checked_int_op!(op, lhs, rhs, I128),
+        (NumberType::USize(lhs), NumberType::USize(rhs)) => checked_int_op!(op, lhs, rhs, USize),
+        (NumberType::ISize(lhs), NumberType::ISize(rhs)) => checked_int_op!(op, lhs, rhs, ISize),
+        (NumberType::F32(lhs), NumberType::F32(rhs)) => float_op!(op, lhs, rhs, F32),
+        (NumberType::F64(lhs), NumberType::F64(rhs)) => float_op!(op, lhs, rhs, F64),
+        _ => Err(ArithError::Mismatch),
+    }
+}
+
+/// applies the comparison `op` to two values of the same type, structs and arrays can only be compared for equality
+fn compare(op: BinOp, lhs: &ConstValue, rhs: &ConstValue) -> Option<bool> {
+    let ordering = match (lhs, rhs) {
+        (ConstValue::Number(lhs_num), ConstValue::Number(rhs_num)) => cmp_numbers(lhs_num, rhs_num),
+        (ConstValue::Bool(lhs), ConstValue::Bool(rhs)) => lhs.partial_cmp(rhs),
+        (ConstValue::Str(lhs), ConstValue::Str(rhs)) => lhs.partial_cmp(rhs),
+        _ => match op {
+            BinOp::EqEq => return Some(lhs == rhs),
+            BinOp::NEq => return Some(lhs != rhs),
+            _ => return None,
+        },
+    };
+    let ordering = ordering?;
+    Some(match op {
+        BinOp::EqEq => ordering.is_eq(),
+        BinOp::NEq => ordering.is_ne(),
+        BinOp::Lt => ordering.is_lt(),
+        BinOp::Gt => ordering.is_gt(),
+        BinOp::Le => ordering.is_le(),
+        BinOp::Ge => ordering.is_ge(),
+        _ => return None,
+    })
+}
+
+/// orders two numbers of the same type, `None` if their types differ or one of them is `NaN`
+fn cmp_numbers(lhs: &NumberType, rhs: &NumberType) -> Option<std::cmp::Ordering> {
+    match (lhs, rhs) {
+        (NumberType::F32(lhs), NumberType::F32(rhs)) => lhs.partial_cmp(rhs),
+        (NumberType::F64(lhs), NumberType::F64(rhs)) => lhs.partial_cmp(rhs),
+        (NumberType::U8(lhs), NumberType::U8(rhs)) => lhs.partial_cmp(rhs),
+        (NumberType::U16(lhs), NumberType::U16(rhs)) => lhs.partial_cmp(rhs),
+        (NumberType::U32(lhs), NumberType::U32(rhs)) => lhs.partial_cmp(rhs),
+        (NumberType::U64(lhs), NumberType::U64(rhs)) => lhs.partial_cmp(rhs),
+        (NumberType::U128(lhs), NumberType::U128(rhs)) => lhs.partial_cmp(rhs),
+        (NumberType::I8(lhs), NumberType::I8(rhs)) => lhs.partial_cmp(rhs),
+        (NumberType::I16(lhs), NumberType::I16(rhs)) => lhs.partial_cmp(rhs),
+        (NumberType::I32(lhs), NumberType::I32(rhs)) => lhs.partial_cmp(rhs),
+        (NumberType::I64(lhs), NumberType::I64(rhs)) => lhs.partial_cmp(rhs),
+        (NumberType::I128(lhs), NumberType::I128(rhs)) => lhs.partial_cmp(rhs),
+        (NumberType::USize(lhs), NumberType::USize(rhs)) => lhs.partial_cmp(rhs),
+        (NumberType::ISize(lhs), NumberType::ISize(rhs)) => lhs.partial_cmp(rhs),
+        _ => None,
+    }
+}
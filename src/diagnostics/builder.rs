@@ -69,6 +69,12 @@ impl<'sup> DiagnosticSubBuilder<'sup> {
         self
     }
 
+    /// adds a note pointing at code other than the diagnostic's input, e.g. at the definition of a macro
+    pub fn note_spanned(&mut self, note: String, input: String, span: Span) -> &mut Self {
+        self.items.push(DiagnosticItem::SpannedNote(note, input, span));
+        self
+    }
+
     pub fn suggest_spanned(&mut self, suggestion: String, span: Span) -> &mut Self {
         self.items
             .push(DiagnosticItem::Suggestion(suggestion, span));
@@ -162,9 +168,33 @@ pub(crate) enum DiagnosticItem {
     Warn(String, Span),
     Suggestion(String, Span),
     Note(String),
+    // a note together with the code it points at
+    SpannedNote(String, String, Span),
 }
 
 impl DiagnosticItem {
+    fn message(&self) -> String {
+        match self {
+            DiagnosticItem::Error(str, _) => format!("error: {}", str),
+            DiagnosticItem::Warn(str, _) => format!("warning: {}", str),
+            DiagnosticItem::Suggestion(str, _) => format!("help: {}", str),
+            DiagnosticItem::Note(str) | DiagnosticItem::SpannedNote(str, _, _) => format!("note: {}", str),
+        }
+    }
+
+    /// the code in `input` (or in its own input) the item points at (if any)
+    fn spanned_code(&self, input: &str) -> Option<String> {
+        let (input, span) = match self {
+            DiagnosticItem::Error(_, span) | DiagnosticItem::Warn(_, span) | DiagnosticItem::Suggestion(_, span) => (input, span),
+            DiagnosticItem::SpannedNote(_, input, span) => (input.as_str(), span),
+            DiagnosticItem::Note(_) => return None,
+        };
+        if span.is_none() {
+            return None;
+        }
+        Some(input.chars().skip(span.start).take(span.end - span.start).collect())
+    }
+
     pub fn to_string(&self, input: &String) -> String {
         match self {
             DiagnosticItem::Error(str, span) => {
@@ -211,6 +241,15 @@ impl DiagnosticItem {
                 }
             }
             DiagnosticItem::Note(str) => String::from("note: ") + str + "\n",
+            DiagnosticItem::SpannedNote(str, input, span) => {
+                input.to_owned()
+                    + "\n"
+                    + &DiagnosticSubBuilder::build_span_string(span)
+                    + "\n"
+                    + "note: "
+                    + str
+                    + "\n"
+            }
         }
     }
 }
@@ -233,6 +272,29 @@ impl DiagnosticBuilder {
     pub fn is_empty(&self) -> bool {
         self.parts.is_empty()
     }
+
+    /// the messages of all diagnostics in the order they were emitted, without their inputs,
+    /// e.g. ["error: unknown type `Strng`", "note: ..."]
+    pub fn messages(&self) -> Vec<String> {
+        self.parts.iter().flat_map(|part| part.items.iter().map(DiagnosticItem::message)).collect()
+    }
+
+    /// the messages of all diagnostics together with the code they point at (if any),
+    /// e.g. [("error: cannot find macro `foo` in this scope", Some("foo!"))]
+    pub fn spanned_messages(&self) -> Vec<(String, Option<String>)> {
+        self.parts.iter()
+            .flat_map(|part| part.items.iter().map(|item| (item.message(), item.spanned_code(&part.input))))
+            .collect()
+    }
+}
+
+impl Display for DiagnosticBuilder {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        for part in self.parts.iter() {
+            Display::fmt(part, f)?;
+        }
+        Ok(())
+    }
 }
 
 #[macro_export]
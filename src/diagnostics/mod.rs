@@ -1,2 +1,4 @@
 pub mod builder;
+pub mod source;
 pub mod span;
+pub mod suggest;
@@ -0,0 +1,38 @@
+use crate::diagnostics::span::Span;
+
+/// a source file diagnostics can point into, the spans of its tokens are char offsets into `src`
+#[derive(Debug, Clone, Default)]
+pub struct SourceFile {
+    path: String,
+    src: String,
+}
+
+impl SourceFile {
+
+    pub fn new(path: String, src: String) -> Self {
+        Self { path, src }
+    }
+
+    /// the location `span` starts at, e.g. `tests/impl_select.tf:12`
+    pub fn location(&self, span: Span) -> String {
+        let line = self.src.chars().take(span.start).filter(|chr| *chr == '\n').count() + 1;
+        format!("{}:{}", self.path, line)
+    }
+
+    /// the line `span` starts in together with `span` relative to the start of that line,
+    /// spans covering multiple lines are cut off at the end of their first line
+    pub fn line(&self, span: Span) -> (String, Span) {
+        let before = self.src.chars().take(span.start).collect::<String>();
+        let line_start = before.rfind('\n').map_or(0, |idx| before[..idx].chars().count() + 1);
+        let line = self.src.chars().skip(line_start).take_while(|chr| *chr != '\n').collect::<String>();
+        let len = line.chars().count();
+        let start = span.start - line_start;
+        (line, Span::multi_token(start, (span.end - line_start).min(len).max(start)))
+    }
+
+    /// whether `span` points into this file, the spans of generated code (like derived impls) don't
+    pub fn contains(&self, span: Span) -> bool {
+        !span.is_none() && span.end <= self.src.chars().count()
+    }
+
+}
@@ -1,7 +1,7 @@
 use std::error::Error;
 use std::fmt::{Arguments, Debug, Display, Formatter, Write};
 
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub struct Span {
     pub start: usize,
     pub end: usize, // FIXME: should this be inclusive or exclusive?
@@ -0,0 +1,37 @@
+/// the number of single character insertions, deletions, substitutions and swaps of adjacent characters
+/// needed to turn `left` into `right`
+/// see: https://en.wikipedia.org/wiki/Damerau%E2%80%93Levenshtein_distance#Optimal_string_alignment_distance
+pub fn edit_distance(left: &str, right: &str) -> usize {
+    let left = left.chars().collect::<Vec<_>>();
+    let right = right.chars().collect::<Vec<_>>();
+    // `dist[i][j]` is the distance between the first `i` chars of `left` and the first `j` chars of `right`
+    let mut dist = vec![vec![0; right.len() + 1]; left.len() + 1];
+    for (i, row) in dist.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    for (j, cell) in dist[0].iter_mut().enumerate() {
+        *cell = j;
+    }
+    for i in 1..=left.len() {
+        for j in 1..=right.len() {
+            let cost = if left[i - 1] == right[j - 1] { 0 } else { 1 };
+            dist[i][j] = (dist[i - 1][j] + 1).min(dist[i][j - 1] + 1).min(dist[i - 1][j - 1] + cost);
+            if i > 1 && j > 1 && left[i - 1] == right[j - 2] && left[i - 2] == right[j - 1] {
+                dist[i][j] = dist[i][j].min(dist[i - 2][j - 2] + 1);
+            }
+        }
+    }
+    dist[left.len()][right.len()]
+}
+
+/// finds the candidate which is most similar to `name`, candidates which are too different
+/// to be a plausible typo (more than a third of the name's length) are ignored
+pub fn find_best_match<'a>(name: &str, candidates: impl IntoIterator<Item = &'a str>) -> Option<&'a str> {
+    let max_distance = (name.chars().count() / 3).max(1);
+    candidates.into_iter()
+        .filter(|candidate| *candidate != name)
+        .map(|candidate| (edit_distance(&name.to_lowercase(), &candidate.to_lowercase()), candidate))
+        .filter(|(distance, _)| *distance <= max_distance)
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, candidate)| candidate)
+}
@@ -61,8 +61,9 @@ pub fn lex(input: String) -> Result<Vec<Token>, DiagnosticBuilder> {
             ' ' => {}
             '"' => {
                 let (buffer, new_cursor) = read_into_buffer(&input, cursor + 1, |x| x != '"');
-                curr_token = Some(Token::StrLit(Span::multi_token(cursor, new_cursor), buffer));
-                cursor = new_cursor - 1;
+                curr_token = Some(Token::StrLit(Span::multi_token(cursor, new_cursor + 1), buffer));
+                // skip the closing `"` as well
+                cursor = new_cursor;
             }
             '0'..='9' => {
                 let (buffer, new_cursor) =
@@ -88,28 +89,68 @@ pub fn lex(input: String) -> Result<Vec<Token>, DiagnosticBuilder> {
             '}' => curr_token = Some(Token::ClosedCurly(FixedTokenSpan::new(cursor))),
             '[' => curr_token = Some(Token::OpenBracket(FixedTokenSpan::new(cursor))),
             ']' => curr_token = Some(Token::ClosedBracket(FixedTokenSpan::new(cursor))),
-            '<' => curr_token = Some(Token::OpenAngle(FixedTokenSpan::new(cursor))),
-            '>' => curr_token = Some(Token::ClosedAngle(FixedTokenSpan::new(cursor))),
-            ':' => curr_token = Some(Token::Colon(FixedTokenSpan::new(cursor))),
+            '<' => {
+                if input.get(cursor + 1) == Some(&'=') {
+                    curr_token = Some(Token::BinOp(Span::multi_token(cursor, cursor + 1), BinOp::Le));
+                    cursor += 1;
+                } else {
+                    curr_token = Some(Token::OpenAngle(FixedTokenSpan::new(cursor)));
+                }
+            }
+            '>' => {
+                if input.get(cursor + 1) == Some(&'=') {
+                    curr_token = Some(Token::BinOp(Span::multi_token(cursor, cursor + 1), BinOp::Ge));
+                    cursor += 1;
+                } else {
+                    curr_token = Some(Token::ClosedAngle(FixedTokenSpan::new(cursor)));
+                }
+            }
+            ':' => {
+                if input.get(cursor + 1) == Some(&':') {
+                    curr_token = Some(Token::PathSep(FixedTokenSpan::new(cursor)));
+                    cursor += 1;
+                } else {
+                    curr_token = Some(Token::Colon(FixedTokenSpan::new(cursor)));
+                }
+            }
             ';' => curr_token = Some(Token::Semi(FixedTokenSpan::new(cursor))),
             ',' => curr_token = Some(Token::Comma(FixedTokenSpan::new(cursor))),
             '#' => curr_token = Some(Token::Hashtag(FixedTokenSpan::new(cursor))),
+            '!' => {
+                if input.get(cursor + 1) == Some(&'=') {
+                    curr_token = Some(Token::BinOp(Span::multi_token(cursor, cursor + 1), BinOp::NEq));
+                    cursor += 1;
+                } else {
+                    curr_token = Some(Token::Exclamation(FixedTokenSpan::new(cursor)));
+                }
+            }
+            '$' => curr_token = Some(Token::Dollar(FixedTokenSpan::new(cursor))),
             '\'' => curr_token = Some(Token::Apostrophe(FixedTokenSpan::new(cursor))),
             '?' => curr_token = Some(Token::Question(FixedTokenSpan::new(cursor))),
             '.' => curr_token = Some(Token::Dot(FixedTokenSpan::new(cursor))),
-            '=' => curr_token = Some(Token::BinOp(Span::single_token(cursor), BinOp::Eq)),
+            '=' => {
+                if input.get(cursor + 1) == Some(&'=') {
+                    curr_token = Some(Token::BinOp(Span::multi_token(cursor, cursor + 1), BinOp::EqEq));
+                    cursor += 1;
+                } else {
+                    curr_token = Some(Token::BinOp(Span::single_token(cursor), BinOp::Eq));
+                }
+            }
+            '%' => curr_token = Some(Token::BinOp(Span::single_token(cursor), BinOp::Mod)),
             '&' => match input[cursor + 1] {
                 '&' => {
                     curr_token = Some(Token::BinOp(
                         Span::multi_token(cursor, cursor + 1),
                         BinOp::AndAnd,
                     ));
+                    cursor += 1;
                 }
                 '=' => {
                     curr_token = Some(Token::BinOp(
                         Span::multi_token(cursor, cursor + 1),
                         BinOp::AndEq,
-                    ))
+                    ));
+                    cursor += 1;
                 }
                 _ => curr_token = Some(Token::And(FixedTokenSpan::new(cursor))),
             },
@@ -1,5 +1,6 @@
 use crate::diagnostics::span::{FixedTokenSpan, Span};
 use crate::parser::keyword::Keyword;
+use std::fmt::{Display, Formatter};
 
 #[derive(PartialEq, Copy, Clone, Debug)]
 pub enum TokenType {
@@ -16,11 +17,14 @@ pub enum TokenType {
     OpenBracket,   // [
     ClosedBracket, // ]
     Colon,         // :
+    PathSep,       // ::
     Semi,          // ;
     Apostrophe,    // '
     OpenAngle,     // <
     ClosedAngle,   // >
     Hashtag,       // #
+    Exclamation,   // !
+    Dollar,        // $
     Star,          // *
     Dot,           // .
     Question,      // ?
@@ -48,11 +52,14 @@ pub enum Token {
     OpenBracket(FixedTokenSpan),   // [
     ClosedBracket(FixedTokenSpan), // ]
     Colon(FixedTokenSpan),         // :
+    PathSep(FixedTokenSpan<2>),    // ::
     Semi(FixedTokenSpan),          // ;
     Apostrophe(FixedTokenSpan),    // '
     OpenAngle(FixedTokenSpan),     // <
     ClosedAngle(FixedTokenSpan),   // >
     Hashtag(FixedTokenSpan),       // #
+    Exclamation(FixedTokenSpan),   // !
+    Dollar(FixedTokenSpan),        // $
     Star(FixedTokenSpan),          // *
     Dot(FixedTokenSpan),           // .
     Question(FixedTokenSpan),      // ?
@@ -80,12 +87,15 @@ impl Token {
             Token::OpenBracket(sp) => sp.to_unfixed_span(),
             Token::ClosedBracket(sp) => sp.to_unfixed_span(),
             Token::Colon(sp) => sp.to_unfixed_span(),
+            Token::PathSep(sp) => sp.to_unfixed_span(),
             Token::Semi(sp) => sp.to_unfixed_span(),
             Token::Invalid(sp, _) => sp.to_unfixed_span(),
             Token::Apostrophe(sp) => sp.to_unfixed_span(),
             Token::OpenAngle(sp) => sp.to_unfixed_span(),
             Token::ClosedAngle(sp) => sp.to_unfixed_span(),
             Token::Hashtag(sp) => sp.to_unfixed_span(),
+            Token::Exclamation(sp) => sp.to_unfixed_span(),
+            Token::Dollar(sp) => sp.to_unfixed_span(),
             Token::Star(sp) => sp.to_unfixed_span(),
             Token::Dot(sp) => sp.to_unfixed_span(),
             Token::Question(sp) => sp.to_unfixed_span(),
@@ -112,11 +122,14 @@ impl Token {
             Token::OpenBracket(_) => TokenType::OpenBracket,
             Token::ClosedBracket(_) => TokenType::ClosedBracket,
             Token::Colon(_) => TokenType::Colon,
+            Token::PathSep(_) => TokenType::PathSep,
             Token::Semi(_) => TokenType::Semi,
             Token::Apostrophe(_) => TokenType::Apostrophe,
             Token::OpenAngle(_) => TokenType::OpenAngle,
             Token::ClosedAngle(_) => TokenType::ClosedAngle,
             Token::Hashtag(_) => TokenType::Hashtag,
+            Token::Exclamation(_) => TokenType::Exclamation,
+            Token::Dollar(_) => TokenType::Dollar,
             Token::Star(_) => TokenType::Star,
             Token::Dot(_) => TokenType::Dot,
             Token::Question(_) => TokenType::Question,
@@ -130,6 +143,44 @@ impl Token {
     }
 }
 
+// the token as it is written in the source
+impl Display for Token {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Token::Ident(_, name) => f.write_str(name),
+            Token::Keyword(_, kw) => f.write_str(kw.to_string()),
+            Token::BinOp(_, op) => f.write_str(op.to_string()),
+            Token::StrLit(_, content) => write!(f, "\"{}\"", content),
+            Token::NumLit(_, num) => f.write_str(num),
+            Token::Comma(_) => f.write_str(","),
+            Token::OpenParen(_) => f.write_str("("),
+            Token::ClosedParen(_) => f.write_str(")"),
+            Token::OpenCurly(_) => f.write_str("{"),
+            Token::ClosedCurly(_) => f.write_str("}"),
+            Token::OpenBracket(_) => f.write_str("["),
+            Token::ClosedBracket(_) => f.write_str("]"),
+            Token::Colon(_) => f.write_str(":"),
+            Token::PathSep(_) => f.write_str("::"),
+            Token::Semi(_) => f.write_str(";"),
+            Token::Apostrophe(_) => f.write_str("'"),
+            Token::OpenAngle(_) => f.write_str("<"),
+            Token::ClosedAngle(_) => f.write_str(">"),
+            Token::Hashtag(_) => f.write_str("#"),
+            Token::Exclamation(_) => f.write_str("!"),
+            Token::Dollar(_) => f.write_str("$"),
+            Token::Star(_) => f.write_str("*"),
+            Token::Dot(_) => f.write_str("."),
+            Token::Question(_) => f.write_str("?"),
+            Token::Arrow(_) => f.write_str("->"),
+            Token::And(_) => f.write_str("&"),
+            Token::Or(_) => f.write_str("|"),
+            Token::Comment(_, comment) => f.write_str(comment),
+            Token::EOF(_) => Ok(()),
+            Token::Invalid(_, chr) => write!(f, "{}", chr),
+        }
+    }
+}
+
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
 pub enum BinOp {
     Add,
@@ -146,8 +197,12 @@ pub enum BinOp {
     AndAnd,
     OrOr,
     Eq,
-    // EqEq, // ==
-    // NEq,  // !=
+    EqEq, // ==
+    NEq,  // !=
+    Lt,   // <
+    Gt,   // >
+    Le,   // <=
+    Ge,   // >=
     //?ModEq, // %=
 }
 
@@ -168,8 +223,47 @@ impl BinOp {
             BinOp::AndEq => 1,
             BinOp::OrEq => 1,
             BinOp::Eq => 1,
-            // BinOp::EqEq => 2,
-            // BinOp::NEq => 2,
+            BinOp::EqEq => 3,
+            BinOp::NEq => 3,
+            BinOp::Lt => 3,
+            BinOp::Gt => 3,
+            BinOp::Le => 3,
+            BinOp::Ge => 3,
         }
     }
+
+    pub fn to_string(self) -> &'static str {
+        match self {
+            BinOp::Add => "+",
+            BinOp::Sub => "-",
+            BinOp::Mul => "*",
+            BinOp::Div => "/",
+            BinOp::Mod => "%",
+            BinOp::AddEq => "+=",
+            BinOp::SubEq => "-=",
+            BinOp::MulEq => "*=",
+            BinOp::DivEq => "/=",
+            BinOp::AndEq => "&=",
+            BinOp::OrEq => "|=",
+            BinOp::AndAnd => "&&",
+            BinOp::OrOr => "||",
+            BinOp::Eq => "=",
+            BinOp::EqEq => "==",
+            BinOp::NEq => "!=",
+            BinOp::Lt => "<",
+            BinOp::Gt => ">",
+            BinOp::Le => "<=",
+            BinOp::Ge => ">=",
+        }
+    }
+
+    /// whether the operator assigns to its lhs, e.g. `=` or `+=`
+    pub fn is_assign(&self) -> bool {
+        matches!(self, BinOp::Eq | BinOp::AddEq | BinOp::SubEq | BinOp::MulEq | BinOp::DivEq | BinOp::AndEq | BinOp::OrEq)
+    }
+
+    /// whether the operator compares its operands and results in a `bool`
+    pub fn is_comparison(&self) -> bool {
+        matches!(self, BinOp::EqEq | BinOp::NEq | BinOp::Lt | BinOp::Gt | BinOp::Le | BinOp::Ge)
+    }
 }
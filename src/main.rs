@@ -6,12 +6,14 @@ use crate::parser::parser::Parser;
 use crate::parser::token_stream::TokenStream;
 use std::fs::File;
 use std::io::{Error, ErrorKind, Write};
-use std::{env, fs, thread};
+use std::{env, fs, process, thread};
 use std::sync::Arc;
 use crate::datastructures::concurrent_vec::InsertOnlyConcVec;
 use crate::diagnostics::builder::DiagnosticBuilder;
+use crate::diagnostics::source::SourceFile;
 use crate::lexer::lex;
 use crate::parser::ast::{Crate, ItemKind, StmtKind};
+use crate::parser::cfg::CfgOptions;
 use crate::tyck::{DEFAULT_PATH, Ty, tyck_item};
 
 mod diagnostics;
@@ -23,33 +25,66 @@ mod datastructures;
 mod const_eval;
 
 fn main() {
+    let cfg = match parse_cfg_args(env::args().skip(1)) {
+        Ok(cfg) => cfg,
+        Err((arg, error)) => {
+            let mut diagnostics = DiagnosticBuilder::new();
+            let mut diagnostic = diagnostics.diagnostic(arg);
+            diagnostic.error(error);
+            diagnostic.build();
+            eprint!("{}", diagnostics);
+            process::exit(1);
+        }
+    };
     let path = env::current_dir().unwrap();
     println!("The current directory is {}", path.display());
     let mut input = input("Please insert a path to a source file: ".to_owned()).unwrap();
-    let file = fs::read_to_string(input).unwrap();
+    let file = fs::read_to_string(&input).unwrap();
     println!("pre-lex");
-    let lexed = lexer::lex(file).unwrap();
+    let lexed = lexer::lex(file.clone()).unwrap();
     let tokens = lexed.len();
     println!("lexed!");
     println!("{:?}", lexed);
     let mut token_stream = TokenStream::new(lexed);
     let mut parser = Parser::new(token_stream);
     println!("parsing...");
-    let krate = parser.parse_crate().unwrap();
+    let mut krate = parser.parse_crate().unwrap();
     println!("parsed!");
     println!("ast: {:?}", krate);
     println!("tokens: {}", tokens);
     println!("items: {}", krate.items.len());
-    let mut tyck_ctx = krate.build_ctx();
     let mut diagnostics = DiagnosticBuilder::new();
+    krate.strip_cfg(&cfg, &mut diagnostics);
+    krate.check_attrs(&mut diagnostics);
+    krate.expand_derives(&mut diagnostics);
+    let mut tyck_ctx = krate.build_ctx(SourceFile::new(input, file));
     for item in &*krate.items {
         tyck_item(&mut tyck_ctx, item);
     }
+    print!("{}{}{}", parser.diagnostics(), diagnostics, tyck_ctx.diagnostics);
 }
 
 // https://hackernoon.com/lets-build-a-programming-language-2612349105c6
 // https://medium.com/hackernoon/compilers-and-interpreters-3e354a2e41cf
 
+/// collects the options passed like `--cfg test --cfg feature="x"`,
+/// errors are returned together with the argument they were found in
+fn parse_cfg_args(mut args: impl Iterator<Item = String>) -> Result<CfgOptions, (String, String)> {
+    let mut cfg = CfgOptions::new();
+    while let Some(arg) = args.next() {
+        let option = if arg == "--cfg" {
+            args.next().ok_or_else(|| (arg.clone(), "expected a cfg option after `--cfg`".to_string()))?
+        } else if let Some(option) = arg.strip_prefix("--cfg=") {
+            option.to_string()
+        } else {
+            let error = format!("unknown argument `{}`", arg);
+            return Err((arg, error));
+        };
+        cfg.parse_option(&option).map_err(|error| (option, error))?;
+    }
+    Ok(cfg)
+}
+
 fn input(text: String) -> std::io::Result<String> {
     print!("{}", text);
     std::io::stdout().flush()?; // because print! doesn't flush
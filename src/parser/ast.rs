@@ -1,43 +1,266 @@
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
 use std::hash::Hash;
+use std::mem;
 
+use crate::diagnostics::builder::DiagnosticBuilder;
+use crate::diagnostics::source::SourceFile;
 use crate::diagnostics::span::Span;
 use crate::lexer::token::BinOp;
-use crate::parser::attrs::{Constness, Mutability, Visibility};
-use crate::tyck::{Adt, DEFAULT_PATH, EMPTY, Environment, TyCtx};
+use crate::parser::attrs::{check_attrs, find_attr, recursion_limit, AttrTarget, Attribute, CaptureBy, Constness, Mutability, Visibility};
+use crate::parser::cfg::CfgOptions;
+use crate::parser::derive::expand_derives;
+use crate::tyck::TyCtx;
 
 // FIXME: interesting: https://en.wikipedia.org/wiki/Terminal_and_nonterminal_symbols
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct Crate {
+    pub(crate) attrs: Box<[Attribute]>, // #![attr]
     pub(crate) items: Box<[ItemKind]>,
 }
 
 impl Crate {
 
-    pub fn build_ctx(&self) -> TyCtx {
-        let mut ret = TyCtx {
-            env: Environment::new(),
-        };
+    /// builds the ctx of the crate which was parsed from `source`
+    pub fn build_ctx(&self, source: SourceFile) -> TyCtx {
+        let mut ret = TyCtx::empty();
+        ret.set_source(source);
+        // `#![allow(lint)]` applies to the whole crate
+        ret.allow_lints(&self.attrs);
+        if let Some(limit) = recursion_limit(&self.attrs) {
+            ret.traits.set_recursion_limit(limit);
+        }
+
+        // aliases and associated types have to be known before the types of any other items get resolved
+        // and user defined types have to be known before the items using them
+        let mut items = self.items.iter().collect::<Vec<_>>();
+        items.sort_by_key(|item| match item {
+            ItemKind::TyAlias(_) | ItemKind::StructImpl(_) => 0,
+            ItemKind::StructDef(_) => 1,
+            _ => 2,
+        });
 
-        // early resolution
+        // the lengths of arrays in the types of items may refer to consts which are defined further down
         for item in &*self.items {
+            match item {
+                ItemKind::ConstVal(val) => ret.consts.insert_const(val),
+                ItemKind::FunctionDef(func) => ret.consts.insert_fn(func),
+                ItemKind::StructDef(def) => ret.consts.insert_struct(def),
+                _ => {}
+            }
+        }
+        for item in items {
             ret.insert_item_glob(item);
         }
+        ret.check_coherence();
+        ret.infer_constness();
 
         ret
     }
 
+    /// removes all items, fields and statements whose `#[cfg(...)]` predicates don't hold
+    pub fn strip_cfg(&mut self, cfg: &CfgOptions, diagnostics: &mut DiagnosticBuilder) {
+        // `#![cfg(...)]` applies to the whole crate
+        if !cfg.is_enabled(&self.attrs, diagnostics) {
+            self.items = Box::new([]);
+            return;
+        }
+        strip_items(&mut self.items, cfg, diagnostics);
+    }
+
+    /// appends the impls generated by `#[derive(...)]` attributes to the crate's items
+    pub fn expand_derives(&mut self, diagnostics: &mut DiagnosticBuilder) {
+        let derived = expand_derives(&self.items, diagnostics);
+        let mut items = mem::take(&mut self.items).into_vec();
+        items.extend(derived);
+        self.items = items.into_boxed_slice();
+    }
+
+    /// checks the attributes of all items, fields and statements inside the crate
+    pub fn check_attrs(&self, diagnostics: &mut DiagnosticBuilder) {
+        check_attrs(&self.attrs, AttrTarget::Crate, diagnostics);
+        for item in &*self.items {
+            item.check_attrs(diagnostics);
+        }
+    }
+
+}
+
+/// removes all nodes whose `#[cfg(...)]` attributes are disabled
+fn retain_enabled<T>(nodes: &mut Box<[T]>, attrs: impl Fn(&T) -> &[Attribute], cfg: &CfgOptions, diagnostics: &mut DiagnosticBuilder) {
+    let mut retained = mem::take(nodes).into_vec();
+    retained.retain(|node| cfg.is_enabled(attrs(node), diagnostics));
+    *nodes = retained.into_boxed_slice();
+}
+
+fn strip_items(items: &mut Box<[ItemKind]>, cfg: &CfgOptions, diagnostics: &mut DiagnosticBuilder) {
+    retain_enabled(items, |item| item.attrs(), cfg, diagnostics);
+    for item in items.iter_mut() {
+        item.strip_cfg(cfg, diagnostics);
+    }
+}
+
+fn strip_block(block: &mut Block, cfg: &CfgOptions, diagnostics: &mut DiagnosticBuilder) {
+    retain_enabled(&mut block.stmts, |stmt| match stmt {
+        StmtKind::Item(item) => item.attrs(),
+        StmtKind::LocalAssign(LocalAssign::DecAssign(assign)) => &assign.attrs,
+        _ => &[],
+    }, cfg, diagnostics);
+    for stmt in block.stmts.iter_mut() {
+        match stmt {
+            StmtKind::Item(item) => item.strip_cfg(cfg, diagnostics),
+            StmtKind::LocalAssign(LocalAssign::DecAssign(assign)) => strip_node(&mut assign.val.val, cfg, diagnostics),
+            StmtKind::LocalAssign(LocalAssign::Assign(assign)) => strip_node(&mut assign.val, cfg, diagnostics),
+            StmtKind::Expr(expr) | StmtKind::Semi(expr) => strip_node(expr, cfg, diagnostics),
+            StmtKind::Empty => {}
+        }
+    }
+}
+
+fn strip_node(node: &mut AstNode, cfg: &CfgOptions, diagnostics: &mut DiagnosticBuilder) {
+    match node {
+        AstNode::Block(block) => strip_block(block, cfg, diagnostics),
+        AstNode::Closure(closure) => strip_node(&mut closure.body, cfg, diagnostics),
+        AstNode::BinaryExpr(expr) => {
+            strip_node(&mut expr.lhs, cfg, diagnostics);
+            strip_node(&mut expr.rhs, cfg, diagnostics);
+        }
+        AstNode::CallExpr(call) => {
+            for arg in call.args.iter_mut() {
+                strip_node(arg, cfg, diagnostics);
+            }
+        }
+        AstNode::StructConstructor(constructor) => {
+            for (_, val) in constructor.fields.iter_mut() {
+                strip_node(val, cfg, diagnostics);
+            }
+            if let Some(base) = &mut constructor.base {
+                strip_node(base, cfg, diagnostics);
+            }
+        }
+        AstNode::ArrayInst(ArrayInst::List(list)) => {
+            for val in list.vals.iter_mut() {
+                strip_node(val, cfg, diagnostics);
+            }
+        }
+        AstNode::ArrayInst(ArrayInst::Short(short)) => {
+            strip_node(&mut short.val, cfg, diagnostics);
+            strip_node(&mut short.amount, cfg, diagnostics);
+        }
+        AstNode::Number(_) | AstNode::StrLit(_) | AstNode::BoolLit(_) | AstNode::Ident(_) | AstNode::Error => {}
+    }
+}
+
+fn check_block_attrs(block: &Block, diagnostics: &mut DiagnosticBuilder) {
+    for stmt in &*block.stmts {
+        match stmt {
+            StmtKind::Item(item) => item.check_attrs(diagnostics),
+            StmtKind::LocalAssign(LocalAssign::DecAssign(assign)) => {
+                check_attrs(&assign.attrs, AttrTarget::Stmt, diagnostics);
+                check_node_attrs(&assign.val.val, diagnostics);
+            }
+            StmtKind::LocalAssign(LocalAssign::Assign(assign)) => check_node_attrs(&assign.val, diagnostics),
+            StmtKind::Expr(expr) | StmtKind::Semi(expr) => check_node_attrs(expr, diagnostics),
+            StmtKind::Empty => {}
+        }
+    }
+}
+
+fn check_node_attrs(node: &AstNode, diagnostics: &mut DiagnosticBuilder) {
+    match node {
+        AstNode::Block(block) => check_block_attrs(block, diagnostics),
+        AstNode::Closure(closure) => check_node_attrs(&closure.body, diagnostics),
+        _ => {}
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum AstNode {
-    Number(NumberType),
+    Number(NumberLit),
+    StrLit(String),
+    BoolLit(bool),
     Ident(String),
     BinaryExpr(Box<BinaryExprNode>),
     CallExpr(CallExprNode),
     Block(Block),
     StructConstructor(StructConstructor), // FIXME: should this be renamed to StructInit?
     ArrayInst(ArrayInst),
+    Closure(Box<ClosureNode>),
+    Error, // an expression which couldn't be parsed, it was already reported
+}
+
+impl AstNode {
+
+    /// the span of the node in the source, `NONE` for the kinds of nodes which don't keep their span (like identifiers)
+    /// and for nodes produced by macros
+    pub fn span(&self) -> Span {
+        match self {
+            AstNode::Number(number) => number.span,
+            AstNode::BinaryExpr(expr) => expr.span,
+            AstNode::CallExpr(call) => call.span,
+            AstNode::ArrayInst(ArrayInst::Short(short)) => short.span,
+            AstNode::Closure(closure) => closure.span,
+            _ => Span::NONE,
+        }
+    }
+
+}
+
+/// the node as it would be written in the source, blocks and closures are abbreviated as `{ .. }` and `|..| ..`
+impl Display for AstNode {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let raw = match self {
+            AstNode::Number(number) => number.val.to_string(),
+            AstNode::StrLit(content) => format!("\"{}\"", content),
+            AstNode::BoolLit(val) => val.to_string(),
+            AstNode::Ident(ident) => ident.clone(),
+            AstNode::BinaryExpr(expr) => {
+                // the operators are left associative, so the rhs needs parens for operators with the same precedence as well
+                let lhs = match &expr.lhs {
+                    AstNode::BinaryExpr(lhs) if lhs.op.precedence() < expr.op.precedence() => format!("({})", expr.lhs),
+                    lhs => lhs.to_string(),
+                };
+                let rhs = match &expr.rhs {
+                    AstNode::BinaryExpr(rhs) if rhs.op.precedence() <= expr.op.precedence() && !expr.op.is_assign() => format!("({})", expr.rhs),
+                    rhs => rhs.to_string(),
+                };
+                format!("{} {} {}", lhs, expr.op.to_string(), rhs)
+            }
+            AstNode::CallExpr(call) => {
+                let args = call.args.iter().map(|arg| arg.to_string()).collect::<Vec<_>>().join(", ");
+                if call.generics.is_empty() {
+                    format!("{}({})", call.callee, args)
+                } else {
+                    let generics = call.generics.iter().map(|generic| generic.to_string()).collect::<Vec<_>>().join(", ");
+                    format!("{}::<{}>({})", call.callee, generics, args)
+                }
+            }
+            AstNode::Block(_) => "{ .. }".to_string(),
+            AstNode::StructConstructor(constructor) => {
+                let mut fields = constructor.fields.iter().map(|(name, val)| format!("{}: {}", name, val)).collect::<Vec<_>>();
+                if let Some(base) = &constructor.base {
+                    fields.push(format!("..{}", base));
+                }
+                format!("{} {{ {} }}", constructor.name, fields.join(", "))
+            }
+            AstNode::ArrayInst(ArrayInst::List(list)) => format!("[{}]", list.vals.iter().map(|val| val.to_string()).collect::<Vec<_>>().join(", ")),
+            AstNode::ArrayInst(ArrayInst::Short(short)) => format!("[{}; {}]", short.val, short.amount),
+            AstNode::Closure(closure) => {
+                let args = closure.args.iter().map(|(name, ty)| match ty {
+                    Some(ty) => format!("{}: {}", name, ty.to_string()),
+                    None => name.clone(),
+                }).collect::<Vec<_>>();
+                let capture = if closure.capture == CaptureBy::Value { "move " } else { "" };
+                match &closure.ret {
+                    Some(ret) => format!("{}|{}| -> {} {}", capture, args.join(", "), ret.to_string(), closure.body),
+                    None => format!("{}|{}| {}", capture, args.join(", "), closure.body),
+                }
+            }
+            AstNode::Error => "{error}".to_string(),
+        };
+        f.write_str(&raw)
+    }
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -63,6 +286,89 @@ pub enum ItemKind {
     StructDef(StructDef),
     TraitDef(TraitDef),
     StructImpl(AdtImpl),
+    TyAlias(TyAlias),
+}
+
+impl ItemKind {
+
+    fn check_attrs(&self, diagnostics: &mut DiagnosticBuilder) {
+        match self {
+            ItemKind::StaticVal(val) => check_attrs(&val.attrs, AttrTarget::Static, diagnostics),
+            ItemKind::ConstVal(val) => check_attrs(&val.attrs, AttrTarget::Const, diagnostics),
+            ItemKind::FunctionDef(func) => {
+                check_attrs(&func.attrs, AttrTarget::Fn, diagnostics);
+                if find_attr(&func.attrs, "test").is_some() && !func.header.args.is_empty() {
+                    let mut diagnostic = diagnostics.diagnostic(func.header.name.clone());
+                    diagnostic.error("functions used as tests can't have any arguments".to_string());
+                    diagnostic.build();
+                }
+                check_block_attrs(&func.body, diagnostics);
+            }
+            ItemKind::StructDef(def) => {
+                check_attrs(&def.attrs, AttrTarget::Struct, diagnostics);
+                for field in &*def.fields {
+                    check_attrs(&field.attrs, AttrTarget::Field, diagnostics);
+                }
+            }
+            ItemKind::TraitDef(def) => {
+                check_attrs(&def.attrs, AttrTarget::Trait, diagnostics);
+                for method in &*def.methods {
+                    check_attrs(&method.attrs, AttrTarget::Fn, diagnostics);
+                    if let Some(default) = &method.default {
+                        check_block_attrs(default, diagnostics);
+                    }
+                }
+                for assoc_ty in &*def.assoc_tys {
+                    check_attrs(&assoc_ty.attrs, AttrTarget::TyAlias, diagnostics);
+                }
+                for assoc_const in &*def.assoc_consts {
+                    check_attrs(&assoc_const.attrs, AttrTarget::Const, diagnostics);
+                }
+            }
+            ItemKind::StructImpl(adt_impl) => {
+                check_attrs(&adt_impl.attrs, AttrTarget::Impl, diagnostics);
+                for item in &*adt_impl.methods {
+                    item.check_attrs(diagnostics);
+                }
+            }
+            ItemKind::TyAlias(alias) => check_attrs(&alias.attrs, AttrTarget::TyAlias, diagnostics),
+        }
+    }
+
+    /// removes the disabled fields, statements and nested items of this item
+    fn strip_cfg(&mut self, cfg: &CfgOptions, diagnostics: &mut DiagnosticBuilder) {
+        match self {
+            ItemKind::StaticVal(val) => strip_node(&mut val.val, cfg, diagnostics),
+            ItemKind::ConstVal(val) => strip_node(&mut val.val, cfg, diagnostics),
+            ItemKind::FunctionDef(func) => strip_block(&mut func.body, cfg, diagnostics),
+            ItemKind::StructDef(def) => retain_enabled(&mut def.fields, |field| &field.attrs, cfg, diagnostics),
+            ItemKind::TraitDef(def) => {
+                retain_enabled(&mut def.methods, |method| &method.attrs, cfg, diagnostics);
+                retain_enabled(&mut def.assoc_tys, |assoc_ty| &assoc_ty.attrs, cfg, diagnostics);
+                retain_enabled(&mut def.assoc_consts, |assoc_const| &assoc_const.attrs, cfg, diagnostics);
+                for method in def.methods.iter_mut() {
+                    if let Some(default) = &mut method.default {
+                        strip_block(default, cfg, diagnostics);
+                    }
+                }
+            }
+            ItemKind::StructImpl(adt_impl) => strip_items(&mut adt_impl.methods, cfg, diagnostics),
+            ItemKind::TyAlias(_) => {}
+        }
+    }
+
+    pub fn attrs(&self) -> &[Attribute] {
+        match self {
+            ItemKind::StaticVal(val) => &val.attrs,
+            ItemKind::ConstVal(val) => &val.attrs,
+            ItemKind::FunctionDef(func) => &func.attrs,
+            ItemKind::StructDef(def) => &def.attrs,
+            ItemKind::TraitDef(def) => &def.attrs,
+            ItemKind::StructImpl(adt_impl) => &adt_impl.attrs,
+            ItemKind::TyAlias(alias) => &alias.attrs,
+        }
+    }
+
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -78,21 +384,54 @@ pub struct BlockModifiers {}
 // every expression consists of some(or none) statements
 // and at most one expression at its end
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, Eq)]
 pub struct BinaryExprNode {
     pub(crate) lhs: AstNode,
     pub(crate) rhs: AstNode,
     pub(crate) op: BinOp,
+    pub(crate) span: Span,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+// the spans of nodes aren't part of their value, e.g. the `2` in `[u8; 2]` is the same wherever it's written
+impl PartialEq for BinaryExprNode {
+    fn eq(&self, other: &Self) -> bool {
+        self.lhs == other.lhs && self.rhs == other.rhs && self.op == other.op
+    }
+}
+
+#[derive(Debug, Clone, Eq)]
 pub struct CallExprNode {
     pub(crate) callee: String,
+    pub(crate) generics: Box<[TyOrConstVal]>, // the explicitly given generic args like in `f::<u32>()`
     pub(crate) args: Box<[AstNode]>,
+    pub(crate) span: Span,
+}
+
+impl PartialEq for CallExprNode {
+    fn eq(&self, other: &Self) -> bool {
+        self.callee == other.callee && self.generics == other.generics && self.args == other.args
+    }
+}
+
+/// This represents a closure like: |a, b| a + b or move |x: u32| -> u32 { x }
+#[derive(Debug, Clone, Eq)]
+pub struct ClosureNode {
+    pub(crate) capture: CaptureBy,
+    pub(crate) args: Box<[(String, Option<Ty>)]>, // name, type
+    pub(crate) ret: Option<Ty>,
+    pub(crate) body: AstNode,
+    pub(crate) span: Span,
+}
+
+impl PartialEq for ClosureNode {
+    fn eq(&self, other: &Self) -> bool {
+        self.capture == other.capture && self.args == other.args && self.ret == other.ret && self.body == other.body
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct StaticValNode {
+    pub(crate) attrs: Box<[Attribute]>,
     pub(crate) ty: Ty,
     // name is contained within val as its lhs field
     pub(crate) val: AstNode,
@@ -116,10 +455,13 @@ impl StaticValNode {
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct ConstValNode {
+    pub(crate) attrs: Box<[Attribute]>,
     pub(crate) ty: Ty,
     // name is contained within val as its lhs field
     pub(crate) val: AstNode,
     pub(crate) visibility: Option<Visibility>,
+    // the span of the whole item, `NONE` if it was generated by a macro
+    pub(crate) span: Span,
 }
 
 impl ConstValNode {
@@ -146,6 +488,7 @@ pub struct FunctionModifiers {
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct FunctionNode {
+    pub(crate) attrs: Box<[Attribute]>,
     pub(crate) modifiers: FunctionModifiers,
     pub(crate) header: FunctionHeader,
     pub(crate) body: Block,
@@ -157,6 +500,7 @@ pub struct FunctionHeader {
     pub(crate) generics: Box<[Generic]>,
     pub(crate) args: Box<[(String, Ty)]>, // name, type
     pub(crate) ret: Option<Ty>,           // type
+    pub(crate) where_clause: Box<[WherePredicate]>,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -174,6 +518,7 @@ pub struct LAssign {
 // LocalDeclareAssignment
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct LDecAssign {
+    pub(crate) attrs: Box<[Attribute]>,
     pub(crate) mutability: Option<Mutability>,
     pub(crate) ty: Option<Ty>,
     pub(crate) val: LAssign,
@@ -181,14 +526,17 @@ pub struct LDecAssign {
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct StructDef {
+    pub(crate) attrs: Box<[Attribute]>,
     pub(crate) visibility: Visibility,
     pub(crate) name: String,
     pub(crate) generics: Box<[Generic]>,
+    pub(crate) where_clause: Box<[WherePredicate]>,
     pub(crate) fields: Box<[StructFieldDef]>,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct StructFieldDef {
+    pub(crate) attrs: Box<[Attribute]>,
     pub(crate) visibility: Visibility,
     pub(crate) name: String,
     pub(crate) ty: Ty,
@@ -196,25 +544,70 @@ pub struct StructFieldDef {
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct TraitDef {
+    pub(crate) attrs: Box<[Attribute]>,
     pub(crate) visibility: Visibility,
     pub(crate) name: String,
     pub(crate) generics: Box<[Generic]>,
     pub(crate) req_sub_traits: Box<[Ty]>, // this may not be generic
-    pub(crate) methods: Box<[FunctionHeader]>,
+    pub(crate) where_clause: Box<[WherePredicate]>,
+    pub(crate) methods: Box<[TraitMethod]>,
+    pub(crate) assoc_tys: Box<[TraitAssocTy]>,
+    pub(crate) assoc_consts: Box<[TraitAssocConst]>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TraitMethod {
+    pub(crate) attrs: Box<[Attribute]>,
+    pub(crate) header: FunctionHeader,
+    pub(crate) default: Option<Block>,
+}
+
+/// This represents an associated type inside a trait like: type Item: Clone;
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TraitAssocTy {
+    pub(crate) attrs: Box<[Attribute]>,
+    pub(crate) name: String,
+    pub(crate) required_traits: Box<[Ty]>,
+}
+
+/// This represents an associated const inside a trait like: const N: usize;
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TraitAssocConst {
+    pub(crate) attrs: Box<[Attribute]>,
+    pub(crate) name: String,
+    pub(crate) ty: Ty,
+    pub(crate) default: Option<AstNode>,
+}
+
+/// This represents a type alias like: type Alias<T> = Wrapper<T>;
+/// (inside impl blocks this is used to provide associated types as well)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TyAlias {
+    pub(crate) attrs: Box<[Attribute]>,
+    pub(crate) visibility: Visibility,
+    pub(crate) name: String,
+    pub(crate) generics: Box<[Generic]>,
+    pub(crate) ty: Ty,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct AdtImpl {
+    pub(crate) attrs: Box<[Attribute]>,
     pub(crate) ty: Ty,
     pub(crate) impl_trait: Option<Ty>, // this may not be generic
+    pub(crate) negative: bool, // `impl !Send for Ty {}`
     pub(crate) generics: Box<[Generic]>,
+    pub(crate) where_clause: Box<[WherePredicate]>,
     pub(crate) methods: Box<[ItemKind]>,
+    // the span of the header, e.g. of `impl<T: Clone> Clone for Wrapper<T>`, `NONE` for derived impls
+    pub(crate) span: Span,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct StructConstructor {
     pub(crate) name: String,
-    pub(crate) fields: Box<[(String, AstNode)]>,
+    pub(crate) fields: Box<[(String, AstNode)]>, // the shorthand `Point { x }` is stored as `Point { x: x }`
+    pub(crate) base: Option<Box<AstNode>>, // the struct the remaining fields get taken from in `Point { x: 1, ..other }`
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -228,12 +621,27 @@ pub enum Generic {
 pub struct GenericType {
     pub(crate) name: String,
     pub(crate) required_traits: Box<[Ty]>,
+    pub(crate) required_lifetimes: Box<[Lifetime]>, // T: 'a
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct GenericLifetime {
     pub(crate) lt: Lifetime,
-    // pub(crate) constraints: Box<[Lifetime]>, // FIXME: implement this!
+    pub(crate) constraints: Box<[Lifetime]>, // 'b: 'a (the lifetimes `lt` has to outlive)
+}
+
+/// This represents a single predicate of a where clause like: where T: Clone + 'a, 'b: 'a
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WherePredicate {
+    Ty(TyWherePredicate),
+    Lifetime(GenericLifetime),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TyWherePredicate {
+    pub(crate) ty: Ty,
+    pub(crate) required_traits: Box<[Ty]>,
+    pub(crate) required_lifetimes: Box<[Lifetime]>,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -245,7 +653,7 @@ pub enum Lifetime {
 
 impl Lifetime {
 
-    fn to_string(&self) -> &str {
+    pub fn to_string(&self) -> &str {
         match self {
             Lifetime::Custom(val) => &val,
             Lifetime::Static => "static",
@@ -279,6 +687,49 @@ impl Ty {
         self.kind.to_string()
     }
 
+    /// replaces all generic params in `generics` with their corresponding types
+    pub fn substitute(&self, generics: &HashMap<String, Ty>) -> Ty {
+        let kind = match &self.kind {
+            TyKind::Ref(rf) => TyKind::Ref(Box::new(RefTy {
+                lt: rf.lt.clone(),
+                mutability: rf.mutability,
+                ty: Box::new(rf.ty.substitute(generics)),
+            })),
+            TyKind::Array(array) => TyKind::Array(Box::new(ArrayTy {
+                ty: array.ty.substitute(generics),
+                amount: array.amount.clone(),
+            })),
+            TyKind::Owned(owned) => {
+                if owned.generics.is_empty() {
+                    if let Some(ty) = generics.get(&owned.name) {
+                        return ty.clone();
+                    }
+                }
+                TyKind::Owned(Box::new(OwnedTy {
+                    name: owned.name.clone(),
+                    generics: owned.generics.iter().map(|generic| match generic {
+                        TyOrConstVal::Ty(ty) => TyOrConstVal::Ty(ty.substitute(generics)),
+                        TyOrConstVal::ConstVal(val) => TyOrConstVal::ConstVal(val.clone()),
+                        TyOrConstVal::Lifetime(lt) => TyOrConstVal::Lifetime(lt.clone()),
+                    }).collect(),
+                }))
+            }
+            TyKind::Func(func) => TyKind::Func(Box::new(FuncTy {
+                kind: func.kind,
+                args: func.args.iter().map(|arg| arg.substitute(generics)).collect(),
+                ret: func.ret.as_ref().map(|ret| ret.substitute(generics)),
+            })),
+            TyKind::Projection(projection) => TyKind::Projection(Box::new(ProjectionTy {
+                ty: projection.ty.substitute(generics),
+                name: projection.name.clone(),
+            })),
+            TyKind::Dyn(dyn_ty) => TyKind::Dyn(Box::new(DynTy {
+                tait: dyn_ty.tait.substitute(generics),
+            })),
+        };
+        Ty { kind }
+    }
+
     /*pub fn to_simple_string(&self) -> String {
         self.kind.to_simple_string()
     }*/
@@ -292,6 +743,9 @@ pub enum TyKind {
     Array(Box<ArrayTy>),
     // Ptr, // TODO
     Owned(Box<OwnedTy>),
+    Func(Box<FuncTy>),
+    Projection(Box<ProjectionTy>),
+    Dyn(Box<DynTy>),
 }
 
 impl TyKind {
@@ -301,6 +755,9 @@ impl TyKind {
             Self::Ref(rf) => rf.ty.kind.get_generics(),
             Self::Array(array) => array.ty.kind.get_generics(),
             Self::Owned(ty) => &ty.generics,
+            Self::Func(_) => &[],
+            Self::Projection(_) => &[],
+            Self::Dyn(_) => &[],
         }
     }
 
@@ -318,6 +775,9 @@ impl TyKind {
                 raw
             },
             Self::Owned(ty) => ty.name.clone(),
+            Self::Func(func) => func.to_string(),
+            Self::Projection(projection) => projection.to_string(),
+            Self::Dyn(dyn_ty) => format!("dyn {}", dyn_ty.tait.kind.simple_ty_name()),
         }
     }
 
@@ -326,6 +786,9 @@ impl TyKind {
             TyKind::Ref(_) => None,
             TyKind::Array(_) => None,
             TyKind::Owned(ty) => Some(&ty),
+            TyKind::Func(_) => None,
+            TyKind::Projection(_) => None,
+            TyKind::Dyn(_) => None,
         }
     }
 
@@ -334,6 +797,9 @@ impl TyKind {
             TyKind::Ref(ref_ty) => ref_ty.to_string(),
             TyKind::Array(array_ty) => array_ty.to_string(),
             TyKind::Owned(owned_ty) => owned_ty.to_string(),
+            TyKind::Func(func_ty) => func_ty.to_string(),
+            TyKind::Projection(projection_ty) => projection_ty.to_string(),
+            TyKind::Dyn(dyn_ty) => dyn_ty.to_string(),
         }
     }
 
@@ -357,7 +823,7 @@ pub struct RefTy {
 impl RefTy {
 
     fn to_string(&self) -> String {
-        format!("&{}{}{}", if let Some(lt) = &self.lt { format!(" '{}", lt.to_string()) } else { String::new() }, if self.mutability == Mutability::Mut { "mut " } else { "" }, self.ty.to_string())
+        format!("&{}{}{}", if let Some(lt) = &self.lt { format!("'{} ", lt.to_string()) } else { String::new() }, if self.mutability == Mutability::Mut { "mut " } else { "" }, self.ty.to_string())
     }
 
 }
@@ -395,12 +861,21 @@ pub struct ArrayTy {
 
 impl ArrayTy {
 
+    /// the length of the array if it's known, that is if it's written as a literal or was evaluated already
+    pub fn len(&self) -> Option<usize> {
+        match &self.amount {
+            Some(AstNode::Number(NumberLit { val: NumberType::U128(len), .. })) => usize::try_from(*len).ok(),
+            Some(AstNode::Number(NumberLit { val: NumberType::USize(len), .. })) => Some(*len),
+            _ => None,
+        }
+    }
+
     fn to_string(&self) -> String {
         let mut raw = "[".to_string();
         raw.push_str(&self.ty.to_string());
         if let Some(amount) = &self.amount {
             raw.push_str("; ");
-            // FIXME: print amount!
+            raw.push_str(&amount.to_string());
         }
         raw.push(']');
         raw
@@ -408,6 +883,91 @@ impl ArrayTy {
 
 }
 
+/// a trait object like `dyn Shape`, it can only be used behind a pointer like `&dyn Shape` or `Box<dyn Shape>`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DynTy {
+    pub(crate) tait: Ty,
+}
+
+impl Display for DynTy {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "dyn {}", self.tait.to_string())
+    }
+}
+
+/// This represents an associated type of a type like: T::Item
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProjectionTy {
+    pub(crate) ty: Ty,
+    pub(crate) name: String,
+}
+
+impl Display for ProjectionTy {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&format!("{}::{}", self.ty.to_string(), self.name))
+    }
+}
+
+/// This represents either a function pointer type like `fn(u32) -> u32`
+/// or one of the function traits like `FnMut(u32) -> u32` which closures implement
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FuncTy {
+    pub(crate) kind: FuncTyKind,
+    pub(crate) args: Box<[Ty]>,
+    pub(crate) ret: Option<Ty>,
+}
+
+impl Display for FuncTy {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let mut raw = self.kind.to_string().to_string();
+        raw.push('(');
+        for arg in &*self.args {
+            raw.push_str(&arg.to_string());
+            raw.push_str(", ");
+        }
+        if !self.args.is_empty() {
+            raw.pop();
+            raw.pop();
+        }
+        raw.push(')');
+        if let Some(ret) = &self.ret {
+            raw.push_str(" -> ");
+            raw.push_str(&ret.to_string());
+        }
+        f.write_str(&raw)
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum FuncTyKind {
+    Ptr, // fn
+    Fn,
+    FnMut,
+    FnOnce,
+}
+
+impl FuncTyKind {
+
+    pub fn from_trait_name(name: &str) -> Option<Self> {
+        match name {
+            "Fn" => Some(FuncTyKind::Fn),
+            "FnMut" => Some(FuncTyKind::FnMut),
+            "FnOnce" => Some(FuncTyKind::FnOnce),
+            _ => None,
+        }
+    }
+
+    pub fn to_string(self) -> &'static str {
+        match self {
+            FuncTyKind::Ptr => "fn",
+            FuncTyKind::Fn => "Fn",
+            FuncTyKind::FnMut => "FnMut",
+            FuncTyKind::FnOnce => "FnOnce",
+        }
+    }
+
+}
+
 /// This represents an array instantiation like: [6, 4, 2, 8, 3, 9] or [6; 20]
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum ArrayInst {
@@ -420,10 +980,17 @@ pub struct ArrayInstList {
     pub(crate) vals: Box<[AstNode]>,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, Eq)]
 pub struct ArrayInstShort {
     pub(crate) val: AstNode,
     pub(crate) amount: AstNode,
+    pub(crate) span: Span,
+}
+
+impl PartialEq for ArrayInstShort {
+    fn eq(&self, other: &Self) -> bool {
+        self.val == other.val && self.amount == other.amount
+    }
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -441,6 +1008,7 @@ pub struct Path {
 pub enum TyOrConstVal {
     Ty(Ty),
     ConstVal(AstNode),
+    Lifetime(Lifetime),
 }
 
 impl TyOrConstVal {
@@ -448,12 +1016,26 @@ impl TyOrConstVal {
     pub fn to_string(&self) -> String {
         match self {
             TyOrConstVal::Ty(ty) => ty.to_string(),
-            TyOrConstVal::ConstVal(ast_node) => todo!(),
+            TyOrConstVal::ConstVal(ast_node) => ast_node.to_string(),
+            TyOrConstVal::Lifetime(lt) => format!("'{}", lt.to_string()),
         }
     }
 
 }
 
+/// a number literal like `300`, lengths of arrays get replaced by their values once they were evaluated
+#[derive(Debug, Copy, Clone, Eq)]
+pub struct NumberLit {
+    pub(crate) val: NumberType,
+    pub(crate) span: Span,
+}
+
+impl PartialEq for NumberLit {
+    fn eq(&self, other: &Self) -> bool {
+        self.val == other.val
+    }
+}
+
 #[derive(Debug, Copy, Clone, PartialEq)]
 pub enum NumberType {
     F32(f32),
@@ -468,6 +1050,29 @@ pub enum NumberType {
     I32(i32),
     I64(i64),
     I128(i128),
+    USize(usize),
+    ISize(isize),
+}
+
+impl Display for NumberType {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            NumberType::F32(val) => write!(f, "{:?}", val),
+            NumberType::F64(val) => write!(f, "{:?}", val),
+            NumberType::U8(val) => write!(f, "{}", val),
+            NumberType::U16(val) => write!(f, "{}", val),
+            NumberType::U32(val) => write!(f, "{}", val),
+            NumberType::U64(val) => write!(f, "{}", val),
+            NumberType::U128(val) => write!(f, "{}", val),
+            NumberType::I8(val) => write!(f, "{}", val),
+            NumberType::I16(val) => write!(f, "{}", val),
+            NumberType::I32(val) => write!(f, "{}", val),
+            NumberType::I64(val) => write!(f, "{}", val),
+            NumberType::I128(val) => write!(f, "{}", val),
+            NumberType::USize(val) => write!(f, "{}", val),
+            NumberType::ISize(val) => write!(f, "{}", val),
+        }
+    }
 }
 
 /// FIXME: currently we assume equality for floats
@@ -1,3 +1,6 @@
+use crate::diagnostics::builder::DiagnosticBuilder;
+use std::fmt::{Display, Formatter};
+
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub enum Constness {
     Undefined,
@@ -23,3 +26,198 @@ pub enum Unsafety {
     Unsafe,
     Safe,
 }
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum CaptureBy {
+    Value, // move
+    Ref,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum AttrStyle {
+    Outer, // #[attr]
+    Inner, // #![attr]
+}
+
+/// An attribute like `#[inline]`, `#[allow(dead_code)]` or `#![cfg(feature = "x")]`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Attribute {
+    pub(crate) style: AttrStyle,
+    pub(crate) meta: MetaItem,
+}
+
+impl Attribute {
+
+    #[inline]
+    pub fn name(&self) -> &String {
+        self.meta.name()
+    }
+
+}
+
+impl Display for Attribute {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&format!("#{}[{}]", if self.style == AttrStyle::Inner { "!" } else { "" }, self.meta))
+    }
+}
+
+/// The contents of an attribute, e.g. `derive(Clone, Copy)` in `#[derive(Clone, Copy)]`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MetaItem {
+    Word(String),                   // inline
+    NameValue(String, String),      // feature = "x"
+    List(String, Box<[MetaItem]>),  // allow(dead_code, unused)
+}
+
+impl MetaItem {
+
+    pub fn name(&self) -> &String {
+        match self {
+            MetaItem::Word(name) => name,
+            MetaItem::NameValue(name, _) => name,
+            MetaItem::List(name, _) => name,
+        }
+    }
+
+}
+
+impl Display for MetaItem {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MetaItem::Word(name) => f.write_str(name),
+            MetaItem::NameValue(name, val) => f.write_str(&format!("{} = \"{}\"", name, val)),
+            MetaItem::List(name, items) => f.write_str(&format!("{}({})", name, items.iter().map(|item| item.to_string()).collect::<Vec<_>>().join(", "))),
+        }
+    }
+}
+
+/// The kind of node an attribute is attached to
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum AttrTarget {
+    Crate,
+    Fn,
+    Struct,
+    Field,
+    Trait,
+    Impl,
+    TyAlias,
+    Static,
+    Const,
+    Stmt,
+}
+
+impl AttrTarget {
+
+    pub fn to_string(self) -> &'static str {
+        match self {
+            AttrTarget::Crate => "crate",
+            AttrTarget::Fn => "function",
+            AttrTarget::Struct => "struct",
+            AttrTarget::Field => "field",
+            AttrTarget::Trait => "trait",
+            AttrTarget::Impl => "impl",
+            AttrTarget::TyAlias => "type alias",
+            AttrTarget::Static => "static",
+            AttrTarget::Const => "constant",
+            AttrTarget::Stmt => "statement",
+        }
+    }
+
+}
+
+/// the attributes the compiler knows about and interprets itself
+pub const BUILTIN_ATTRS: [&str; 9] = ["inline", "test", "deprecated", "allow", "derive", "cfg", "automatically_derived", "lang", "recursion_limit"];
+
+/// checks that all built-in attributes are well-formed and applied to a node they are valid on
+/// and warns about unknown attributes
+pub fn check_attrs(attrs: &[Attribute], target: AttrTarget, diagnostics: &mut DiagnosticBuilder) {
+    for attr in attrs {
+        let name = attr.name().as_str();
+        if !BUILTIN_ATTRS.contains(&name) {
+            let mut diagnostic = diagnostics.diagnostic(attr.to_string());
+            diagnostic.warn(format!("unknown attribute `{}`", name));
+            diagnostic.build();
+            continue;
+        }
+        let valid_targets: &[AttrTarget] = match name {
+            "inline" | "test" => &[AttrTarget::Fn],
+            "derive" => &[AttrTarget::Struct],
+            "lang" => &[AttrTarget::Trait, AttrTarget::Struct],
+            "recursion_limit" => &[AttrTarget::Crate],
+            // `#[deprecated]` doesn't make sense on things which can't be referred to
+            "deprecated" => &[AttrTarget::Fn, AttrTarget::Struct, AttrTarget::Field, AttrTarget::Trait, AttrTarget::TyAlias, AttrTarget::Static, AttrTarget::Const],
+            _ => &[],
+        };
+        if !valid_targets.is_empty() && !valid_targets.contains(&target) {
+            let mut diagnostic = diagnostics.diagnostic(attr.to_string());
+            diagnostic.error(format!("`#[{}]` can't be applied to a {}", name, target.to_string()));
+            diagnostic.build();
+            continue;
+        }
+        let well_formed = match (name, &attr.meta) {
+            ("inline", MetaItem::Word(_)) => true,
+            ("inline", MetaItem::List(_, args)) => matches!(&**args, [MetaItem::Word(arg)] if arg == "always" || arg == "never"),
+            ("test", MetaItem::Word(_)) => true,
+            ("deprecated", MetaItem::Word(_) | MetaItem::NameValue(_, _)) => true,
+            ("deprecated", MetaItem::List(_, args)) => args.iter().all(|arg| matches!(arg, MetaItem::NameValue(key, _) if key == "since" || key == "note")),
+            ("allow" | "derive", MetaItem::List(_, args)) => !args.is_empty() && args.iter().all(|arg| matches!(arg, MetaItem::Word(_))),
+            ("cfg", MetaItem::List(_, args)) => args.len() == 1,
+            ("lang", MetaItem::NameValue(_, _)) => true,
+            ("recursion_limit", MetaItem::NameValue(_, limit)) => limit.parse::<usize>().is_ok(),
+            _ => false,
+        };
+        if !well_formed {
+            let mut diagnostic = diagnostics.diagnostic(attr.to_string());
+            diagnostic.error(format!("malformed `{}` attribute", name));
+            diagnostic.build();
+        }
+    }
+}
+
+/// finds the first attribute with the name `name`
+pub fn find_attr<'a>(attrs: &'a [Attribute], name: &str) -> Option<&'a Attribute> {
+    attrs.iter().find(|attr| attr.name() == name)
+}
+
+/// collects the args of all attributes with the name `name`
+/// e.g. `#[derive(Clone)] #[derive(Copy, Debug)]` results in `[Clone, Copy, Debug]` for `derive`
+pub fn attr_list_args<'a>(attrs: &'a [Attribute], name: &str) -> Vec<&'a MetaItem> {
+    attrs.iter().filter(|attr| attr.name() == name).flat_map(|attr| match &attr.meta {
+        MetaItem::List(_, args) => args.iter().collect(),
+        _ => vec![],
+    }).collect()
+}
+
+/// checks whether a lint was allowed via `#[allow(lint)]`
+pub fn is_lint_allowed(attrs: &[Attribute], lint: &str) -> bool {
+    attr_list_args(attrs, "allow").iter().any(|arg| arg.name() == lint)
+}
+
+/// returns the note of a `#[deprecated]` attribute if the node is deprecated
+/// (`Some(None)` if it is deprecated without a note)
+pub fn deprecation(attrs: &[Attribute]) -> Option<Option<&String>> {
+    find_attr(attrs, "deprecated").map(|attr| match &attr.meta {
+        MetaItem::NameValue(_, note) => Some(note),
+        MetaItem::List(_, args) => args.iter().find_map(|arg| match arg {
+            MetaItem::NameValue(key, note) if key == "note" => Some(note),
+            _ => None,
+        }),
+        MetaItem::Word(_) => None,
+    })
+}
+
+/// returns the name of the lang item defined via `#[lang = "name"]` (if any)
+pub fn lang_item(attrs: &[Attribute]) -> Option<&String> {
+    find_attr(attrs, "lang").and_then(|attr| match &attr.meta {
+        MetaItem::NameValue(_, name) => Some(name),
+        _ => None,
+    })
+}
+
+/// returns the limit set by `#![recursion_limit = "N"]` (if any)
+pub fn recursion_limit(attrs: &[Attribute]) -> Option<usize> {
+    find_attr(attrs, "recursion_limit").and_then(|attr| match &attr.meta {
+        MetaItem::NameValue(_, limit) => limit.parse().ok(),
+        _ => None,
+    })
+}
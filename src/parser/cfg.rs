@@ -0,0 +1,80 @@
+use std::collections::HashSet;
+use crate::diagnostics::builder::DiagnosticBuilder;
+use crate::parser::attrs::{Attribute, MetaItem};
+
+/// the options conditional compilation (`#[cfg(...)]`) gets evaluated against,
+/// these are passed to the driver like: `--cfg test --cfg feature="x"`
+#[derive(Debug, Clone, Default)]
+pub struct CfgOptions {
+    options: HashSet<(String, Option<String>)>, // key, value
+}
+
+impl CfgOptions {
+
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// parses and adds an option like `test` or `feature="x"`
+    pub fn parse_option(&mut self, option: &str) -> Result<(), String> {
+        let (key, value) = match option.split_once('=') {
+            Some((key, value)) => {
+                let value = value.trim();
+                let value = value.strip_prefix('"').and_then(|value| value.strip_suffix('"'))
+                    .ok_or_else(|| format!("the value of the cfg option `{}` has to be a string literal", key.trim()))?;
+                (key.trim(), Some(value.to_string()))
+            }
+            None => (option.trim(), None),
+        };
+        if key.is_empty() || !key.chars().all(|chr| chr.is_alphanumeric() || chr == '_') {
+            return Err(format!("invalid cfg option `{}`", option));
+        }
+        self.insert(key.to_string(), value);
+        Ok(())
+    }
+
+    pub fn insert(&mut self, key: String, value: Option<String>) {
+        self.options.insert((key, value));
+    }
+
+    /// evaluates a predicate like `all(test, not(feature = "x"))`
+    pub fn eval(&self, predicate: &MetaItem) -> Result<bool, String> {
+        match predicate {
+            MetaItem::Word(key) => Ok(self.options.contains(&(key.clone(), None))),
+            MetaItem::NameValue(key, value) => Ok(self.options.contains(&(key.clone(), Some(value.clone())))),
+            MetaItem::List(name, args) => {
+                // all args get evaluated, so errors in any of them get reported
+                let args = args.iter().map(|arg| self.eval(arg)).collect::<Result<Vec<_>, _>>()?;
+                match (name.as_str(), &*args) {
+                    ("not", [arg]) => Ok(!arg),
+                    ("not", _) => Err(format!("`not` expects exactly one predicate but got {}", args.len())),
+                    ("all", args) => Ok(args.iter().all(|arg| *arg)),
+                    ("any", args) => Ok(args.iter().any(|arg| *arg)),
+                    (name, _) => Err(format!("invalid predicate `{}`, expected one of `not`, `all` or `any`", name)),
+                }
+            }
+        }
+    }
+
+    /// checks whether the predicates of all `#[cfg(...)]` attributes of a node hold,
+    /// malformed attributes are ignored here as `check_attrs` reports them
+    pub fn is_enabled(&self, attrs: &[Attribute], diagnostics: &mut DiagnosticBuilder) -> bool {
+        let mut enabled = true;
+        for attr in attrs.iter().filter(|attr| attr.name() == "cfg") {
+            let predicate = match &attr.meta {
+                MetaItem::List(_, args) if args.len() == 1 => &args[0],
+                _ => continue,
+            };
+            match self.eval(predicate) {
+                Ok(holds) => enabled &= holds,
+                Err(error) => {
+                    let mut diagnostic = diagnostics.diagnostic(attr.to_string());
+                    diagnostic.error(error);
+                    diagnostic.build();
+                }
+            }
+        }
+        enabled
+    }
+
+}
@@ -0,0 +1,90 @@
+use crate::diagnostics::builder::DiagnosticBuilder;
+use crate::diagnostics::span::Span;
+use crate::parser::ast::{AdtImpl, AstNode, Generic, ItemKind, OwnedTy, StructDef, Ty, TyKind, TyOrConstVal};
+use crate::parser::attrs::{attr_list_args, AttrStyle, Attribute, MetaItem};
+
+/// the traits which can be implemented via `#[derive(...)]`
+pub const DERIVABLE_TRAITS: [&str; 5] = ["Clone", "Copy", "Debug", "PartialEq", "Default"];
+
+/// marks impls which were generated by `#[derive(...)]`
+pub const DERIVED_ATTR: &str = "automatically_derived";
+
+/// generates an impl for every trait listed in the `#[derive(...)]` attributes of the given items
+// FIXME: support enums once they can be parsed
+pub fn expand_derives(items: &[ItemKind], diagnostics: &mut DiagnosticBuilder) -> Vec<ItemKind> {
+    let mut ret = vec![];
+    for item in items {
+        // derives on other items were already reported by `check_attrs`
+        let def = if let ItemKind::StructDef(def) = item {
+            def
+        } else {
+            continue;
+        };
+        for derive in attr_list_args(&def.attrs, "derive") {
+            match derive {
+                MetaItem::Word(name) if DERIVABLE_TRAITS.contains(&name.as_str()) => {
+                    ret.push(ItemKind::StructImpl(derive_impl(def, name)));
+                }
+                MetaItem::Word(name) => {
+                    let mut diagnostic = diagnostics.diagnostic(format!("#[derive({})]", name));
+                    diagnostic.error(format!("cannot find derive macro `{}` in this scope", name));
+                    diagnostic.note(format!("the built-in derivable traits are: {}", DERIVABLE_TRAITS.join(", ")));
+                    diagnostic.build();
+                }
+                _ => {
+                    let mut diagnostic = diagnostics.diagnostic(format!("#[derive({})]", derive));
+                    diagnostic.error("expected the name of a trait to derive".to_string());
+                    diagnostic.build();
+                }
+            }
+        }
+    }
+    ret
+}
+
+/// creates `impl<T: Trait> Trait for Struct<T> {}` for the given struct,
+/// the methods of derived impls are provided by the compiler itself
+fn derive_impl(def: &StructDef, tait: &str) -> AdtImpl {
+    let tait = named_ty(tait.to_string());
+    let generics = def.generics.iter().map(|generic| match generic {
+        // every generic param has to implement the trait as well
+        Generic::Type(ty) => {
+            let mut ty = ty.clone();
+            ty.required_traits = ty.required_traits.iter().cloned().chain([tait.clone()]).collect();
+            Generic::Type(ty)
+        }
+        generic => generic.clone(),
+    }).collect::<Box<[_]>>();
+    let args = def.generics.iter().map(|generic| match generic {
+        Generic::Type(ty) => TyOrConstVal::Ty(named_ty(ty.name.clone())),
+        Generic::Constant(constant) => TyOrConstVal::ConstVal(AstNode::Ident(constant.name.clone())),
+        Generic::Lifetime(lt) => TyOrConstVal::Lifetime(lt.lt.clone()),
+    }).collect();
+    AdtImpl {
+        attrs: Box::new([Attribute {
+            style: AttrStyle::Outer,
+            meta: MetaItem::Word(DERIVED_ATTR.to_string()),
+        }]),
+        ty: Ty {
+            kind: TyKind::Owned(Box::new(OwnedTy {
+                name: def.name.clone(),
+                generics: args,
+            })),
+        },
+        impl_trait: Some(tait),
+        negative: false,
+        generics,
+        where_clause: def.where_clause.clone(),
+        methods: Box::new([]),
+        span: Span::NONE,
+    }
+}
+
+fn named_ty(name: String) -> Ty {
+    Ty {
+        kind: TyKind::Owned(Box::new(OwnedTy {
+            name,
+            generics: Box::new([]),
+        })),
+    }
+}
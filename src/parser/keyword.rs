@@ -1,4 +1,4 @@
-use crate::parser::keyword::Keyword::{Const, Else, Enum, Fn, For, If, Impl, In, Let, Loop, Match, Mod, Mut, Pub, Rt, SelfLower, SelfUpper, Static, Struct, Trait, Type, While};
+use crate::parser::keyword::Keyword::{Const, Else, Enum, False, Fn, For, If, Impl, In, Let, Loop, Match, Mod, Move, Mut, Pub, Rt, SelfLower, SelfUpper, Static, Struct, Trait, True, Type, Where, While, Dyn};
 
 #[derive(Debug, Copy, Clone, PartialEq)]
 pub enum Keyword {
@@ -27,6 +27,11 @@ pub enum Keyword {
     Extern,
     Trait,
     Type,
+    Move,
+    Where,
+    True,
+    False,
+    Dyn,
     // FIXME: there should be a couple of other keywords missing here
 }
 
@@ -55,7 +60,47 @@ impl Keyword {
             "in" => Some(In),
             "trait" => Some(Trait),
             "type" => Some(Type),
+            "move" => Some(Move),
+            "where" => Some(Where),
+            "true" => Some(True),
+            "false" => Some(False),
+            "dyn" => Some(Dyn),
             _ => None,
         }
     }
+
+    pub fn to_string(self) -> &'static str {
+        match self {
+            Pub => "pub",
+            Static => "static",
+            Const => "const",
+            Rt => "runtime",
+            Let => "let",
+            Fn => "fn",
+            Mut => "mut",
+            Enum => "enum",
+            Struct => "struct",
+            Mod => "mod",
+            SelfUpper => "Self",
+            SelfLower => "self",
+            Impl => "impl",
+            If => "if",
+            Else => "else",
+            Match => "match",
+            For => "for",
+            While => "while",
+            Loop => "loop",
+            In => "in",
+            Trait => "trait",
+            Type => "type",
+            Move => "move",
+            Where => "where",
+            True => "true",
+            False => "false",
+            Dyn => "dyn",
+            Keyword::Async => "async",
+            Keyword::Unsafe => "unsafe",
+            Keyword::Extern => "extern",
+        }
+    }
 }
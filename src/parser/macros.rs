@@ -0,0 +1,629 @@
+use std::collections::{HashMap, HashSet};
+use std::rc::Rc;
+use crate::diagnostics::span::Span;
+use crate::lexer::token::{BinOp, Token, TokenType};
+use crate::parser::keyword::Keyword;
+use crate::parser::parser::parse_fragment;
+
+// declarative macros (`macro_rules!`) operate on token trees and get expanded
+// before the tokens at their call site get parsed
+// see: https://doc.rust-lang.org/reference/macros-by-example.html
+
+/// the maximum number of nested macro expansions before we assume that the expansion doesn't terminate
+pub const MACRO_RECURSION_LIMIT: usize = 128;
+
+/// the macros which are available in every crate
+pub const PRELUDE: &str = "
+macro_rules! print {
+    ($($arg:tt)*) => { __print($($arg)*) };
+}
+
+macro_rules! println {
+    () => { __print_ln() };
+    ($($arg:tt)*) => { __print_ln($($arg)*) };
+}
+";
+
+/// the functions the macros of the prelude expand to, they are provided by the compiler and accept any args
+pub const PRELUDE_INTRINSICS: [&str; 2] = ["__print", "__print_ln"];
+
+pub type ExpnId = usize;
+
+pub type MacroTable = HashMap<String, Rc<MacroDef>>;
+
+/// information about a single macro expansion, this is used to find out how deeply
+/// expansions are nested when checking the recursion limit
+#[derive(Debug, Clone)]
+pub struct ExpnData {
+    // the expansion the call itself is part of (if any)
+    pub(crate) parent: Option<ExpnId>,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Delim {
+    Paren,   // ()
+    Bracket, // []
+    Curly,   // {}
+}
+
+impl Delim {
+
+    pub fn from_open(token: &Token) -> Option<Self> {
+        match token.to_type() {
+            TokenType::OpenParen => Some(Delim::Paren),
+            TokenType::OpenBracket => Some(Delim::Bracket),
+            TokenType::OpenCurly => Some(Delim::Curly),
+            _ => None,
+        }
+    }
+
+    pub fn closes(&self, token: &Token) -> bool {
+        match self {
+            Delim::Paren => token.to_type() == TokenType::ClosedParen,
+            Delim::Bracket => token.to_type() == TokenType::ClosedBracket,
+            Delim::Curly => token.to_type() == TokenType::ClosedCurly,
+        }
+    }
+
+}
+
+fn is_closing(token: &Token) -> bool {
+    matches!(token.to_type(), TokenType::ClosedParen | TokenType::ClosedBracket | TokenType::ClosedCurly)
+}
+
+#[derive(Debug, Clone)]
+pub enum TokenTree {
+    Token(Token),
+    Delimited(Delim, Token, Vec<TokenTree>, Token), // delim, open, contents, close
+}
+
+impl TokenTree {
+
+    /// the number of tokens in the tree including its delimiters
+    fn len(&self) -> usize {
+        match self {
+            TokenTree::Token(_) => 1,
+            TokenTree::Delimited(_, _, tts, _) => tts.iter().map(TokenTree::len).sum::<usize>() + 2,
+        }
+    }
+
+    fn flatten_into(&self, out: &mut Vec<Token>) {
+        match self {
+            TokenTree::Token(token) => out.push(token.clone()),
+            TokenTree::Delimited(_, open, tts, close) => {
+                out.push(open.clone());
+                for tt in tts {
+                    tt.flatten_into(out);
+                }
+                out.push(close.clone());
+            }
+        }
+    }
+
+}
+
+/// groups a flat list of tokens into token trees, this fails if the delimiters aren't balanced
+pub fn parse_token_trees(tokens: &[Token]) -> Result<Vec<TokenTree>, String> {
+    // a stack of the currently open delimited groups
+    let mut open: Vec<(Delim, Token, Vec<TokenTree>)> = vec![];
+    let mut ret = vec![];
+    for token in tokens {
+        if let Some(delim) = Delim::from_open(token) {
+            open.push((delim, token.clone(), vec![]));
+        } else if is_closing(token) {
+            let (delim, open_token, tts) = open.pop().ok_or_else(|| "unexpected closing delimiter".to_string())?;
+            if !delim.closes(token) {
+                return Err("mismatched closing delimiter".to_string());
+            }
+            let tt = TokenTree::Delimited(delim, open_token, tts, token.clone());
+            open.last_mut().map_or(&mut ret, |(_, _, tts)| tts).push(tt);
+        } else {
+            open.last_mut().map_or(&mut ret, |(_, _, tts)| tts).push(TokenTree::Token(token.clone()));
+        }
+    }
+    if !open.is_empty() {
+        return Err("unclosed delimiter".to_string());
+    }
+    Ok(ret)
+}
+
+/// renders `tokens` as code on a single line (e.g. as the input of a diagnostic) together with the span of each token
+/// in the rendered code, tokens which were separated in the source are separated by a single space
+pub fn render_tokens(tokens: &[Token]) -> (String, Vec<Span>) {
+    let mut code = String::new();
+    let mut spans = vec![];
+    let mut prev: Option<&Token> = None;
+    for token in tokens {
+        if prev.is_some_and(|prev| token.span().start > prev.span().end) {
+            code.push(' ');
+        }
+        let text = token.to_string();
+        let start = code.chars().count();
+        spans.push(Span::multi_token(start, start + text.chars().count()));
+        code.push_str(&text);
+        prev = Some(token);
+    }
+    (code, spans)
+}
+
+/// compares two tokens while ignoring their spans
+fn same_token(left: &Token, right: &Token) -> bool {
+    match (left, right) {
+        (Token::Ident(_, left), Token::Ident(_, right)) => left == right,
+        (Token::Keyword(_, left), Token::Keyword(_, right)) => left == right,
+        (Token::BinOp(_, left), Token::BinOp(_, right)) => left == right,
+        (Token::StrLit(_, left), Token::StrLit(_, right)) => left == right,
+        (Token::NumLit(_, left), Token::NumLit(_, right)) => left == right,
+        (Token::Invalid(_, left), Token::Invalid(_, right)) => left == right,
+        _ => left.to_type() == right.to_type(),
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum FragmentKind {
+    Expr,
+    Ident,
+    Ty,
+    Tt,
+    Literal,
+    Block,
+    Lifetime,
+}
+
+impl FragmentKind {
+
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "expr" => Some(FragmentKind::Expr),
+            "ident" => Some(FragmentKind::Ident),
+            "ty" => Some(FragmentKind::Ty),
+            "tt" => Some(FragmentKind::Tt),
+            "literal" => Some(FragmentKind::Literal),
+            "block" => Some(FragmentKind::Block),
+            "lifetime" => Some(FragmentKind::Lifetime),
+            _ => None,
+        }
+    }
+
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum RepetitionOp {
+    ZeroOrMore, // *
+    OneOrMore,  // +
+    ZeroOrOne,  // ?
+}
+
+impl RepetitionOp {
+
+    fn from_token(token: &Token) -> Option<Self> {
+        match token {
+            Token::BinOp(_, BinOp::Mul) | Token::Star(_) => Some(RepetitionOp::ZeroOrMore),
+            Token::BinOp(_, BinOp::Add) => Some(RepetitionOp::OneOrMore),
+            Token::Question(_) => Some(RepetitionOp::ZeroOrOne),
+            _ => None,
+        }
+    }
+
+}
+
+#[derive(Debug, Clone)]
+enum Matcher {
+    Token(Token),
+    Delimited(Delim, Vec<Matcher>),
+    Fragment(String, FragmentKind), // $name:kind
+    Repetition(Vec<Matcher>, Option<Token>, RepetitionOp), // $( ... ) sep op
+}
+
+#[derive(Debug, Clone)]
+enum Transcriber {
+    Token(Token),
+    Delimited(Token, Vec<Transcriber>, Token),
+    Var(String), // $name
+    Repetition(Vec<Transcriber>, Option<Token>, RepetitionOp), // $( ... ) sep op
+}
+
+/// parses the `$( ... ) sep op` part of a repetition after the `$` and returns the separator and the op
+fn parse_repetition_suffix(tts: &[TokenTree], pos: &mut usize) -> Result<(Option<Token>, RepetitionOp), String> {
+    let op_at = |pos: usize| match tts.get(pos) {
+        Some(TokenTree::Token(token)) => RepetitionOp::from_token(token),
+        _ => None,
+    };
+    if let Some(op) = op_at(*pos) {
+        *pos += 1;
+        return Ok((None, op));
+    }
+    match (tts.get(*pos), op_at(*pos + 1)) {
+        (Some(TokenTree::Token(sep)), Some(op)) => {
+            *pos += 2;
+            Ok((Some(sep.clone()), op))
+        }
+        _ => Err("expected one of `*`, `+` or `?` after a repetition".to_string()),
+    }
+}
+
+fn parse_matchers(tts: &[TokenTree]) -> Result<Vec<Matcher>, String> {
+    let mut ret = vec![];
+    let mut pos = 0;
+    while let Some(tt) = tts.get(pos) {
+        pos += 1;
+        match tt {
+            TokenTree::Token(Token::Dollar(_)) => match tts.get(pos) {
+                Some(TokenTree::Token(Token::Ident(_, name))) => {
+                    let kind = match (tts.get(pos + 1), tts.get(pos + 2)) {
+                        (Some(TokenTree::Token(Token::Colon(_))), Some(TokenTree::Token(Token::Ident(_, kind)))) => FragmentKind::from_name(kind)
+                            .ok_or_else(|| format!("invalid fragment specifier `{}`", kind))?,
+                        _ => return Err(format!("missing fragment specifier for `${}`", name)),
+                    };
+                    ret.push(Matcher::Fragment(name.clone(), kind));
+                    pos += 3;
+                }
+                Some(TokenTree::Delimited(Delim::Paren, _, inner, _)) => {
+                    pos += 1;
+                    let (sep, op) = parse_repetition_suffix(tts, &mut pos)?;
+                    ret.push(Matcher::Repetition(parse_matchers(inner)?, sep, op));
+                }
+                _ => return Err("expected a macro variable or a repetition after `$`".to_string()),
+            },
+            TokenTree::Token(token) => ret.push(Matcher::Token(token.clone())),
+            TokenTree::Delimited(delim, _, inner, _) => ret.push(Matcher::Delimited(*delim, parse_matchers(inner)?)),
+        }
+    }
+    Ok(ret)
+}
+
+fn parse_transcribers(tts: &[TokenTree]) -> Result<Vec<Transcriber>, String> {
+    let mut ret = vec![];
+    let mut pos = 0;
+    while let Some(tt) = tts.get(pos) {
+        pos += 1;
+        match tt {
+            TokenTree::Token(Token::Dollar(_)) => match tts.get(pos) {
+                Some(TokenTree::Token(Token::Ident(_, name))) => {
+                    ret.push(Transcriber::Var(name.clone()));
+                    pos += 1;
+                }
+                Some(TokenTree::Delimited(Delim::Paren, _, inner, _)) => {
+                    pos += 1;
+                    let (sep, op) = parse_repetition_suffix(tts, &mut pos)?;
+                    ret.push(Transcriber::Repetition(parse_transcribers(inner)?, sep, op));
+                }
+                _ => return Err("expected a macro variable or a repetition after `$`".to_string()),
+            },
+            TokenTree::Token(token) => ret.push(Transcriber::Token(token.clone())),
+            TokenTree::Delimited(_, open, inner, close) => ret.push(Transcriber::Delimited(open.clone(), parse_transcribers(inner)?, close.clone())),
+        }
+    }
+    Ok(ret)
+}
+
+#[derive(Debug, Clone)]
+enum Binding {
+    Fragment(FragmentKind, Vec<TokenTree>),
+    Seq(Vec<Binding>), // the bindings of a variable inside a repetition, one for each iteration
+}
+
+type Bindings = HashMap<String, Binding>;
+
+fn matcher_vars(matchers: &[Matcher], vars: &mut Vec<String>) {
+    for matcher in matchers {
+        match matcher {
+            Matcher::Token(_) => {}
+            Matcher::Delimited(_, inner) | Matcher::Repetition(inner, _, _) => matcher_vars(inner, vars),
+            Matcher::Fragment(name, _) => vars.push(name.clone()),
+        }
+    }
+}
+
+fn transcriber_vars(transcribers: &[Transcriber], vars: &mut Vec<String>) {
+    for transcriber in transcribers {
+        match transcriber {
+            Transcriber::Token(_) => {}
+            Transcriber::Delimited(_, inner, _) | Transcriber::Repetition(inner, _, _) => transcriber_vars(inner, vars),
+            Transcriber::Var(name) => vars.push(name.clone()),
+        }
+    }
+}
+
+/// matches `matchers` against the start of `input` and returns the number of token trees which got matched
+fn match_seq(matchers: &[Matcher], input: &[TokenTree], bindings: &mut Bindings) -> Option<usize> {
+    let mut pos = 0;
+    for matcher in matchers {
+        match matcher {
+            Matcher::Token(expected) => match input.get(pos) {
+                Some(TokenTree::Token(token)) if same_token(token, expected) => pos += 1,
+                _ => return None,
+            },
+            Matcher::Delimited(delim, inner) => match input.get(pos) {
+                Some(TokenTree::Delimited(actual, _, tts, _)) if actual == delim => {
+                    if match_seq(inner, tts, bindings)? != tts.len() {
+                        return None;
+                    }
+                    pos += 1;
+                }
+                _ => return None,
+            },
+            Matcher::Fragment(name, kind) => {
+                let len = match_fragment(*kind, &input[pos..])?;
+                bindings.insert(name.clone(), Binding::Fragment(*kind, input[pos..(pos + len)].to_vec()));
+                pos += len;
+            }
+            Matcher::Repetition(inner, sep, op) => {
+                // repetitions are matched greedily
+                let mut iterations: Vec<Bindings> = vec![];
+                loop {
+                    if *op == RepetitionOp::ZeroOrOne && iterations.len() == 1 {
+                        break;
+                    }
+                    let mut start = pos;
+                    if let (Some(sep), false) = (sep, iterations.is_empty()) {
+                        match input.get(start) {
+                            Some(TokenTree::Token(token)) if same_token(token, sep) => start += 1,
+                            _ => break,
+                        }
+                    }
+                    let mut iteration = HashMap::new();
+                    match match_seq(inner, &input[start..], &mut iteration) {
+                        // empty iterations would repeat forever
+                        Some(len) if len > 0 => {
+                            pos = start + len;
+                            iterations.push(iteration);
+                        }
+                        _ => break,
+                    }
+                }
+                if *op == RepetitionOp::OneOrMore && iterations.is_empty() {
+                    return None;
+                }
+                let mut vars = vec![];
+                matcher_vars(inner, &mut vars);
+                for var in vars {
+                    let seq = iterations.iter_mut().map(|iteration| iteration.remove(&var).unwrap()).collect();
+                    bindings.insert(var, Binding::Seq(seq));
+                }
+            }
+        }
+    }
+    Some(pos)
+}
+
+/// returns the number of token trees the fragment at the start of `input` consists of
+fn match_fragment(kind: FragmentKind, input: &[TokenTree]) -> Option<usize> {
+    let token_at = |idx: usize| match input.get(idx) {
+        Some(TokenTree::Token(token)) => Some(token),
+        _ => None,
+    };
+    match kind {
+        FragmentKind::Tt => input.first().map(|_| 1),
+        FragmentKind::Ident => matches!(token_at(0), Some(Token::Ident(_, _))).then_some(1),
+        FragmentKind::Literal => match (token_at(0), token_at(1)) {
+            (Some(Token::StrLit(_, _) | Token::NumLit(_, _)), _) => Some(1),
+            (Some(Token::BinOp(_, BinOp::Sub)), Some(Token::NumLit(_, _))) => Some(2),
+            _ => None,
+        },
+        FragmentKind::Lifetime => match (token_at(0), token_at(1)) {
+            (Some(Token::Apostrophe(_)), Some(Token::Ident(_, _) | Token::Keyword(_, Keyword::Static))) => Some(2),
+            _ => None,
+        },
+        FragmentKind::Block => matches!(input.first(), Some(TokenTree::Delimited(Delim::Curly, _, _, _))).then_some(1),
+        FragmentKind::Expr | FragmentKind::Ty => {
+            // we let the parser decide how many tokens belong to the fragment
+            // and then map these tokens back onto the token trees they are part of
+            let mut tokens = vec![];
+            let mut boundaries = vec![];
+            for tt in input {
+                tt.flatten_into(&mut tokens);
+                boundaries.push(tokens.len());
+            }
+            let consumed = parse_fragment(tokens, kind)?;
+            boundaries.iter().position(|boundary| *boundary == consumed).map(|idx| idx + 1)
+        }
+    }
+}
+
+/// looks up the binding of a variable for the current iteration of each repetition it is part of
+fn lookup<'a>(mut binding: &'a Binding, repetition: &[usize]) -> &'a Binding {
+    for idx in repetition {
+        match binding {
+            Binding::Seq(seq) => binding = &seq[*idx],
+            Binding::Fragment(_, _) => break,
+        }
+    }
+    binding
+}
+
+fn repetition_count(transcribers: &[Transcriber], bindings: &Bindings, repetition: &[usize]) -> Result<usize, String> {
+    let mut vars = vec![];
+    transcriber_vars(transcribers, &mut vars);
+    let mut count: Option<(usize, String)> = None;
+    for var in vars {
+        if let Some(Binding::Seq(seq)) = bindings.get(&var).map(|binding| lookup(binding, repetition)) {
+            match &count {
+                Some((expected, other)) if *expected != seq.len() => {
+                    return Err(format!("meta-variable `{}` repeats {} times, but `{}` repeats {} times", other, expected, var, seq.len()));
+                }
+                Some(_) => {}
+                None => count = Some((seq.len(), var)),
+            }
+        }
+    }
+    count.map(|(count, _)| count).ok_or_else(|| "attempted to repeat an expression containing no syntax variables matched as repeating at this depth".to_string())
+}
+
+/// transcribes the macro's body, the boolean is true for all tokens originating from the macro's definition
+fn transcribe(transcribers: &[Transcriber], bindings: &Bindings, repetition: &mut Vec<usize>, out: &mut Vec<(Token, bool)>) -> Result<(), String> {
+    for transcriber in transcribers {
+        match transcriber {
+            Transcriber::Token(token) => out.push((token.clone(), true)),
+            Transcriber::Delimited(open, inner, close) => {
+                out.push((open.clone(), true));
+                transcribe(inner, bindings, repetition, out)?;
+                out.push((close.clone(), true));
+            }
+            Transcriber::Var(name) => {
+                let binding = bindings.get(name).ok_or_else(|| format!("unknown macro variable `{}`", name))?;
+                match lookup(binding, repetition) {
+                    Binding::Seq(_) => return Err(format!("variable `{}` is still repeating at this depth", name)),
+                    Binding::Fragment(kind, tts) => {
+                        let mut tokens = vec![];
+                        for tt in tts {
+                            tt.flatten_into(&mut tokens);
+                        }
+                        // expressions are opaque to the surrounding tokens, so `$x * 2` with `$x = 1 + 1` results in `(1 + 1) * 2`
+                        let wrap = *kind == FragmentKind::Expr && tokens.len() > 1;
+                        if wrap {
+                            out.push((Token::OpenParen(tokens[0].span().start.into()), false));
+                        }
+                        let last = tokens.last().map(|token| token.span().end);
+                        out.extend(tokens.into_iter().map(|token| (token, false)));
+                        if wrap {
+                            out.push((Token::ClosedParen(last.unwrap().saturating_sub(1).into()), false));
+                        }
+                    }
+                }
+            }
+            Transcriber::Repetition(inner, sep, op) => {
+                let count = repetition_count(inner, bindings, repetition)?;
+                match op {
+                    RepetitionOp::ZeroOrOne if count > 1 => return Err("this must repeat at most once".to_string()),
+                    RepetitionOp::OneOrMore if count == 0 => return Err("this must repeat at least once".to_string()),
+                    _ => {}
+                }
+                for idx in 0..count {
+                    if let (Some(sep), true) = (sep, idx > 0) {
+                        out.push((sep.clone(), true));
+                    }
+                    repetition.push(idx);
+                    transcribe(inner, bindings, repetition, out)?;
+                    repetition.pop();
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// renames all locals which get declared inside the macro's definition, so they can't
+/// collide with locals passed to the macro by the caller, e.g. in:
+/// `macro_rules! double { ($e:expr) => { { let x = 2; x * $e } } }`
+/// `double!(x)` still refers to the caller's `x` and not to the one defined inside the macro
+fn apply_hygiene(tokens: &mut [(Token, bool)], expn: ExpnId) {
+    let mut bound = HashSet::new();
+    for (idx, (token, from_def)) in tokens.iter().enumerate() {
+        if !*from_def || !matches!(token, Token::Keyword(_, Keyword::Let)) {
+            continue;
+        }
+        let name_idx = if matches!(tokens.get(idx + 1), Some((Token::Keyword(_, Keyword::Mut), _))) { idx + 2 } else { idx + 1 };
+        if let Some((Token::Ident(_, name), true)) = tokens.get(name_idx) {
+            bound.insert(name.clone());
+        }
+    }
+    for (token, from_def) in tokens.iter_mut() {
+        if let (Token::Ident(_, name), true) = (token, *from_def) {
+            if bound.contains(name) {
+                // `#` can't be part of any identifier written by the user
+                *name = format!("{}#{}", name, expn);
+            }
+        }
+    }
+}
+
+#[derive(Debug)]
+struct MacroRule {
+    matcher: Vec<Matcher>,
+    transcriber: Vec<Transcriber>,
+    // the rule as it is written, e.g. `($x:expr) => { $x * $x }`, and the span of its matcher in it
+    source: String,
+    matcher_span: Span,
+}
+
+/// an error while expanding a macro call together with the index of the rule that matched the call (if any)
+#[derive(Debug)]
+pub struct ExpandError {
+    pub(crate) msg: String,
+    pub(crate) rule: Option<usize>,
+}
+
+#[derive(Debug)]
+pub struct MacroDef {
+    pub(crate) name: String,
+    rules: Vec<MacroRule>,
+}
+
+impl MacroDef {
+
+    /// parses the rules of a macro like `(matcher) => { transcriber }; ...`
+    pub fn new(name: String, body: &[Token]) -> Result<Self, String> {
+        let tts = parse_token_trees(body)?;
+        let mut rules = vec![];
+        let mut pos = 0;
+        while pos < tts.len() {
+            let matcher = match &tts[pos] {
+                TokenTree::Delimited(_, _, matcher, _) => parse_matchers(matcher)?,
+                _ => return Err("expected a macro matcher".to_string()),
+            };
+            match (tts.get(pos + 1), tts.get(pos + 2)) {
+                (Some(TokenTree::Token(Token::BinOp(_, BinOp::Eq))), Some(TokenTree::Token(Token::ClosedAngle(_)))) => {}
+                _ => return Err("expected `=>` after a macro matcher".to_string()),
+            }
+            let transcriber = match tts.get(pos + 3) {
+                Some(TokenTree::Delimited(_, _, transcriber, _)) => parse_transcribers(transcriber)?,
+                _ => return Err("expected a macro body after `=>`".to_string()),
+            };
+            let mut tokens = vec![];
+            for tt in &tts[pos..pos + 4] {
+                tt.flatten_into(&mut tokens);
+            }
+            let (source, spans) = render_tokens(&tokens);
+            let matcher_len = tts[pos].len();
+            rules.push(MacroRule {
+                matcher,
+                transcriber,
+                source,
+                matcher_span: Span::multi_token(spans[0].start, spans[matcher_len - 1].end),
+            });
+            pos += 4;
+            match tts.get(pos) {
+                Some(TokenTree::Token(Token::Semi(_))) => pos += 1,
+                None => {}
+                _ => return Err("expected `;` between macro rules".to_string()),
+            }
+        }
+        if rules.is_empty() {
+            return Err(format!("the macro `{}` has no rules", name));
+        }
+        Ok(Self { name, rules })
+    }
+
+    /// expands an invocation of this macro with `input` (excluding the delimiters) as its args
+    pub fn expand(&self, input: &[Token], expn: ExpnId) -> Result<Vec<Token>, ExpandError> {
+        let input = parse_token_trees(input).map_err(|msg| ExpandError { msg, rule: None })?;
+        for (idx, rule) in self.rules.iter().enumerate() {
+            let mut bindings = HashMap::new();
+            if match_seq(&rule.matcher, &input, &mut bindings) != Some(input.len()) {
+                continue;
+            }
+            let mut tokens = vec![];
+            transcribe(&rule.transcriber, &bindings, &mut vec![], &mut tokens).map_err(|msg| ExpandError { msg, rule: Some(idx) })?;
+            apply_hygiene(&mut tokens, expn);
+            return Ok(tokens.into_iter().map(|(token, _)| token).collect());
+        }
+        Err(ExpandError {
+            msg: format!("no rules of the macro `{}` expected this invocation", self.name),
+            rule: None,
+        })
+    }
+
+    /// the rule at `idx` as it is written together with the span of its matcher in it
+    pub fn rule_source(&self, idx: usize) -> (String, Span) {
+        (self.rules[idx].source.clone(), self.rules[idx].matcher_span)
+    }
+
+    /// the start of the macro's definition, e.g. `macro_rules! square`, together with the span of its name in it
+    pub fn def_source(&self) -> (String, Span) {
+        let source = format!("macro_rules! {}", self.name);
+        let span = Span::multi_token(source.len() - self.name.len(), source.len());
+        (source, span)
+    }
+
+}
@@ -1,5 +1,8 @@
 pub mod ast;
 pub mod attrs;
+pub mod cfg;
+pub mod derive;
 pub mod keyword;
+pub mod macros;
 pub mod parser;
 pub mod token_stream;
@@ -4,15 +4,21 @@ use crate::lexer;
 use crate::lexer::token::{BinOp, Token, TokenType};
 use crate::parser::ast::{
     ArrayInst, ArrayInstList, ArrayInstShort, ArrayTy, AstNode, BinaryExprNode, Block,
-    BlockModifiers, CallExprNode, ConstValNode, Crate, FunctionHeader, FunctionModifiers,
-    FunctionNode, Generic, GenericConstant, GenericLifetime, GenericType, ItemKind, LAssign,
-    LDecAssign, Lifetime, LocalAssign, NumberType, OwnedTy, RefTy, StaticValNode, Stmt, StmtKind,
-    StructConstructor, StructDef, StructFieldDef, AdtImpl, TraitDef, Ty, TyKind, TyOrConstVal,
+    BlockModifiers, CallExprNode, ClosureNode, ConstValNode, Crate, DynTy, FuncTy, FuncTyKind,
+    FunctionHeader, FunctionModifiers, FunctionNode, Generic, GenericConstant, GenericLifetime, GenericType, ItemKind, LAssign,
+    LDecAssign, Lifetime, LocalAssign, NumberLit, NumberType, OwnedTy, ProjectionTy, RefTy, StaticValNode,
+    Stmt, StmtKind, StructConstructor, StructDef, StructFieldDef, AdtImpl, TraitAssocConst,
+    TraitAssocTy, TraitDef, TraitMethod, Ty, TyAlias, TyKind, TyOrConstVal, TyWherePredicate,
+    WherePredicate,
 };
-use crate::parser::attrs::{Constness, Mutability, Visibility};
+use crate::parser::attrs::{attr_list_args, AttrStyle, Attribute, CaptureBy, Constness, MetaItem, Mutability, Visibility};
+#[cfg(test)]
+use crate::parser::cfg::CfgOptions;
 use crate::parser::keyword::Keyword;
+use crate::parser::macros::{render_tokens, Delim, ExpnData, FragmentKind, MacroDef, MacroTable, MACRO_RECURSION_LIMIT, PRELUDE};
 use crate::parser::token_stream::TokenStream;
 use std::fs;
+use std::rc::Rc;
 
 // converts a stream of tokens into an ast
 // (a compiler is just a program that operates on data
@@ -22,40 +28,66 @@ pub struct Parser {
     token_stream: TokenStream,
     curr: Token,
     diagnostics: DiagnosticBuilder,
+    // the macros which are currently in scope
+    macros: MacroTable,
+    expansions: Vec<ExpnData>,
+    // whether macro calls get expanded or skipped, the latter is used when matching macro fragments
+    expand_macros: bool,
+    // whether `<` and `>` are comparison operators, they close the generic args instead when parsing const generic args
+    angle_ops: bool,
 }
 
+// the trait and lifetime bounds of a generic param, e.g. `T: Trait1 + Trait2 + 'a`
+type Bounds = (Box<[Ty]>, Box<[Lifetime]>);
+
 // FIXME: should we actually do this here?
 // FIXME: check for duplicate parameter/function names
 
 impl Parser {
     // FIXME: see: https://www.youtube.com/watch?v=4m7ubrdbWQU
 
-    pub fn new(mut token_stream: TokenStream) -> Self {
+    pub fn new(token_stream: TokenStream) -> Self {
+        let prelude = lexer::lex(PRELUDE.to_string()).unwrap();
+        let mut prelude_parser = Self::with_macros(TokenStream::new(prelude), MacroTable::new(), true);
+        prelude_parser.parse_crate().unwrap();
+        Self::with_macros(token_stream, prelude_parser.macros, true)
+    }
+
+    fn with_macros(mut token_stream: TokenStream, macros: MacroTable, expand_macros: bool) -> Self {
         let curr = token_stream.get_next_and_advance().unwrap().clone();
         Self {
             token_stream,
             curr,
             diagnostics: DiagnosticBuilder::new(),
+            macros,
+            expansions: vec![],
+            expand_macros,
+            angle_ops: true,
         }
     }
 
+    pub fn diagnostics(&self) -> &DiagnosticBuilder {
+        &self.diagnostics
+    }
+
     pub fn parse_crate(&mut self) -> Result<Crate, ()> {
+        let attrs = self.parse_inner_attrs()?;
         let mut items = vec![];
         while self.curr.to_type() != TokenType::EOF && self.token_stream.can_advance() {
             // FIXME: this loop runs indefinitely!
-            println!("in loop!");
             match self.parse_item() {
-                Ok(val) => {
+                Ok(Some(val)) => {
                     items.push(val);
                 }
+                Ok(None) => {}
                 Err(_) => {
                     self.advance(); // FIXME: is this correct?
                                     // FIXME: insert error into diagnostics builder
-                    println!("err!!!");
                 }
             }
         }
         Ok(Crate {
+            attrs: attrs.into_boxed_slice(),
             items: items.into_boxed_slice(),
         })
     }
@@ -64,7 +96,6 @@ impl Parser {
     pub fn parse_all(&mut self) {
         while self.curr.to_type() != TokenType::EOF && self.token_stream.can_advance() {
             // FIXME: this loop runs indefinitely!
-            println!("in loop!");
             match self.parse_entry() {
                 Ok(val) => {
                     self.ast.push(val);
@@ -83,12 +114,34 @@ impl Parser {
     }*/
 
     fn parse_number_expr(&mut self) -> Result<AstNode, ()> {
+        let start = self.token_stream.position() - 1;
         if let Token::NumLit(_, content) = &self.curr {
-            let ret = Ok(AstNode::Number(NumberType::F64(
-                content.parse::<f64>().unwrap(),
-            ))); // FIXME: do proper parsing of numbers
+            // the actual type of the literal gets inferred later on
+            let number = if content.contains('.') {
+                content.parse::<f64>().ok().map(NumberType::F64)
+            } else {
+                content.parse::<u128>().ok().map(NumberType::U128)
+            }; // FIXME: do proper parsing of numbers
+            let ret = match number {
+                Some(number) => number,
+                None => {
+                    let mut diagnostic = self.diagnostics.diagnostic(content.clone());
+                    if content.contains('.') {
+                        diagnostic.error(format!("invalid float literal `{}`", content));
+                    } else {
+                        diagnostic.error("literal out of range".to_string());
+                        diagnostic.note(format!("integer literals can't be larger than `{}`", u128::MAX));
+                    }
+                    diagnostic.build();
+                    self.advance();
+                    return Ok(AstNode::Error);
+                }
+            };
             self.advance();
-            ret
+            Ok(AstNode::Number(NumberLit {
+                val: ret,
+                span: self.span_since(start),
+            }))
         } else {
             Err(())
         }
@@ -117,7 +170,7 @@ impl Parser {
     }
 
     /// assumes the let keyword was already skipped
-    fn parse_let(&mut self) -> Result<StmtKind, ()> {
+    fn parse_let(&mut self, attrs: Vec<Attribute>) -> Result<StmtKind, ()> {
         let mutability = self.parse_mutability();
         let name = self.parse_ident();
         if let Some((_, name)) = name {
@@ -135,6 +188,7 @@ impl Parser {
                 }
 
                 return Ok(StmtKind::LocalAssign(LocalAssign::DecAssign(LDecAssign {
+                    attrs: attrs.into_boxed_slice(),
                     mutability,
                     ty,
                     val: LAssign { name, val },
@@ -151,13 +205,11 @@ impl Parser {
         // skip the `fn` keyword
         self.advance();
         if let Some((_, name)) = self.parse_ident() {
-            println!("ident!");
             let generics = self.parse_maybe_generics_definition()?;
 
             if !self.eat(TokenType::OpenParen) {
                 return Err(());
             }
-            println!("open paren {}", name);
             let mut args = vec![];
             while let Some(param) = self.parse_param()? {
                 args.push(param);
@@ -176,25 +228,29 @@ impl Parser {
             } else {
                 None
             };
+            let where_clause = self.parse_maybe_where_clause()?;
 
             Ok(FunctionHeader {
                 name,
                 generics,
                 args: args.into_boxed_slice(),
                 ret,
+                where_clause,
             })
         } else {
             Err(()) // FIXME: return error!
         }
     }
 
-    fn parse_function(&mut self, visibility: Option<Visibility>) -> Result<ItemKind, ()> {
+    fn parse_function(&mut self, mut attrs: Vec<Attribute>, visibility: Option<Visibility>) -> Result<ItemKind, ()> {
+        let constness = self.parse_constness();
         let header = self.parse_function_header()?;
-        let body = self.parse_block_no_attr()?;
+        let body = self.parse_block_with_inner_attrs(&mut attrs)?;
 
         Ok(ItemKind::FunctionDef(Box::new(FunctionNode {
+            attrs: attrs.into_boxed_slice(),
             modifiers: FunctionModifiers {
-                constness: Constness::Undefined,
+                constness,
                 visibility: visibility.unwrap_or(Visibility::Private),
             },
             header,
@@ -202,7 +258,27 @@ impl Parser {
         })))
     }
 
+    /// parses the `const` or `runtime` modifier of a function, functions without one may be either
+    fn parse_constness(&mut self) -> Constness {
+        if self.eat_kw(Keyword::Const) {
+            Constness::Const
+        } else if self.eat_kw(Keyword::Rt) {
+            Constness::Rt
+        } else {
+            Constness::Undefined
+        }
+    }
+
+    /// checks whether the current token starts a function, that is `fn`, `const fn` or `runtime fn`
+    fn check_function(&self) -> bool {
+        self.check_kw(Keyword::Fn) || ((self.check_kw(Keyword::Const) || self.check_kw(Keyword::Rt))
+            && self.token_stream.look_ahead(1, |token| matches!(token, Token::Keyword(_, Keyword::Fn))))
+    }
+
     fn parse_param(&mut self) -> Result<Option<(String, Ty)>, ()> {
+        if let Some(receiver) = self.parse_maybe_self_param()? {
+            return Ok(Some(receiver));
+        }
         if let Some((_, name)) = self.parse_ident() {
             if !self.eat(TokenType::Colon) {
                 return Err(());
@@ -215,6 +291,46 @@ impl Parser {
         }
     }
 
+    /// parses the receivers `self`, `&self`, `&mut self` and `self: Ty` which are sugar for
+    /// `self: Self`, `self: &Self` and `self: &mut Self`
+    fn parse_maybe_self_param(&mut self) -> Result<Option<(String, Ty)>, ()> {
+        let is_self = |token: &Token| matches!(token, Token::Keyword(_, Keyword::SelfLower));
+        let mutability = if self.check_kw(Keyword::SelfLower) {
+            None
+        } else if self.check(TokenType::And) && self.token_stream.look_ahead(1, is_self) {
+            self.advance();
+            Some(Mutability::Immut)
+        } else if self.check(TokenType::And)
+            && self.token_stream.look_ahead(1, |token| matches!(token, Token::Keyword(_, Keyword::Mut)))
+            && self.token_stream.look_ahead(2, is_self) {
+            self.advance();
+            self.advance();
+            Some(Mutability::Mut)
+        } else {
+            return Ok(None);
+        };
+        // skip the `self` keyword
+        self.advance();
+        let self_ty = Ty {
+            kind: TyKind::Owned(Box::new(OwnedTy {
+                name: "Self".to_string(),
+                generics: Box::new([]),
+            })),
+        };
+        let ty = match mutability {
+            Some(mutability) => Ty {
+                kind: TyKind::Ref(Box::new(RefTy {
+                    lt: None,
+                    mutability,
+                    ty: Box::new(self_ty),
+                })),
+            },
+            None if self.eat(TokenType::Colon) => self.parse_ty()?,
+            None => self_ty,
+        };
+        Ok(Some(("self".to_string(), ty)))
+    }
+
     fn parse_comma_separated(&mut self) -> Vec<AstNode> {
         let mut ret = vec![];
         while let Ok(item) = self.parse_expr() {
@@ -227,13 +343,25 @@ impl Parser {
     }
 
     fn parse_call(&mut self) -> Result<AstNode, ()> {
+        let start = self.token_stream.position() - 1;
         if let Some((_, name)) = self.parse_ident() {
+            // turbofish: `f::<u32>()`
+            let generics = if self.eat(TokenType::PathSep) {
+                if !self.eat(TokenType::OpenAngle) {
+                    return Err(());
+                }
+                self.parse_const_generic_vals_and_tys()?
+            } else {
+                Box::new([])
+            };
             if self.eat(TokenType::OpenParen) {
                 let args = self.parse_comma_separated();
                 if self.eat(TokenType::ClosedParen) {
                     return Ok(AstNode::CallExpr(CallExprNode {
                         callee: name,
+                        generics,
                         args: args.into_boxed_slice(),
+                        span: self.span_since(start),
                     }));
                 }
             }
@@ -242,18 +370,16 @@ impl Parser {
     }
 
     fn parse_bin_op(&mut self) -> Result<AstNode, ()> {
+        let start = self.token_stream.position() - 1;
         let lhs = self.parse_primary()?;
-        self.parse_bin_op_rhs(0, lhs)
+        self.parse_bin_op_rhs(0, lhs, start)
     }
 
-    fn parse_bin_op_rhs(&mut self, prec: usize, mut lhs: AstNode) -> Result<AstNode, ()> {
+    /// `start` is the position of the first token of `lhs`
+    fn parse_bin_op_rhs(&mut self, prec: usize, mut lhs: AstNode, start: usize) -> Result<AstNode, ()> {
         // If this is a binop, find its precedence.
         loop {
-            let bin_op = if let Token::BinOp(_, bin_op) = &self.curr {
-                Some(*bin_op)
-            } else {
-                None
-            };
+            let bin_op = self.curr_bin_op();
 
             // If this is a binop that binds at least as tightly as the current binop,
             // consume it, otherwise we are done.
@@ -265,17 +391,14 @@ impl Parser {
                 return Ok(lhs);
             }
             let bin_op = bin_op.unwrap();
-            self.eat(TokenType::BinOp);
+            self.advance();
 
+            let rhs_start = self.token_stream.position() - 1;
             let mut rhs = Some(self.parse_primary()?);
 
             // If BinOp binds less tightly with RHS than the operator after RHS, let
             // the pending operator take RHS as its LHS.
-            let next_bin_op = if let Token::BinOp(_, bin_op) = &self.curr {
-                Some(*bin_op)
-            } else {
-                None
-            };
+            let next_bin_op = self.curr_bin_op();
 
             match next_bin_op {
                 None => {
@@ -283,24 +406,95 @@ impl Parser {
                         lhs,
                         rhs: rhs.take().unwrap(),
                         op: bin_op,
+                        span: self.span_since(start),
                     })));
                 },
                 Some(next_bin_op) => {
                     if bin_op.precedence() < next_bin_op.precedence() {
                         rhs = rhs.map(|rhs| {
-                            self.parse_bin_op_rhs(bin_op.precedence() + 1, rhs).unwrap()
+                            self.parse_bin_op_rhs(bin_op.precedence() + 1, rhs, rhs_start).unwrap()
                         });
                     }
                     lhs = AstNode::BinaryExpr(Box::new(BinaryExprNode {
                         lhs,
                         rhs: rhs.take().unwrap(),
                         op: bin_op,
+                        span: self.span_since(start),
                     }));
                 }
             }
         }
     }
 
+    /// the binary operator `curr` represents (if any), `<` and `>` are lexed as angle brackets
+    fn curr_bin_op(&self) -> Option<BinOp> {
+        match &self.curr {
+            Token::BinOp(_, bin_op) => Some(*bin_op),
+            Token::OpenAngle(_) if self.angle_ops => Some(BinOp::Lt),
+            Token::ClosedAngle(_) if self.angle_ops => Some(BinOp::Gt),
+            _ => None,
+        }
+    }
+
+    /// parses attributes like `#[inline]` which apply to the item, field or statement following them
+    fn parse_outer_attrs(&mut self) -> Result<Vec<Attribute>, ()> {
+        self.parse_attrs(AttrStyle::Outer)
+    }
+
+    /// parses attributes like `#![allow(x)]` which apply to the crate or item they are contained in
+    fn parse_inner_attrs(&mut self) -> Result<Vec<Attribute>, ()> {
+        self.parse_attrs(AttrStyle::Inner)
+    }
+
+    fn parse_attrs(&mut self, style: AttrStyle) -> Result<Vec<Attribute>, ()> {
+        let mut attrs = vec![];
+        while self.check(TokenType::Hashtag)
+            && self.token_stream.look_ahead(1, |token| token.to_type() == TokenType::Exclamation) == (style == AttrStyle::Inner)
+        {
+            // skip the `#` (and the `!`)
+            self.advance();
+            if style == AttrStyle::Inner {
+                self.advance();
+            }
+            if !self.eat(TokenType::OpenBracket) {
+                return Err(());
+            }
+            let meta = self.parse_meta_item()?;
+            if !self.eat(TokenType::ClosedBracket) {
+                return Err(());
+            }
+            attrs.push(Attribute { style, meta });
+        }
+        Ok(attrs)
+    }
+
+    /// parses the contents of an attribute like `name`, `name = "val"` or `name(meta_item, ...)`
+    fn parse_meta_item(&mut self) -> Result<MetaItem, ()> {
+        let (_, name) = self.parse_ident().ok_or(())?;
+        if self.eat_bin_op(BinOp::Eq) {
+            if let Token::StrLit(_, val) = &self.curr {
+                let val = val.clone();
+                self.advance();
+                return Ok(MetaItem::NameValue(name, val));
+            }
+            return Err(());
+        }
+        if !self.eat(TokenType::OpenParen) {
+            return Ok(MetaItem::Word(name));
+        }
+        let mut items = vec![];
+        while !self.check(TokenType::ClosedParen) {
+            items.push(self.parse_meta_item()?);
+            if !self.eat(TokenType::Comma) {
+                break;
+            }
+        }
+        if !self.eat(TokenType::ClosedParen) {
+            return Err(());
+        }
+        Ok(MetaItem::List(name, items.into_boxed_slice()))
+    }
+
     fn parse_visibility(&mut self) -> Option<Visibility> {
         if self.eat_kw(Keyword::Pub) {
             Some(Visibility::Public)
@@ -321,6 +515,10 @@ impl Parser {
         if !self.eat(TokenType::Apostrophe) {
             return Ok(None);
         }
+        // `static` is a keyword and thus won't get lexed as an ident
+        if self.eat_kw(Keyword::Static) {
+            return Ok(Some(Lifetime::Static));
+        }
         if let Some((_, name)) = self.parse_ident() {
             Ok(Some(match name.as_str() {
                 "static" => Lifetime::Static,
@@ -350,29 +548,15 @@ impl Parser {
                     return Err(());
                 }
             } else if let Some((_, name)) = self.parse_ident() {
-                let traits = if self.eat(TokenType::Colon) {
-                    let mut traits = vec![];
-                    traits.push(self.parse_ty()?);
-
-                    while self.eat_bin_op(BinOp::Add) {
-                        let ty = self.parse_ty()?;
-                        traits.push(ty);
-                    }
-
-                    traits.into_boxed_slice()
-                } else {
-                    Box::new([])
-                };
+                let (required_traits, required_lifetimes) = self.parse_maybe_bounds()?;
 
                 generics.push(Generic::Type(GenericType {
                     name,
-                    required_traits: traits,
+                    required_traits,
+                    required_lifetimes,
                 }));
             } else {
-                let lt = self.parse_lt()?;
-                generics.push(Generic::Lifetime(GenericLifetime {
-                    lt, // FIXME: support constraints!
-                }));
+                generics.push(Generic::Lifetime(self.parse_lt_with_constraints()?));
             }
 
             if !self.eat(TokenType::Comma) {
@@ -392,6 +576,98 @@ impl Parser {
         Ok(generics.into_boxed_slice())
     }
 
+    /// parses bounds like `: Trait1 + Trait2` if there are any
+    fn parse_maybe_trait_bounds(&mut self) -> Result<Box<[Ty]>, ()> {
+        let (traits, lifetimes) = self.parse_maybe_bounds()?;
+        if !lifetimes.is_empty() {
+            // FIXME: support lifetime bounds on traits and associated types
+            return Err(());
+        }
+        Ok(traits)
+    }
+
+    /// parses bounds like `: Trait1 + Trait2 + 'a` if there are any
+    fn parse_maybe_bounds(&mut self) -> Result<Bounds, ()> {
+        if !self.eat(TokenType::Colon) {
+            return Ok((Box::new([]), Box::new([])));
+        }
+        self.parse_bounds()
+    }
+
+    fn parse_bounds(&mut self) -> Result<Bounds, ()> {
+        let mut traits = vec![];
+        let mut lifetimes = vec![];
+        loop {
+            if self.check(TokenType::Apostrophe) {
+                lifetimes.push(self.parse_lt()?);
+            } else {
+                traits.push(self.parse_ty()?);
+            }
+
+            if !self.eat_bin_op(BinOp::Add) {
+                break;
+            }
+        }
+
+        Ok((traits.into_boxed_slice(), lifetimes.into_boxed_slice()))
+    }
+
+    /// parses a lifetime with optional constraints like `'c: 'b + 'a`
+    fn parse_lt_with_constraints(&mut self) -> Result<GenericLifetime, ()> {
+        let lt = self.parse_lt()?;
+        let mut constraints = vec![];
+        if self.eat(TokenType::Colon) {
+            constraints.push(self.parse_lt()?);
+            while self.eat_bin_op(BinOp::Add) {
+                constraints.push(self.parse_lt()?);
+            }
+        }
+        Ok(GenericLifetime {
+            lt,
+            constraints: constraints.into_boxed_slice(),
+        })
+    }
+
+    /// parses a where clause like `where T: Clone + 'a, 'b: 'a, Vec<T>: Debug` if there is one
+    fn parse_maybe_where_clause(&mut self) -> Result<Box<[WherePredicate]>, ()> {
+        if !self.eat_kw(Keyword::Where) {
+            return Ok(Box::new([]));
+        }
+
+        let mut predicates = vec![];
+        // a where clause is always followed by either a body or a `;`
+        while !self.check(TokenType::OpenCurly) && !self.check(TokenType::Semi) {
+            if self.check(TokenType::Apostrophe) {
+                let lt = self.parse_lt_with_constraints()?;
+                if lt.constraints.is_empty() {
+                    return Err(());
+                }
+                predicates.push(WherePredicate::Lifetime(lt));
+            } else {
+                let ty = self.parse_ty()?;
+                if !self.eat(TokenType::Colon) {
+                    return Err(());
+                }
+                let (required_traits, required_lifetimes) = self.parse_bounds()?;
+                predicates.push(WherePredicate::Ty(TyWherePredicate {
+                    ty,
+                    required_traits,
+                    required_lifetimes,
+                }));
+            }
+
+            if !self.eat(TokenType::Comma) {
+                break;
+            }
+        }
+
+        if predicates.is_empty() {
+            return Err(());
+        }
+
+        Ok(predicates.into_boxed_slice())
+    }
+
     fn parse_maybe_const_generic_vals_and_tys(&mut self) -> Result<Box<[TyOrConstVal]>, ()> {
         if !self.eat(TokenType::OpenAngle) {
             return Ok(Box::new([]));
@@ -405,6 +681,13 @@ impl Parser {
         let mut generics = vec![];
 
         while !self.check(TokenType::ClosedAngle) {
+            if self.check(TokenType::Apostrophe) {
+                generics.push(TyOrConstVal::Lifetime(self.parse_lt()?));
+                if !self.eat(TokenType::Comma) {
+                    break;
+                }
+                continue;
+            }
             let ty_or_expr = self.parse_ty_or_expr(&[TokenType::Comma, TokenType::ClosedAngle])?;
 
             match ty_or_expr {
@@ -434,7 +717,7 @@ impl Parser {
         &mut self,
         next_expected: &[TokenType],
     ) -> Result<(Option<Ty>, Option<AstNode>), ()> {
-        if self.check(TokenType::Ident)
+        if self.check_kw(Keyword::Dyn) || self.check(TokenType::Ident)
             && self.token_stream.look_ahead(1, |token| {
                 next_expected.iter().any(|ty| ty == &token.to_type())
                     || token.to_type() == TokenType::OpenAngle
@@ -444,7 +727,10 @@ impl Parser {
             self.parse_ty().map(|ty| (Some(ty), None))
         } else {
             // expr
-            self.parse_expr().map(|node| (None, Some(node)))
+            let outer_angle_ops = std::mem::replace(&mut self.angle_ops, false);
+            let expr = self.parse_expr();
+            self.angle_ops = outer_angle_ops;
+            expr.map(|node| (None, Some(node)))
         }
     }
 
@@ -457,15 +743,86 @@ impl Parser {
             self.parse_array_ty().map(|array| Ty {
                 kind: TyKind::Array(Box::new(array)),
             })
-        } else {
-            self.parse_owned_ty().map(|owned| Ty {
-                kind: TyKind::Owned(Box::new(owned)),
+        } else if self.eat_kw(Keyword::Fn) {
+            self.parse_func_ty(FuncTyKind::Ptr).map(|func| Ty {
+                kind: TyKind::Func(Box::new(func)),
             })
+        } else if let Token::Ident(_, name) = &self.curr {
+            // handle the `Fn(u32) -> u32` sugar of the function traits
+            if let Some(kind) = FuncTyKind::from_trait_name(name) {
+                if self
+                    .token_stream
+                    .look_ahead(1, |token| token.to_type() == TokenType::OpenParen)
+                {
+                    self.advance();
+                    return self.parse_func_ty(kind).map(|func| Ty {
+                        kind: TyKind::Func(Box::new(func)),
+                    });
+                }
+            }
+            self.parse_path_ty()
+        } else if self.check_kw(Keyword::SelfUpper) {
+            self.parse_path_ty()
+        } else if self.eat_kw(Keyword::Dyn) {
+            let tait = self.parse_path_ty()?;
+            Ok(Ty {
+                kind: TyKind::Dyn(Box::new(DynTy { tait })),
+            })
+        } else {
+            Err(())
         }
     }
 
+    /// parses an owned type and any associated types projected out of it like `T::Item`
+    fn parse_path_ty(&mut self) -> Result<Ty, ()> {
+        let mut ty = self.parse_owned_ty().map(|owned| Ty {
+            kind: TyKind::Owned(Box::new(owned)),
+        })?;
+        while self.eat(TokenType::PathSep) {
+            let (_, name) = self.parse_ident().ok_or(())?;
+            ty = Ty {
+                kind: TyKind::Projection(Box::new(ProjectionTy { ty, name })),
+            };
+        }
+        Ok(ty)
+    }
+
+    /// assumes the `fn` keyword or the function trait's name was already skipped
+    fn parse_func_ty(&mut self, kind: FuncTyKind) -> Result<FuncTy, ()> {
+        if !self.eat(TokenType::OpenParen) {
+            return Err(());
+        }
+        let mut args = vec![];
+        while !self.check(TokenType::ClosedParen) {
+            args.push(self.parse_ty()?);
+            if !self.eat(TokenType::Comma) {
+                break;
+            }
+        }
+        if !self.eat(TokenType::ClosedParen) {
+            return Err(());
+        }
+
+        let ret = if self.eat(TokenType::Arrow) {
+            Some(self.parse_ty()?)
+        } else {
+            None
+        };
+
+        Ok(FuncTy {
+            kind,
+            args: args.into_boxed_slice(),
+            ret,
+        })
+    }
+
     fn parse_owned_ty(&mut self) -> Result<OwnedTy, ()> {
-        if let Some((_, name)) = self.parse_ident() {
+        let name = if self.eat_kw(Keyword::SelfUpper) {
+            Some("Self".to_string())
+        } else {
+            self.parse_ident().map(|(_, name)| name)
+        };
+        if let Some(name) = name {
             let generics = self.parse_maybe_const_generic_vals_and_tys()?;
 
             return Ok(OwnedTy { name, generics });
@@ -488,7 +845,8 @@ impl Parser {
         let ty = self.parse_ty()?;
 
         let amount = if self.eat(TokenType::Semi) {
-            Some(self.parse_number_expr()?)
+            // the amount gets evaluated once the consts it may refer to are known
+            Some(self.parse_bin_op()?)
         } else {
             None
         };
@@ -501,6 +859,7 @@ impl Parser {
     }
 
     fn parse_array_constructor(&mut self) -> Result<AstNode, ()> {
+        let start = self.token_stream.position() - 1;
         if !self.eat(TokenType::OpenBracket) {
             return Err(());
         }
@@ -538,13 +897,17 @@ impl Parser {
             TokenType::Semi => {
                 // we skip the `;` token
                 self.advance();
-                let cnt = self.parse_number_expr()?;
+                let cnt = self.parse_bin_op()?;
 
                 if !self.eat(TokenType::ClosedBracket) {
                     return Err(());
                 }
 
-                ArrayInst::Short(Box::new(ArrayInstShort { val, amount: cnt }))
+                ArrayInst::Short(Box::new(ArrayInstShort {
+                    val,
+                    amount: cnt,
+                    span: self.span_since(start),
+                }))
             }
             _ => {
                 return Err(());
@@ -554,10 +917,88 @@ impl Parser {
         Ok(AstNode::ArrayInst(inst))
     }
 
+    fn parse_closure(&mut self) -> Result<AstNode, ()> {
+        let start = self.token_stream.position() - 1;
+        let capture = if self.eat_kw(Keyword::Move) {
+            CaptureBy::Value
+        } else {
+            CaptureBy::Ref
+        };
+
+        let mut args = vec![];
+        // `||` gets lexed as a single binop, so we have to handle closures without args separately
+        if !self.eat_bin_op(BinOp::OrOr) {
+            if !self.eat(TokenType::Or) {
+                return Err(());
+            }
+            while let Some((_, name)) = self.parse_ident() {
+                let ty = if self.eat(TokenType::Colon) {
+                    Some(self.parse_ty()?)
+                } else {
+                    None
+                };
+                args.push((name, ty));
+                if !self.eat(TokenType::Comma) {
+                    break;
+                }
+            }
+            if !self.eat(TokenType::Or) {
+                return Err(());
+            }
+        }
+
+        let (ret, body) = if self.eat(TokenType::Arrow) {
+            // closures with an explicit return type require a block as their body
+            let ret = self.parse_ty()?;
+            let body = self.parse_block_no_attr()?;
+            (Some(ret), AstNode::Block(body))
+        } else {
+            (None, self.parse_expr()?)
+        };
+
+        Ok(AstNode::Closure(Box::new(ClosureNode {
+            capture,
+            args: args.into_boxed_slice(),
+            ret,
+            body,
+            span: self.span_since(start),
+        })))
+    }
+
     fn parse_stmt_or_expr(&mut self) -> Result<StmtKind, ()> {
+        let attrs = self.parse_outer_attrs()?;
         // handle `let x = y;`
         if self.eat_kw(Keyword::Let) {
-            return self.parse_let();
+            return self.parse_let(attrs);
+        }
+        if self.check_item() {
+            return self.parse_glob(attrs).map(StmtKind::Item);
+        }
+        if !attrs.is_empty() {
+            // FIXME: support attributes on expression statements
+            return Err(());
+        }
+        if self.check_macro_rules() {
+            self.parse_macro_rules()?;
+            return Ok(StmtKind::Empty);
+        }
+        if self.check_macro_call() && self.expand_macros {
+            // macros in statement position may expand to multiple statements,
+            // so only wrap them if they are followed by an operator or similar
+            let wrap = !self.token_stream.look_ahead(self.macro_call_len(), |token| {
+                matches!(token.to_type(), TokenType::Semi | TokenType::ClosedCurly)
+            });
+            if !self.expand_macro_call(wrap)? {
+                self.eat(TokenType::Semi);
+                return Ok(StmtKind::Empty);
+            }
+            if self.check(TokenType::ClosedCurly) {
+                return Ok(StmtKind::Empty);
+            }
+            return self.parse_stmt_or_expr();
+        }
+        if self.eat(TokenType::Semi) {
+            return Ok(StmtKind::Empty);
         }
         // FIXME: handle `x = y;`
         let expr = self.parse_expr()?;
@@ -571,14 +1012,33 @@ impl Parser {
         if !self.eat(TokenType::OpenCurly) {
             return Err(());
         }
+        self.parse_block_contents()
+    }
+
+    /// parses a block whose inner attributes (like `#![allow(x)]`) belong to the surrounding item
+    fn parse_block_with_inner_attrs(&mut self, attrs: &mut Vec<Attribute>) -> Result<Block, ()> {
+        if !self.eat(TokenType::OpenCurly) {
+            return Err(());
+        }
+        attrs.extend(self.parse_inner_attrs()?);
+        self.parse_block_contents()
+    }
+
+    /// parses the statements of a block, assumes the `{` was already skipped
+    fn parse_block_contents(&mut self) -> Result<Block, ()> {
+        // macros defined inside the block are only visible until its end
+        let macros = self.macros.clone();
+        let block = self.parse_block_stmts();
+        self.macros = macros;
+        block
+    }
+
+    fn parse_block_stmts(&mut self) -> Result<Block, ()> {
         let mut stmts = vec![];
         while self.curr.to_type() != TokenType::ClosedCurly {
             let combined = self.parse_stmt_or_expr()?;
             match combined {
-                StmtKind::Item(_) => {
-                    return Err(());
-                }
-                StmtKind::Semi(_) | StmtKind::LocalAssign(_) | StmtKind::Empty => {
+                StmtKind::Item(_) | StmtKind::Semi(_) | StmtKind::LocalAssign(_) | StmtKind::Empty => {
                     stmts.push(combined);
                 }
                 StmtKind::Expr(_) => {
@@ -608,11 +1068,12 @@ impl Parser {
         }
     }
 
-    fn parse_static(&mut self, visibility: Option<Visibility>) -> Result<ItemKind, ()> {
+    fn parse_static(&mut self, attrs: Vec<Attribute>, visibility: Option<Visibility>) -> Result<ItemKind, ()> {
         // skip `static` keyword
         self.advance();
         let mutability = self.parse_mutability();
 
+        let name_start = self.token_stream.position() - 1;
         if let Some((_, name)) = self.parse_ident() {
             let ty = if self.eat(TokenType::Colon) {
                 self.parse_ty()
@@ -621,12 +1082,13 @@ impl Parser {
                 Err(())
             }?;
 
-            let rhs = self.parse_bin_op_rhs(0, AstNode::Ident(name))?;
+            let rhs = self.parse_bin_op_rhs(0, AstNode::Ident(name), name_start)?;
             if !self.eat(TokenType::Semi) {
                 return Err(());
             }
 
             Ok(ItemKind::StaticVal(Box::new(StaticValNode {
+                attrs: attrs.into_boxed_slice(),
                 ty,
                 mutability,
                 val: rhs,
@@ -637,10 +1099,12 @@ impl Parser {
         }
     }
 
-    fn parse_const(&mut self, visibility: Option<Visibility>) -> Result<ItemKind, ()> {
+    fn parse_const(&mut self, attrs: Vec<Attribute>, visibility: Option<Visibility>) -> Result<ItemKind, ()> {
+        let start = self.token_stream.position() - 1;
         // skip the `const` keyword
         self.advance();
 
+        let name_start = self.token_stream.position() - 1;
         if let Some((_, name)) = self.parse_ident() {
             let ty = if self.eat(TokenType::Colon) {
                 self.parse_ty()
@@ -649,15 +1113,17 @@ impl Parser {
                 Err(())
             }?;
 
-            let rhs = self.parse_bin_op_rhs(0, AstNode::Ident(name))?;
+            let rhs = self.parse_bin_op_rhs(0, AstNode::Ident(name), name_start)?;
             if !self.eat(TokenType::Semi) {
                 return Err(());
             }
 
             Ok(ItemKind::ConstVal(Box::new(ConstValNode {
+                attrs: attrs.into_boxed_slice(),
                 ty,
                 val: rhs,
                 visibility,
+                span: self.span_since(start),
             })))
         } else {
             Err(())
@@ -670,12 +1136,28 @@ impl Parser {
                 return Err(());
             }
             let mut fields = vec![];
-            while let Some((_, name)) = self.parse_ident() {
-                if !self.eat(TokenType::Colon) {
-                    return Err(());
+            let mut base = None;
+            loop {
+                // functional update syntax: `..other`
+                if self.eat(TokenType::Dot) {
+                    if !self.eat(TokenType::Dot) {
+                        return Err(());
+                    }
+                    base = Some(Box::new(self.parse_expr()?));
+                    // the base has to be the last part of the constructor
+                    break;
                 }
-
-                let val = self.parse_expr()?;
+                let (_, name) = if let Some(field) = self.parse_ident() {
+                    field
+                } else {
+                    break;
+                };
+                let val = if self.eat(TokenType::Colon) {
+                    self.parse_expr()?
+                } else {
+                    // field shorthand: `Point { x }`
+                    AstNode::Ident(name.clone())
+                };
                 fields.push((name, val));
 
                 if !self.eat(TokenType::Comma) {
@@ -688,32 +1170,40 @@ impl Parser {
             return Ok(AstNode::StructConstructor(StructConstructor {
                 name,
                 fields: fields.into_boxed_slice(),
+                base,
             }));
         } else {
             Err(())
         }
     }
 
-    fn parse_struct_def(&mut self, visibility: Option<Visibility>) -> Result<ItemKind, ()> {
+    fn parse_struct_def(&mut self, attrs: Vec<Attribute>, visibility: Option<Visibility>) -> Result<ItemKind, ()> {
         // skip the `struct` keyword
         self.advance();
         if let Some((_, name)) = self.parse_ident() {
             let generics = self.parse_maybe_generics_definition()?;
+            let where_clause = self.parse_maybe_where_clause()?;
 
             if !self.eat(TokenType::OpenCurly) {
                 return Err(());
             }
 
-            fn parse_param_with_vis(
+            fn parse_field(
                 parser: &mut Parser,
-            ) -> Result<Option<(Visibility, String, Ty)>, ()> {
+            ) -> Result<Option<StructFieldDef>, ()> {
+                let attrs = parser.parse_outer_attrs()?;
                 let vis = parser.parse_visibility();
 
                 let param = parser.parse_param()?;
-                if let Some(param) = param {
-                    Ok(Some((vis.unwrap_or(Visibility::Private), param.0, param.1)))
+                if let Some((name, ty)) = param {
+                    Ok(Some(StructFieldDef {
+                        attrs: attrs.into_boxed_slice(),
+                        visibility: vis.unwrap_or(Visibility::Private),
+                        name,
+                        ty,
+                    }))
                 } else {
-                    if vis.is_none() {
+                    if vis.is_none() && attrs.is_empty() {
                         Ok(None)
                     } else {
                         Err(())
@@ -722,12 +1212,8 @@ impl Parser {
             }
 
             let mut fields = vec![];
-            while let Some((visibility, name, ty)) = parse_param_with_vis(self)? {
-                fields.push(StructFieldDef {
-                    visibility,
-                    name,
-                    ty,
-                });
+            while let Some(field) = parse_field(self)? {
+                fields.push(field);
                 if !self.eat(TokenType::Comma) {
                     break;
                 }
@@ -738,9 +1224,11 @@ impl Parser {
             }
 
             Ok(ItemKind::StructDef(StructDef {
+                attrs: attrs.into_boxed_slice(),
                 visibility: visibility.unwrap_or(Visibility::Private),
                 name,
                 generics,
+                where_clause,
                 fields: fields.into_boxed_slice(),
             }))
         } else {
@@ -748,35 +1236,77 @@ impl Parser {
         }
     }
 
-    fn parse_trait_def(&mut self, visibility: Option<Visibility>) -> Result<ItemKind, ()> {
+    fn parse_trait_def(&mut self, mut attrs: Vec<Attribute>, visibility: Option<Visibility>) -> Result<ItemKind, ()> {
         // skip the `trait` keyword
         self.advance();
         if let Some((_, name)) = self.parse_ident() {
             let generics = self.parse_maybe_generics_definition()?;
-            let req_sub_traits = if self.eat(TokenType::Colon) {
-                let mut sub_traits = vec![];
-                sub_traits.push(self.parse_ty()?);
-
-                while self.eat_bin_op(BinOp::Add) {
-                    sub_traits.push(self.parse_ty()?);
-                }
-                sub_traits.into_boxed_slice()
-            } else {
-                Box::new([])
-            };
+            let req_sub_traits = self.parse_maybe_trait_bounds()?;
+            let where_clause = self.parse_maybe_where_clause()?;
 
             if !self.eat(TokenType::OpenCurly) {
                 return Err(());
             }
+            attrs.extend(self.parse_inner_attrs()?);
 
             let mut methods = vec![];
-            while self.check_kw(Keyword::Fn) {
-                let header = self.parse_function_header()?;
+            let mut assoc_tys = vec![];
+            let mut assoc_consts = vec![];
+            loop {
+                let mut item_attrs = self.parse_outer_attrs()?;
+                if self.check_kw(Keyword::Fn) {
+                    let header = self.parse_function_header()?;
+
+                    // methods can either be required or provide a default body
+                    let default = if self.eat(TokenType::Semi) {
+                        None
+                    } else {
+                        Some(self.parse_block_with_inner_attrs(&mut item_attrs)?)
+                    };
+                    methods.push(TraitMethod {
+                        attrs: item_attrs.into_boxed_slice(),
+                        header,
+                        default,
+                    });
+                } else if self.eat_kw(Keyword::Type) {
+                    let (_, name) = self.parse_ident().ok_or(())?;
+                    let required_traits = self.parse_maybe_trait_bounds()?;
+
+                    if !self.eat(TokenType::Semi) {
+                        return Err(());
+                    }
+                    assoc_tys.push(TraitAssocTy {
+                        attrs: item_attrs.into_boxed_slice(),
+                        name,
+                        required_traits,
+                    });
+                } else if self.eat_kw(Keyword::Const) {
+                    let (_, name) = self.parse_ident().ok_or(())?;
+                    if !self.eat(TokenType::Colon) {
+                        return Err(());
+                    }
+                    let ty = self.parse_ty()?;
+                    let default = if self.eat_bin_op(BinOp::Eq) {
+                        Some(self.parse_expr()?)
+                    } else {
+                        None
+                    };
 
-                if !self.eat(TokenType::Semi) {
+                    if !self.eat(TokenType::Semi) {
+                        return Err(());
+                    }
+                    assoc_consts.push(TraitAssocConst {
+                        attrs: item_attrs.into_boxed_slice(),
+                        name,
+                        ty,
+                        default,
+                    });
+                } else if item_attrs.is_empty() {
+                    break;
+                } else {
+                    // attributes have to be followed by an item
                     return Err(());
                 }
-                methods.push(header);
             }
 
             if !self.eat(TokenType::ClosedCurly) {
@@ -784,45 +1314,92 @@ impl Parser {
             }
 
             Ok(ItemKind::TraitDef(TraitDef {
+                attrs: attrs.into_boxed_slice(),
                 visibility: visibility.unwrap_or(Visibility::Private),
                 name,
                 generics,
                 req_sub_traits,
+                where_clause,
                 methods: methods.into_boxed_slice(),
+                assoc_tys: assoc_tys.into_boxed_slice(),
+                assoc_consts: assoc_consts.into_boxed_slice(),
+            }))
+        } else {
+            Err(())
+        }
+    }
+
+    fn parse_ty_alias(&mut self, attrs: Vec<Attribute>, visibility: Option<Visibility>) -> Result<ItemKind, ()> {
+        // skip the `type` keyword
+        self.advance();
+        if let Some((_, name)) = self.parse_ident() {
+            let generics = self.parse_maybe_generics_definition()?;
+
+            if !self.eat_bin_op(BinOp::Eq) {
+                return Err(());
+            }
+            let ty = self.parse_ty()?;
+
+            if !self.eat(TokenType::Semi) {
+                return Err(());
+            }
+
+            Ok(ItemKind::TyAlias(TyAlias {
+                attrs: attrs.into_boxed_slice(),
+                visibility: visibility.unwrap_or(Visibility::Private),
+                name,
+                generics,
+                ty,
             }))
         } else {
             Err(())
         }
     }
 
-    fn parse_impl_block(&mut self) -> Result<ItemKind, ()> {
+    fn parse_impl_block(&mut self, mut attrs: Vec<Attribute>) -> Result<ItemKind, ()> {
+        let start = self.token_stream.position() - 1;
         // skip the `impl` keyword
         self.advance();
 
         let generics = self.parse_maybe_generics_definition()?;
+        let negative = self.eat(TokenType::Exclamation);
         let ty = self.parse_ty()?;
         let (impl_trait, ty) = if self.eat_kw(Keyword::For) {
             let tait = self.parse_ty()?;
             (Some(ty), tait)
+        } else if negative {
+            // only trait impls can be negative
+            return Err(());
         } else {
             (None, ty)
         };
+        let where_clause = self.parse_maybe_where_clause()?;
+        let span = self.span_since(start);
 
         if !self.eat(TokenType::OpenCurly) {
             return Err(());
         }
 
+        attrs.extend(self.parse_inner_attrs()?);
+
         let mut methods = vec![];
-        let mut visibility = self.parse_visibility();
-        // collect all functions inside the impl block
-        while self.check_kw(Keyword::Fn) {
-            let function = self.parse_function(visibility.take())?;
-            methods.push(function);
-            visibility = self.parse_visibility();
-        }
-        // check for invalid trailing visibility modifier
-        if visibility.is_some() {
-            return Err(());
+        // collect all functions (and associated types and consts) inside the impl block
+        loop {
+            let item_attrs = self.parse_outer_attrs()?;
+            let visibility = self.parse_visibility();
+            let item = if self.check_function() {
+                self.parse_function(item_attrs, visibility)?
+            } else if self.check_kw(Keyword::Type) {
+                self.parse_ty_alias(item_attrs, visibility)?
+            } else if self.check_kw(Keyword::Const) {
+                self.parse_const(item_attrs, visibility)?
+            } else if visibility.is_none() && item_attrs.is_empty() {
+                break;
+            } else {
+                // check for invalid trailing visibility modifiers and attributes
+                return Err(());
+            };
+            methods.push(item);
         }
 
         if !self.eat(TokenType::ClosedCurly) {
@@ -830,14 +1407,18 @@ impl Parser {
         }
 
         Ok(ItemKind::StructImpl(AdtImpl {
+            attrs: attrs.into_boxed_slice(),
             ty,
             impl_trait,
+            negative,
             generics,
+            where_clause,
             methods: methods.into_boxed_slice(),
+            span,
         }))
     }
 
-    fn parse_glob(&mut self) -> Result<ItemKind, ()> {
+    fn parse_glob(&mut self, attrs: Vec<Attribute>) -> Result<ItemKind, ()> {
         let visibility = self.parse_visibility()/*.unwrap_or(Visibility::Private)*/;
 
         match self.curr {
@@ -850,27 +1431,18 @@ impl Parser {
                         // FIXME: error
                         Err(())
                     }
-                    Keyword::Static => self.parse_static(visibility),
-                    Keyword::Const => {
-                        if self.token_stream.look_ahead(1, |x| x.to_type() == TokenType::Ident) {
-                            self.parse_const(visibility)
-                        } else {
-                            // FIXME: parse function attrs and then the function itself
-                            println!("don't parse const!");
-                            Err(())
-                        }
-                    }
-                    Keyword::Rt => Err(()), // FIXME: ?
-                    Keyword::Fn => self.parse_function(visibility),
+                    Keyword::Static => self.parse_static(attrs, visibility),
+                    Keyword::Const | Keyword::Rt | Keyword::Fn if self.check_function() => self.parse_function(attrs, visibility),
+                    Keyword::Const => self.parse_const(attrs, visibility),
                     Keyword::Enum => Err(()),
-                    Keyword::Struct => self.parse_struct_def(visibility),
+                    Keyword::Struct => self.parse_struct_def(attrs, visibility),
                     Keyword::Mod => Err(()),
-                    Keyword::Impl => self.parse_impl_block(),
+                    Keyword::Impl => self.parse_impl_block(attrs),
                     Keyword::Async => Err(()),
                     Keyword::Unsafe => Err(()),
                     Keyword::Extern => Err(()),
-                    Keyword::Trait => self.parse_trait_def(visibility),
-                    Keyword::Type => Err(()),
+                    Keyword::Trait => self.parse_trait_def(attrs, visibility),
+                    Keyword::Type => self.parse_ty_alias(attrs, visibility),
                     _ => Err(()), // FIXME: error
                 };
             }
@@ -891,12 +1463,32 @@ impl Parser {
     }
 
     fn parse_primary(&mut self) -> Result<AstNode, ()> {
-        println!("curr: {:?}", self.curr);
         match &self.curr {
             Token::Ident(_, content) => {
+                if self.check_macro_call() {
+                    if !self.expand_macros {
+                        // this is only used to find out where an expression ends, so the result doesn't matter
+                        let name = content.clone();
+                        self.advance();
+                        self.advance();
+                        self.parse_delimited_tokens()?;
+                        return Ok(AstNode::Ident(name));
+                    }
+                    if !self.expand_macro_call(true)? {
+                        // the call was already reported, so parsing continues with an empty block in its place
+                        return Ok(AstNode::Block(Block {
+                            modifiers: BlockModifiers {},
+                            stmts: Box::new([]),
+                        }));
+                    }
+                    return self.parse_primary();
+                }
                 if self
                     .token_stream
                     .look_ahead(1, |token| token.to_type() == TokenType::OpenParen)
+                    || self
+                        .token_stream
+                        .look_ahead(1, |token| token.to_type() == TokenType::PathSep)
                 {
                     self.parse_call() // FIXME: handle errors properly!
                                       /*} else if self.token_stream.look_ahead(1, |token| token.to_type() == TokenType::Dot) {
@@ -914,11 +1506,27 @@ impl Parser {
                     Ok(AstNode::Ident(content))
                 }
             }
+            Token::Keyword(_, Keyword::SelfLower) => {
+                self.advance();
+                Ok(AstNode::Ident("self".to_string()))
+            }
+            Token::Keyword(_, kw @ (Keyword::True | Keyword::False)) => {
+                let val = *kw == Keyword::True;
+                self.advance();
+                Ok(AstNode::BoolLit(val))
+            }
             //#!Token::Keyword(_, _) => {}
-            // Token::StrLit(_, _) => {}
+            Token::StrLit(_, content) => {
+                let content = content.clone();
+                self.advance();
+                Ok(AstNode::StrLit(content))
+            }
             Token::NumLit(_, _) => self.parse_number_expr(),
             Token::OpenParen(_) => self.parse_paren_expr(),
             Token::OpenBracket(_) => self.parse_array_constructor(),
+            Token::Or(_) | Token::BinOp(_, BinOp::OrOr) | Token::Keyword(_, Keyword::Move) => {
+                self.parse_closure()
+            }
             //#!Token::OpenCurly(_) => {}
             // Token::OpenBracket(_) => {}
             // Token::Eq(_) => {}
@@ -945,9 +1553,29 @@ impl Parser {
         self.parse_bin_op()
     }
 
-    fn parse_item(&mut self) -> Result<ItemKind, ()> {
+    /// returns `None` for macro definitions and calls as they don't result in items on their own
+    fn parse_item(&mut self) -> Result<Option<ItemKind>, ()> {
+        let attrs = self.parse_outer_attrs()?;
+        if self.check_macro_rules() {
+            self.parse_macro_rules()?;
+            return Ok(None);
+        }
+        if self.check_macro_call() {
+            self.expand_macro_call(false)?;
+            return Ok(None);
+        }
+        // enums can't be parsed yet, so they are skipped as a whole
+        if self.check_enum() {
+            self.skip_enum(&attrs)?;
+            return Ok(None);
+        }
         match self.curr {
-            Token::Keyword(_, _) => self.parse_glob(),
+            Token::Keyword(_, _) => self.parse_glob(attrs).map(Some),
+            // the trailing `;` of macro calls like `foo!();`
+            Token::Semi(_) => {
+                self.advance();
+                Ok(None)
+            }
             // Token::StrLit(_, _) => {}
             //#!Token::OpenCurly(_) => {}
             // Token::OpenBracket(_) => {}
@@ -965,6 +1593,208 @@ impl Parser {
         }
     }
 
+    /// whether the current token starts an enum, that is `enum` or `pub enum`
+    fn check_enum(&self) -> bool {
+        self.check_kw(Keyword::Enum) || (self.check_kw(Keyword::Pub)
+            && self.token_stream.look_ahead(1, |token| matches!(token, Token::Keyword(_, Keyword::Enum))))
+    }
+
+    /// skips an enum including its variants and rejects the traits it derives
+    // FIXME: parse enums once they can be type checked
+    fn skip_enum(&mut self, attrs: &[Attribute]) -> Result<(), ()> {
+        self.parse_visibility();
+        let start = self.token_stream.position() - 1;
+        // skip the `enum` keyword
+        self.advance();
+        if self.parse_ident().is_none() {
+            return Err(());
+        }
+        let (header, spans) = render_tokens(self.token_stream.tokens(start..start + 2));
+
+        // skip the generics and the where clause (if any) and then the variants
+        while !self.check(TokenType::OpenCurly) {
+            if self.curr.to_type() == TokenType::EOF {
+                return Err(());
+            }
+            self.advance();
+        }
+        let mut depth = 0;
+        loop {
+            match self.curr.to_type() {
+                TokenType::OpenCurly => depth += 1,
+                TokenType::ClosedCurly => depth -= 1,
+                TokenType::EOF => return Err(()),
+                _ => {}
+            }
+            self.advance();
+            if depth == 0 {
+                break;
+            }
+        }
+
+        let derives = attr_list_args(attrs, "derive");
+        if !derives.is_empty() {
+            let derives = derives.iter().map(|derive| derive.to_string()).collect::<Vec<_>>();
+            let mut diagnostic = self.diagnostics.diagnostic(header);
+            diagnostic.error_spanned(format!("`#[derive({})]` is not supported on enums yet", derives.join(", ")), spans[1]);
+            diagnostic.note("only structs can derive traits for now".to_string());
+            diagnostic.build();
+        }
+        Ok(())
+    }
+
+    /// whether the current token starts an item, e.g. a function defined inside of a block
+    fn check_item(&self) -> bool {
+        matches!(&self.curr, Token::Keyword(_, Keyword::Pub | Keyword::Static | Keyword::Const | Keyword::Rt | Keyword::Fn
+            | Keyword::Struct | Keyword::Impl | Keyword::Trait | Keyword::Type))
+    }
+
+    fn check_macro_call(&self) -> bool {
+        self.check(TokenType::Ident) && self.token_stream.look_ahead(1, |token| token.to_type() == TokenType::Exclamation)
+    }
+
+    fn check_macro_rules(&self) -> bool {
+        matches!(&self.curr, Token::Ident(_, name) if name == "macro_rules") && self.check_macro_call()
+    }
+
+    /// parses `macro_rules! name { ... }` and makes the macro available for the rest of the current scope
+    fn parse_macro_rules(&mut self) -> Result<(), ()> {
+        // skip `macro_rules!`
+        self.advance();
+        self.advance();
+        let (_, name) = self.parse_ident().ok_or(())?;
+        let delim = Delim::from_open(&self.curr).ok_or(())?;
+        let body = self.parse_delimited_tokens()?;
+        if delim != Delim::Curly && !self.eat(TokenType::Semi) {
+            return Err(());
+        }
+        match MacroDef::new(name.clone(), &body) {
+            Ok(def) => {
+                self.macros.insert(name, Rc::new(def));
+            }
+            Err(error) => {
+                let source = format!("macro_rules! {}", name);
+                let name_span = Span::multi_token(source.len() - name.len(), source.len());
+                let mut diagnostic = self.diagnostics.diagnostic(source);
+                diagnostic.error_spanned(error, name_span);
+                diagnostic.build();
+            }
+        }
+        Ok(())
+    }
+
+    /// collects the tokens between the current (opening) delimiter and its closing counterpart
+    /// and skips past the closing delimiter
+    fn parse_delimited_tokens(&mut self) -> Result<Vec<Token>, ()> {
+        let delim = Delim::from_open(&self.curr).ok_or(())?;
+        self.advance();
+        let mut depth = 0;
+        let mut tokens = vec![];
+        while depth > 0 || !delim.closes(&self.curr) {
+            match self.curr.to_type() {
+                TokenType::EOF => return Err(()),
+                TokenType::OpenParen | TokenType::OpenBracket | TokenType::OpenCurly => depth += 1,
+                TokenType::ClosedParen | TokenType::ClosedBracket | TokenType::ClosedCurly => depth -= 1,
+                _ => {}
+            }
+            tokens.push(self.curr.clone());
+            self.advance();
+        }
+        self.advance();
+        Ok(tokens)
+    }
+
+    /// the number of tokens the macro call at the current token consists of
+    fn macro_call_len(&self) -> usize {
+        let mut depth = 0;
+        let mut len = 2;
+        while self.token_stream.look_ahead(len, |token| {
+            match token.to_type() {
+                TokenType::OpenParen | TokenType::OpenBracket | TokenType::OpenCurly => depth += 1,
+                TokenType::ClosedParen | TokenType::ClosedBracket | TokenType::ClosedCurly => depth -= 1,
+                _ => {}
+            }
+            depth > 0
+        }) {
+            len += 1;
+        }
+        len + 1
+    }
+
+    /// replaces the macro call at the current token with its expansion and continues at the start of the expansion,
+    /// if `wrap` is set the expansion is wrapped in parens so it can be used as an expression.
+    /// calls which can't be expanded get reported and skipped, in which case `false` is returned
+    fn expand_macro_call(&mut self, wrap: bool) -> Result<bool, ()> {
+        // the index of the current token
+        let start = self.token_stream.position() - 1;
+        let (call_site, name) = self.parse_ident().ok_or(())?;
+        // skip `!`
+        self.advance();
+        let input = self.parse_delimited_tokens()?;
+        let end = self.token_stream.position() - 1;
+
+        let parent = self.token_stream.expansion_at(start);
+        let mut depth = 0;
+        let mut curr = parent;
+        while let Some(expn) = curr {
+            depth += 1;
+            curr = self.expansions[expn].parent;
+        }
+
+        // errors point at the name of the macro in the call, e.g. `square!` in `square!(1 + 2)`
+        let (call, spans) = render_tokens(self.token_stream.tokens(start..end));
+        let name_span = Span::multi_token(spans[0].start, spans[1].end);
+        let def = if let Some(def) = self.macros.get(&name) {
+            def.clone()
+        } else {
+            let mut diagnostic = self.diagnostics.diagnostic(call);
+            diagnostic.error_spanned(format!("cannot find macro `{}` in this scope", name), name_span);
+            diagnostic.build();
+            return Ok(false);
+        };
+        if depth >= MACRO_RECURSION_LIMIT {
+            let (def_source, def_span) = def.def_source();
+            let mut diagnostic = self.diagnostics.diagnostic(call);
+            diagnostic.error_spanned(format!("recursion limit reached while expanding `{}!`", name), name_span);
+            diagnostic.note_spanned(format!("the macro `{}` is defined here", name), def_source, def_span);
+            diagnostic.note(format!("the recursion limit is {} nested expansions", MACRO_RECURSION_LIMIT));
+            diagnostic.build();
+            return Ok(false);
+        }
+
+        let expn = self.expansions.len();
+        self.expansions.push(ExpnData {
+            parent,
+        });
+        let mut tokens = match def.expand(&input, expn) {
+            Ok(tokens) => tokens,
+            Err(error) => {
+                let mut diagnostic = self.diagnostics.diagnostic(call);
+                diagnostic.error_spanned(error.msg, name_span);
+                match error.rule {
+                    Some(rule) => {
+                        let (rule_source, matcher_span) = def.rule_source(rule);
+                        diagnostic.note_spanned(format!("while expanding this rule of `{}!`", name), rule_source, matcher_span);
+                    }
+                    None => {
+                        let (def_source, def_span) = def.def_source();
+                        diagnostic.note_spanned(format!("the macro `{}` is defined here", name), def_source, def_span);
+                    }
+                }
+                diagnostic.build();
+                return Ok(false);
+            }
+        };
+        if wrap {
+            tokens.insert(0, Token::OpenParen(call_site.start.into()));
+            tokens.push(Token::ClosedParen(call_site.start.into()));
+        }
+        self.token_stream.splice(start..end, tokens, expn);
+        self.token_stream.set_position(start);
+        self.advance();
+        Ok(true)
+    }
+
     fn check(&self, token: TokenType) -> bool {
         self.curr.to_type() == token
     }
@@ -1006,6 +1836,17 @@ impl Parser {
         false
     }
 
+    /// the span from the token at `start` up to the end of the token before the current one,
+    /// tokens produced by macros point into the macro's definition, so items starting in an expansion don't get a span
+    fn span_since(&self, start: usize) -> Span {
+        let end = self.token_stream.position() - 1;
+        if start >= end || self.token_stream.expansion_at(start).is_some() {
+            return Span::NONE;
+        }
+        let tokens = self.token_stream.tokens(start..end);
+        Span::multi_token(tokens[0].span().start, tokens[tokens.len() - 1].span().end)
+    }
+
     fn advance(&mut self) {
         if let Some(next) = self.token_stream.get_next() {
             self.curr = next.clone();
@@ -1025,6 +1866,20 @@ impl Parser {
 
 const EOF_TOKEN: Token = Token::EOF(FixedTokenSpan::<1>::NONE);
 
+/// returns the number of tokens at the start of `tokens` which form a fragment of the given kind
+pub(crate) fn parse_fragment(mut tokens: Vec<Token>, kind: FragmentKind) -> Option<usize> {
+    tokens.push(EOF_TOKEN);
+    let mut parser = Parser::with_macros(TokenStream::new(tokens), MacroTable::new(), false);
+    let parsed = match kind {
+        FragmentKind::Expr => parser.parse_expr().is_ok(),
+        FragmentKind::Ty => parser.parse_ty().is_ok(),
+        _ => unreachable!(),
+    };
+    // the current token is the first one that isn't part of the fragment
+    let consumed = parser.token_stream.position() - 1;
+    (parsed && consumed > 0).then_some(consumed)
+}
+
 #[cfg(test)]
 fn test_file<F: FnOnce(Vec<Token>, Crate) -> bool>(path: &str, assumed: F) -> bool {
     let file = fs::read_to_string(path).unwrap();
@@ -1035,6 +1890,61 @@ fn test_file<F: FnOnce(Vec<Token>, Crate) -> bool>(path: &str, assumed: F) -> bo
     assumed(lexed, krate)
 }
 
+/// parses the file at `path` and returns the crate together with the messages of the parser's diagnostics
+/// and the code they point at
+#[cfg(test)]
+fn parse_file_spanned(path: &str) -> (Crate, Vec<(String, Option<String>)>) {
+    let file = fs::read_to_string(path).unwrap();
+    let mut parser = Parser::new(TokenStream::new(lexer::lex(file).unwrap()));
+    let krate = parser.parse_crate().unwrap();
+    (krate, parser.diagnostics().spanned_messages())
+}
+
+/// the result of parsing and type checking a test file
+#[cfg(test)]
+struct Checked {
+    krate: Crate,
+    ctx: crate::tyck::TyCtx,
+    /// the messages of all diagnostics in the order they were emitted, e.g. "error: unknown type `Strng`"
+    diagnostics: Vec<String>,
+}
+
+/// parses the file at `path` and checks all of its items
+#[cfg(test)]
+fn check_file(path: &str) -> Checked {
+    check_file_with_cfg(path, &CfgOptions::new())
+}
+
+/// runs all passes like `main` does: the items disabled by `cfg` get stripped, the attributes get checked,
+/// the derives get expanded and finally all items get type checked
+#[cfg(test)]
+fn check_file_with_cfg(path: &str, cfg: &CfgOptions) -> Checked {
+    let file = fs::read_to_string(path).unwrap();
+    let mut parser = Parser::new(TokenStream::new(lexer::lex(file.clone()).unwrap()));
+    let mut krate = parser.parse_crate().unwrap();
+    let mut diagnostics = parser.diagnostics().messages();
+    let mut passes = DiagnosticBuilder::new();
+    krate.strip_cfg(cfg, &mut passes);
+    krate.check_attrs(&mut passes);
+    krate.expand_derives(&mut passes);
+    diagnostics.extend(passes.messages());
+    let mut ctx = krate.build_ctx(crate::diagnostics::source::SourceFile::new(path.to_string(), file));
+    for item in &*krate.items {
+        crate::tyck::tyck_item(&mut ctx, item);
+    }
+    diagnostics.extend(ctx.diagnostics.messages());
+    Checked { krate, ctx, diagnostics }
+}
+
+/// the function named `name` among the items of `krate`
+#[cfg(test)]
+fn find_fn<'a>(krate: &'a Crate, name: &str) -> &'a FunctionNode {
+    krate.items.iter().find_map(|item| match item {
+        ItemKind::FunctionDef(func) if func.header.name == name => Some(&**func),
+        _ => None,
+    }).unwrap()
+}
+
 #[test]
 fn test_func() {
     assert!(test_file("tests/func.tf", |tokens, krate| tokens.len()
@@ -1081,8 +1991,83 @@ fn test_struct_constructor() {
 #[test]
 fn test_generics() {
     assert!(test_file("tests/generics.tf", |tokens, krate| tokens.len()
-        == 93
-        && krate.items.len() == 7));
+        == 131
+        && krate.items.len() == 8));
+}
+
+#[test]
+fn test_generic_structs() {
+    let checked = check_file("tests/generic_structs.tf");
+    assert_eq!(checked.krate.items.len(), 5);
+    // the generic args of structs have to match, `inferred` instantiates `T` with `u8`
+    assert_eq!(checked.diagnostics, vec![
+        "error: mismatched types: expected `Wrapper<u8>`, found `Wrapper<bool>`",
+        "note: the types are required to match because of the type annotation of `a`",
+        "error: mismatched types: expected `Wrapper<u8>`, found `Wrapper<bool>`",
+        "note: the types are required to match because of the type annotation of `c`",
+        "error: mismatched types: expected `Wrapper<u8>`, found `Wrapper<bool>`",
+        "note: the types are required to match because of the return type of `mismatched`",
+    ]);
+}
+
+#[test]
+fn test_attrs() {
+    use crate::parser::attrs::find_attr;
+
+    assert!(test_file("tests/attrs.tf", |tokens, krate| {
+        let mut diagnostics = DiagnosticBuilder::new();
+        krate.check_attrs(&mut diagnostics);
+        assert_eq!(diagnostics.messages(), vec![
+            "warning: unknown attribute `frobnicate`",
+            "error: `#[inline]` can't be applied to a struct",
+            "error: malformed `allow` attribute",
+        ]);
+        assert_eq!(krate.attrs.iter().map(|attr| attr.to_string()).collect::<Vec<_>>(), vec!["#![allow(deprecated)]"]);
+        let old_fn = find_fn(&krate, "old_fn");
+        // the inner attributes of the body belong to the function itself
+        assert_eq!(old_fn.attrs.iter().map(|attr| attr.name().as_str()).collect::<Vec<_>>(), vec!["inline", "deprecated", "allow"]);
+        match &old_fn.body.stmts[0] {
+            StmtKind::LocalAssign(LocalAssign::DecAssign(assign)) => assert!(find_attr(&assign.attrs, "cfg").is_some()),
+            stmt => panic!("expected the `let` of `val` but found {:?}", stmt),
+        }
+        let point = krate.items.iter().find_map(|item| match item {
+            ItemKind::StructDef(def) if def.name == "Point" => Some(def),
+            _ => None,
+        }).unwrap();
+        assert!(find_attr(&point.fields[0].attrs, "deprecated").is_some());
+        assert!(point.fields[1].attrs.is_empty());
+        tokens.len() == 135 && krate.items.len() == 6
+    }));
+}
+
+#[test]
+fn test_lints() {
+    assert!(test_file("tests/lints.tf", |_, krate| {
+        let mut ctx = krate.build_ctx(crate::diagnostics::source::SourceFile::default());
+        assert!(!ctx.is_lint_allowed("deprecated"));
+        // `still_old` allows the use of deprecated functions while its body is being checked
+        let prev = ctx.allow_lints(&find_fn(&krate, "still_old").attrs);
+        assert!(ctx.is_lint_allowed("deprecated"));
+        assert!(!ctx.is_lint_allowed("unused"));
+        ctx.restore_lints(prev);
+        assert!(!ctx.is_lint_allowed("deprecated"));
+        krate.items.len() == 3
+    }));
+}
+
+#[test]
+fn test_where_clause() {
+    let checked = check_file("tests/where_clause.tf");
+    assert_eq!(checked.krate.items.len(), 10);
+    // the bounds declared in the where clauses of `Holder`, `Pick`, its impl and `first` hold
+    assert_eq!(checked.diagnostics, vec![
+        "error: the reference type `&'a &'b u8` requires `'b: 'a` which isn't known to hold",
+        "error: use of undeclared lifetime `'c`",
+        "error: missing lifetime specifier in the return type `&u8`",
+        "note: it has to be specified explicitly as there are 2 lifetimes in the args",
+        "error: the trait bound `Plain: Copy` is not satisfied",
+        "note: required by the bound `T: Copy` of `copied`",
+    ]);
 }
 
 #[test]
@@ -1098,3 +2083,636 @@ fn test_array() {
         == 72
         && krate.items.len() == 2));
 }
+
+#[test]
+fn test_closure() {
+    use crate::tyck::closure::CaptureKind;
+    use crate::tyck::Ty;
+
+    let mut checked = check_file("tests/closure.tf");
+    assert_eq!(checked.krate.items.len(), 4);
+    // the local fn and the statements of the closure's body in `invalid` get checked as well
+    assert_eq!(checked.diagnostics, vec![
+        "error: mismatched types: expected `u32`, found `bool`",
+        "note: the types are required to match because of the operator `*`",
+        "error: cannot find function `undefined` in this scope",
+        "error: `twice` takes 1 argument but 2 were supplied",
+    ]);
+    let body = find_fn(&checked.krate, "test").body.clone();
+    checked.ctx.push_scope();
+    for stmt in &*body.stmts {
+        if let StmtKind::LocalAssign(local) = stmt {
+            checked.ctx.push_local(local);
+        }
+    }
+    let captures = |name: &str| match checked.ctx.env.resolve_var(&name.to_string()) {
+        Some(Ty::Closure(closure)) => closure.captures.iter().map(|capture| (capture.name.clone(), capture.kind)).collect::<Vec<_>>(),
+        ty => panic!("`{}` isn't a closure but {:?}", name, ty),
+    };
+    assert_eq!(captures("add"), vec![]);
+    assert_eq!(captures("scale"), vec![("base".to_string(), CaptureKind::Ref)]);
+    assert_eq!(captures("offset"), vec![("base".to_string(), CaptureKind::Value)]);
+    assert_eq!(captures("count"), vec![("counter".to_string(), CaptureKind::MutRef)]);
+}
+
+#[test]
+fn test_alias() {
+    let checked = check_file("tests/alias.tf");
+    assert_eq!(checked.krate.items.len(), 6);
+    // `Wrapper<u8>::Item` is `u8` as provided by `impl Container for Wrapper<u8>`
+    assert_eq!(checked.diagnostics, vec![
+        "error: mismatched types: expected `u8`, found `bool`",
+        "note: the types are required to match because of the return type of `unwrap`",
+    ]);
+    let get = &find_fn(&checked.krate, "get").header;
+    assert_eq!(checked.ctx.normalize_ast_ty(&get.args[0].1).to_string(), "Wrapper<u8>");
+    assert_eq!(checked.ctx.normalize_ast_ty(get.ret.as_ref().unwrap()).to_string(), "u8");
+}
+
+#[test]
+fn test_trait_impl() {
+    let checked = check_file("tests/trait_impl.tf");
+    assert_eq!(checked.krate.items.len(), 7);
+    // `impl Container for Wrapper` conforms to the trait, `count` is provided by the trait's default
+    assert_eq!(checked.diagnostics, vec![
+        "error: const `LEN` has type `u8` but the trait expects `usize`",
+        "error: method `first` doesn't match the trait's signature: expected the arg `idx` to be of type `usize` but found `u8`",
+        "error: method `last` is not a member of trait `Container`",
+        "error: missing type `Item`",
+        "error: the trait `Container` requires `Base` to be implemented for `Broken` as well",
+    ]);
+}
+
+#[test]
+fn test_hello_world() {
+    assert!(test_file("tests/hello_world.tf", |tokens, krate| tokens.len()
+        == 13
+        && krate.items.len() == 1));
+}
+
+#[test]
+fn test_macros() {
+    use crate::const_eval::ConstValue;
+    use crate::parser::ast::NumberType;
+
+    let mut checked = check_file("tests/macros.tf");
+    assert_eq!(checked.krate.items.len(), 4);
+    // `println!` expands to a call of the compiler provided `__print_ln`
+    assert!(checked.diagnostics.is_empty(), "{:?}", checked.diagnostics);
+    let mut eval = |name: &str| checked.ctx.consts.eval_const(&name.to_string(), &mut checked.ctx.diagnostics);
+    assert_eq!(eval("LIMIT"), Some(ConstValue::Number(NumberType::U32(10))));
+    // expression fragments stay grouped, so this is `(1 + 2) * (1 + 2)`
+    assert_eq!(eval("SQUARE"), Some(ConstValue::Number(NumberType::U32(9))));
+    assert_eq!(eval("SUM"), Some(ConstValue::Number(NumberType::U32(6))));
+    // the `tmp` declared by `with_tmp!` is renamed, so it doesn't shadow the caller's `tmp`
+    let body = &find_fn(&checked.krate, "squared").body.stmts;
+    let expanded = match body.last() {
+        Some(StmtKind::Expr(AstNode::Block(block))) => &block.stmts,
+        stmt => panic!("expected the expansion of `with_tmp!` but found {:?}", stmt),
+    };
+    match expanded.last() {
+        Some(StmtKind::Expr(AstNode::BinaryExpr(expr))) => {
+            assert!(matches!(&expr.lhs, AstNode::Ident(name) if name.starts_with("tmp#")), "{:?}", expr.lhs);
+            assert_eq!(expr.rhs, AstNode::Ident("tmp".to_string()));
+        }
+        stmt => panic!("expected `tmp * $e` but found {:?}", stmt),
+    }
+}
+
+#[test]
+fn test_macro_errors() {
+    // the errors point at the call and the notes at the rule which was being expanded or the macro's definition
+    // calls which can't be expanded are skipped, so the functions containing them are still parsed
+    let (krate, diagnostics) = parse_file_spanned("tests/macro_mismatch.tf");
+    assert_eq!(krate.items.len(), 3);
+    assert_eq!(diagnostics, vec![
+        ("error: no rules of the macro `pair` expected this invocation".to_string(), Some("pair!".to_string())),
+        ("note: the macro `pair` is defined here".to_string(), Some("pair".to_string())),
+        ("error: meta-variable `a` repeats 2 times, but `b` repeats 1 times".to_string(), Some("zip!".to_string())),
+        ("note: while expanding this rule of `zip!`".to_string(), Some("($($a:expr),* ; $($b:expr),*)".to_string())),
+        ("error: this must repeat at most once".to_string(), Some("first!".to_string())),
+        ("note: while expanding this rule of `first!`".to_string(), Some("($($a:expr),*)".to_string())),
+    ]);
+    let (krate, diagnostics) = parse_file_spanned("tests/macro_recursion.tf");
+    assert_eq!(krate.items.len(), 1);
+    assert_eq!(diagnostics, vec![
+        ("error: recursion limit reached while expanding `forever!`".to_string(), Some("forever!".to_string())),
+        ("note: the macro `forever` is defined here".to_string(), Some("forever".to_string())),
+        ("note: the recursion limit is 128 nested expansions".to_string(), None),
+    ]);
+}
+
+#[test]
+fn test_derive() {
+    assert!(test_file("tests/derive.tf", |tokens, mut krate| {
+        krate.expand_derives(&mut DiagnosticBuilder::new());
+        let derived = krate.items.iter().filter_map(|item| match item {
+            ItemKind::StructImpl(s_impl) if s_impl.ty.to_string() == "Point" => s_impl.impl_trait.as_ref().map(|tait| tait.to_string()),
+            _ => None,
+        }).collect::<Vec<_>>();
+        assert_eq!(derived, vec!["Clone", "Copy", "Debug", "PartialEq", "Default"]);
+        // the derived impls check that the fields implement the derived traits
+        let mut ctx = krate.build_ctx(crate::diagnostics::source::SourceFile::default());
+        for item in krate.items.iter().filter(|item| matches!(item, ItemKind::StructImpl(_))) {
+            crate::tyck::tyck_item(&mut ctx, item);
+        }
+        // `Tagged` can't be `Copy` as `Label` isn't and it doesn't derive `Clone`
+        assert_eq!(ctx.diagnostics.messages(), vec![
+            "error: the trait `Copy` is not implemented for `Label`",
+            "note: required for the field `label` of `Tagged` because of `#[derive(Copy)]`",
+            "error: the trait `Clone` is not implemented for `Tagged`",
+            "note: `Copy` requires `Clone` to be implemented as well",
+        ]);
+        // every derive adds an impl of the derived trait next to the struct
+        tokens.len() == 111 && krate.items.len() == 15
+    }));
+}
+
+#[test]
+fn test_derive_enum() {
+    // enums are skipped, so only the struct, its derived impl and the function are left
+    let (mut krate, diagnostics) = parse_file_spanned("tests/derive_enum.tf");
+    krate.expand_derives(&mut DiagnosticBuilder::new());
+    assert_eq!(krate.items.len(), 3);
+    assert_eq!(diagnostics, vec![
+        ("error: `#[derive(Clone, Debug)]` is not supported on enums yet".to_string(), Some("Shape".to_string())),
+        ("note: only structs can derive traits for now".to_string(), None),
+    ]);
+}
+
+#[test]
+fn test_cfg() {
+    assert!(test_file("tests/cfg.tf", |tokens, mut krate| {
+        let mut cfg = CfgOptions::new();
+        cfg.parse_option("test").unwrap();
+        cfg.parse_option("feature=\"std\"").unwrap();
+        let mut diagnostics = DiagnosticBuilder::new();
+        krate.strip_cfg(&cfg, &mut diagnostics);
+        // items with invalid predicates are kept
+        assert_eq!(diagnostics.messages(), vec!["error: invalid predicate `nope`, expected one of `not`, `all` or `any`"]);
+        let items = krate.items.iter().map(|item| match item {
+            ItemKind::FunctionDef(func) => format!("fn {}", func.header.name),
+            ItemKind::StructDef(def) => format!("struct {} {{ {} }}", def.name, def.fields.iter().map(|field| field.name.clone()).collect::<Vec<_>>().join(", ")),
+            ItemKind::StructImpl(adt_impl) => format!("impl {} {{ {} }}", adt_impl.ty.to_string(), adt_impl.methods.len()),
+            item => panic!("unexpected item {:?}", item),
+        }).collect::<Vec<_>>();
+        assert_eq!(items, vec!["fn with_std", "struct Config { verbose, fast }", "impl Config { 1 }", "fn invalid_predicate"]);
+        // only the `let` enabled by `test` is left
+        let with_std = find_fn(&krate, "with_std");
+        match &*with_std.body.stmts {
+            [StmtKind::LocalAssign(LocalAssign::DecAssign(assign))] => assert_eq!(assign.val.val.to_string(), "2"),
+            stmts => panic!("expected a single `let` but found {:?}", stmts),
+        }
+        tokens.len() == 152
+    }));
+}
+
+#[test]
+fn test_infer() {
+    let checked = check_file("tests/infer.tf");
+    assert_eq!(checked.krate.items.len(), 2);
+    // `main` type checks, the errors are all in `mismatch`
+    assert_eq!(checked.diagnostics, vec![
+        "error: literal out of range",
+        "note: integer literals can't be larger than `340282366920938463463374607431768211455`",
+        "error: mismatched types: expected `bool`, found `{integer}`",
+        "note: the types are required to match because of the type annotation of `flag`",
+        "note: found `{integer}` because of the integer literal `3`",
+        "error: mismatched types: expected `{integer}`, found `{float}`",
+        "note: the types are required to match because of the array element",
+        "note: expected `{integer}` because of the integer literal `3`",
+        "note: found `{float}` because of the float literal `0.5`",
+    ]);
+}
+
+#[test]
+fn test_primitives() {
+    let checked = check_file("tests/primitives.tf");
+    assert_eq!(checked.krate.items.len(), 4);
+    // `Wide`, `SMALL` and `widen` only use primitive types, the names in `narrow` are misspelled
+    assert_eq!(checked.diagnostics, vec![
+        "error: unknown type `u33`",
+        "note: a type with a similar name exists: `u32`",
+        "error: unknown type `boool`",
+        "note: a type with a similar name exists: `bool`",
+    ]);
+}
+
+#[test]
+fn test_call() {
+    let checked = check_file("tests/call.tf");
+    assert_eq!(checked.krate.items.len(), 8);
+    // the calls in `main` are fine, every call in `misuse` is wrong in a different way
+    assert_eq!(checked.diagnostics, vec![
+        "error: `takes` takes 2 arguments but 1 was supplied",
+        "error: mismatched types: expected `bool`, found `{integer}`",
+        "note: the types are required to match because of the argument 2 (`b`) of the call to `takes`",
+        "note: found `{integer}` because of the integer literal `2`",
+        "error: the trait bound `Square: Clone` is not satisfied",
+        "note: required by the bound `T: Clone` of `pick`",
+    ]);
+}
+
+#[test]
+fn test_errors() {
+    let checked = check_file("tests/errors.tf");
+    assert_eq!(checked.krate.items.len(), 5);
+    // every item gets checked even though the previous ones contain errors,
+    // `s` is of an unknown type, so it doesn't cause any further errors
+    assert_eq!(checked.diagnostics, vec![
+        "error: the type alias `Pair` takes 1 generic argument but 2 were supplied",
+        "error: cannot find value `valeu` in this scope",
+        "error: cannot find function `undefined` in this scope",
+        "error: unknown type `Strng`",
+        "error: impls can't be defined inside of a function",
+        "note: impls are only supported at the top level for now",
+    ]);
+}
+
+#[test]
+fn test_struct_fields() {
+    let checked = check_file("tests/struct_fields.tf");
+    assert_eq!(checked.krate.items.len(), 4);
+    // the fields, the base and the annotation all share the generic args of the constructed struct
+    assert_eq!(checked.diagnostics, vec![
+        "error: mismatched types: expected `bool`, found `{integer}`",
+        "note: the types are required to match because of the field `second` of `Pair`",
+        "note: expected `bool` because of the field `first` of `Pair`",
+        "note: found `{integer}` because of the integer literal `1`",
+        "error: mismatched types: expected `Pair<bool>`, found `Pair<{integer}>`",
+        "note: the types are required to match because of the type annotation of `counts`",
+        "error: mismatched types: expected `Pair<{integer}>`, found `Pair<bool>`",
+        "note: the types are required to match because of the functional update of `Pair`",
+    ]);
+}
+
+#[test]
+fn test_ops() {
+    let checked = check_file("tests/ops.tf");
+    assert_eq!(checked.krate.items.len(), 7);
+    // the overloaded `==` and `+` in `ops` are fine
+    assert_eq!(checked.diagnostics, vec![
+        "error: mismatched types: expected `bool`, found `u32`",
+        "note: the types are required to match because of the operator `&&`",
+        "error: mismatched types: expected `Meters`, found `{integer}`",
+        "note: the types are required to match because of the operator `+` implemented by `impl Add<Meters> for Meters`",
+        "note: found `{integer}` because of the integer literal `1`",
+        "error: cannot apply binary operator `+` to type `bool`",
+        "note: `bool` doesn't implement `Add`",
+    ]);
+}
+
+#[test]
+fn test_bounds() {
+    let checked = check_file("tests/bounds.tf");
+    assert_eq!(checked.krate.items.len(), 13);
+    // the generic and the blanket impl apply to `Circle`
+    assert_eq!(checked.diagnostics, vec![
+        "error: the trait bound `Boxed<Square>: Named` is not satisfied",
+        "note: required by the bound `N: Named` of `Registry`",
+        "note: `Boxed<Square>` does not implement `Named` because `Square: Shape` is not satisfied, required by the impl `impl<T: Shape> Named for Boxed<T>` at tests/bounds.tf:33",
+        "note: the impl `impl Shape for Circle` exists but doesn't apply to `Square`",
+        "error: the trait bound `Square: Drawable` is not satisfied",
+        "note: required by the bound `D: Drawable` of `draw`",
+        "note: `Square` does not implement `Drawable` because `Square: Shape` is not satisfied, required by the impl `impl<T: Shape> Drawable for T` at tests/bounds.tf:37",
+        "note: the impl `impl Shape for Circle` exists but doesn't apply to `Square`",
+    ]);
+}
+
+#[test]
+fn test_impl_select() {
+    let checked = check_file("tests/impl_select.tf");
+    assert_eq!(checked.krate.items.len(), 17);
+    // `List<List<Circle>>` from the signature of `valid` selects `impl<T: Shape> Encode for List<List<T>>` with `T = Circle`
+    let shapes = &find_fn(&checked.krate, "valid").header.args[1].1;
+    let list = match &shapes.kind.get_owned().unwrap().generics[0] {
+        TyOrConstVal::Ty(ty) => ty.clone(),
+        _ => unreachable!(),
+    };
+    let encode = Ty {
+        kind: TyKind::Owned(Box::new(OwnedTy {
+            name: "Encode".to_string(),
+            generics: Box::new([]),
+        })),
+    };
+    let selected = checked.ctx.traits.select_impl_in(&list, &encode, &[]).map(|substs| substs["T"].to_string());
+    assert_eq!(selected.as_deref(), Some("Circle"));
+    assert_eq!(checked.diagnostics, vec![
+        "error: the trait bound `List<u16>: Encode` is not satisfied",
+        "note: required by the bound `E: Encode` of `Store`",
+        "note: the impl `impl Encode for List<u8>` exists but doesn't apply to `List<u16>`",
+        "note: the impl `impl<T: Shape> Encode for List<List<T>>` exists but doesn't apply to `List<u16>`",
+        "note: the impl `impl<T: Encode> Encode for Pair<T, T>` exists but doesn't apply to `List<u16>`",
+        "note: the impl `impl<T, S: Shape> Encode for Tagged<T>` exists but doesn't apply to `List<u16>`",
+        "error: the trait bound `List<List<Square>>: Encode` is not satisfied",
+        "note: required by the bound `E: Encode` of `Store`",
+        "note: `List<List<Square>>` does not implement `Encode` because `Square: Shape` is not satisfied, required by the impl `impl<T: Shape> Encode for List<List<T>>` at tests/impl_select.tf:38",
+        "note: the impl `impl Shape for Circle` exists but doesn't apply to `Square`",
+        "error: the trait bound `Pair<List<u8>, List<Circle>>: Encode` is not satisfied",
+        "note: required by the bound `E: Encode` of `Store`",
+        "note: the impl `impl Encode for List<u8>` exists but doesn't apply to `Pair<List<u8>, List<Circle>>`",
+        "note: the impl `impl<T: Shape> Encode for List<List<T>>` exists but doesn't apply to `Pair<List<u8>, List<Circle>>`",
+        "note: the impl `impl<T: Encode> Encode for Pair<T, T>` exists but doesn't apply to `Pair<List<u8>, List<Circle>>`",
+        "note: the impl `impl<T, S: Shape> Encode for Tagged<T>` exists but doesn't apply to `Pair<List<u8>, List<Circle>>`",
+        // values select impls by their generic args as well and the bound of `S` can't be proven as `S` isn't bound by the header
+        "error: the trait bound `List<u16>: Encode` is not satisfied",
+        "note: required by the bound `E: Encode` of `encode`",
+        "note: the impl `impl Encode for List<u8>` exists but doesn't apply to `List<u16>`",
+        "note: the impl `impl<T: Shape> Encode for List<List<T>>` exists but doesn't apply to `List<u16>`",
+        "note: the impl `impl<T: Encode> Encode for Pair<T, T>` exists but doesn't apply to `List<u16>`",
+        "note: the impl `impl<T, S: Shape> Encode for Tagged<T>` exists but doesn't apply to `List<u16>`",
+        "error: the trait bound `Tagged<Circle>: Encode` is not satisfied",
+        "note: required by the bound `E: Encode` of `encode`",
+        "note: `Tagged<Circle>` does not implement `Encode` because `S: Shape` is not satisfied, required by the impl `impl<T, S: Shape> Encode for Tagged<T>` at tests/impl_select.tf:58",
+    ]);
+}
+
+#[test]
+fn test_supertraits() {
+    let checked = check_file("tests/supertraits.tf");
+    assert_eq!(checked.krate.items.len(), 14);
+    // `T: Solid` implies `T: Shape` for both `volume` and `impl<T: Solid> Solid for Boxed<T>`
+    assert_eq!(checked.diagnostics, vec![
+        "error: the trait `Solid` requires `Shape` to be implemented for `Sphere` as well",
+        "error: the trait bound `N: Shape` is not satisfied",
+        "note: required by the bound `S: Shape` of `area`",
+        "note: the impl `impl Shape for Cube` exists but doesn't apply to `N`",
+        "note: the impl `impl<T: Shape> Shape for Boxed<T>` exists but doesn't apply to `N`",
+    ]);
+}
+
+#[test]
+fn test_coherence() {
+    let checked = check_file("tests/coherence.tf");
+    assert_eq!(checked.krate.items.len(), 16);
+    // `Circle` isn't `Copy`, so the blanket impl doesn't apply to it
+    assert_eq!(checked.diagnostics, vec![
+        "error: conflicting implementations of trait `Area` for type `u8`",
+        "note: first implementation: `impl<T: Copy> Area for T`",
+        "error: only traits defined in the current crate can be implemented for types defined outside of it",
+        "note: neither the trait `Default` nor the type `&str` is defined in the current crate",
+        "error: conflicting implementations of trait `Shape` for type `Circle`",
+        "note: first implementation: `impl Shape for Circle`",
+        "error: conflicting implementations of trait `Shape` for type `Wrapper<u32>`",
+        "note: first implementation: `impl<T> Shape for Wrapper<T>`",
+        "error: cannot find trait `Default` in this scope",
+    ]);
+    // the errors point at the conflicting impls and the notes at the ones they conflict with
+    let code = checked.ctx.diagnostics.spanned_messages().into_iter().filter_map(|(_, code)| code).collect::<Vec<_>>();
+    assert_eq!(code, vec![
+        "impl Area for u8",
+        "impl<T: Copy> Area for T",
+        "impl Default for &str",
+        "impl Shape for Circle",
+        "impl Shape for Circle",
+        "impl Shape for Wrapper<u32>",
+        "impl<T> Shape for Wrapper<T>",
+    ]);
+}
+
+#[test]
+fn test_solver_cycles() {
+    let checked = check_file("tests/solver_cycles.tf");
+    assert_eq!(checked.krate.items.len(), 10);
+    let wrapped = |depth: usize| format!("{}u8{}", "Wrapper<".repeat(depth), ">".repeat(depth));
+    let mut expected = vec![
+        "error: the trait bound `u8: A` is not satisfied".to_string(),
+        "note: required by the bound `X: A` of `needs_a`".to_string(),
+        "note: `u8` does not implement `A` because `u8: B` is not satisfied, required by the impl `impl<T: B> A for T` at tests/solver_cycles.tf:23".to_string(),
+        "note: `u8` does not implement `B` because `u8: A` is not satisfied, required by the impl `impl<T: A> B for T` at tests/solver_cycles.tf:19".to_string(),
+        // the cycle ends the explanation
+        "error: the trait bound `u8: A` is not satisfied".to_string(),
+        "note: required by the bound `X: A` of `needs_a`".to_string(),
+        "note: `u8` does not implement `A` because `u8: B` is not satisfied, required by the impl `impl<T: B> A for T` at tests/solver_cycles.tf:23".to_string(),
+        "note: `u8` does not implement `B` because `u8: A` is not satisfied, required by the impl `impl<T: A> B for T` at tests/solver_cycles.tf:19".to_string(),
+        "error: the trait bound `u8: Grow` is not satisfied".to_string(),
+        "note: required by the bound `X: Grow` of `needs_grow`".to_string(),
+    ];
+    // the explanation of the growing goal stops after 8 levels
+    expected.extend((0..8).map(|depth| format!(
+        "note: `{}` does not implement `Grow` because `{}: Grow` is not satisfied, required by the impl `impl<T> Grow for T where Wrapper<T>: Grow` at tests/solver_cycles.tf:27",
+        wrapped(depth), wrapped(depth + 1),
+    )));
+    // the cycle and the overflow get reported once even though `u8: A` is required twice
+    expected.extend([
+        "error: cycle detected when proving `u8: A`".to_string(),
+        "note: ...which requires proving `u8: B`...".to_string(),
+        "note: ...which again requires proving `u8: A`, completing the cycle".to_string(),
+        format!("error: overflow evaluating the requirement `{}: Grow`", wrapped(16)),
+        "note: consider increasing the recursion limit by adding a `#![recursion_limit = \"32\"]` attribute to the crate".to_string(),
+    ]);
+    assert_eq!(checked.diagnostics, expected);
+}
+
+#[test]
+fn test_proof_trees() {
+    let checked = check_file("tests/proof_trees.tf");
+    assert_eq!(checked.krate.items.len(), 12);
+    assert_eq!(checked.diagnostics, vec![
+        "error: the trait bound `Civilization<Continent>: CivBase` is not satisfied",
+        "note: required by the bound `Civ: CivBase` of `Empire`",
+        "note: `Civilization<Continent>` does not implement `CivBase` because `Continent: Peaceful` is not satisfied, required by the impl `impl<Cont: Peaceful> CivBase for Civilization<Cont>` at tests/proof_trees.tf:29",
+        "error: the trait bound `u8: Shape` is not satisfied",
+        "note: required by the bound `S: Shape` of `draw`",
+        "note: the impl `impl Shape for Circle` exists but doesn't apply to `u8`",
+    ]);
+}
+
+#[test]
+fn test_auto_traits() {
+    let checked = check_file("tests/auto_traits.tf");
+    assert_eq!(checked.krate.items.len(), 17);
+    // `Handle` is still `Sync`, `&u8` is `Copy` and the cycle through `&Node` holds as `Send` is an auto trait
+    assert_eq!(checked.diagnostics, vec![
+        "error: negative impls are only allowed for auto traits like `Send` and `Sync`, but `Shape` isn't one",
+        "error: the trait bound `Window: Send` is not satisfied",
+        "note: required by the bound `T: Send` of `spawn`",
+        "note: `Window` does not implement `Send` because `Handle: Send` is not satisfied, required by the fields of `Window`",
+        "note: `Handle` explicitly opts out of `Send` with `impl !Send for Handle`",
+        "error: the trait bound `Window: Copy` is not satisfied",
+        "note: required by the bound `T: Copy` of `duplicate`",
+        "note: the impl `impl Copy for Node` exists but doesn't apply to `Window`",
+        // the fields of `Pair<Handle>` are checked with `T = Handle`
+        "error: the trait bound `Pair<Handle>: Send` is not satisfied",
+        "note: required by the bound `T: Send` of `spawn`",
+        "note: `Pair<Handle>` does not implement `Send` because `Handle: Send` is not satisfied, required by the fields of `Pair<Handle>`",
+        "note: `Handle` explicitly opts out of `Send` with `impl !Send for Handle`",
+        "note: `Pair<Handle>` does not implement `Send` because `[Handle; 2]: Send` is not satisfied, required by the fields of `Pair<Handle>`",
+        "note: `[Handle; 2]` does not implement `Send` because `Handle: Send` is not satisfied, required by `[Handle; 2]`",
+        "note: `Handle` explicitly opts out of `Send` with `impl !Send for Handle`",
+    ]);
+}
+
+#[test]
+fn test_dyn_trait() {
+    use crate::tyck::objects::VtableEntry;
+
+    let checked = check_file("tests/dyn_trait.tf");
+    assert_eq!(checked.krate.items.len(), 23);
+    // `Circle` implements `Shape`, `dyn Shape` can be cast to its supertrait and `Box<Circle>` to `Box<dyn Shape>`
+    assert_eq!(checked.diagnostics, vec![
+        "error: the trait `Factory` cannot be made into an object",
+        "note: ...because associated function `create` has no `self` parameter (in `Factory`)",
+        "note: ...because method `convert` has generic type parameters (in `Factory`)",
+        "note: ...because method `merge` references the `Self` type outside of its receiver (in `Factory`)",
+        "error: the trait `Resizable` cannot be made into an object",
+        "note: ...because it requires `Self: Sized` (in `Resizable`)",
+        "error: the size for values of type `dyn Shape` cannot be known at compilation time",
+        "note: trait objects can only be used behind a pointer like `&dyn Shape`",
+        "error: the trait bound `Point: Shape` is not satisfied",
+        "note: required by the cast from `&Point` to `&dyn Shape` for the argument 1 (`shape`) of the call to `draw`",
+        "note: the impl `impl Shape for Circle` exists but doesn't apply to `Point`",
+        "note: the impl `impl<T> Shape for Sphere<T>` exists but doesn't apply to `Point`",
+        "error: the trait bound `Point: Shape` is not satisfied",
+        "note: required by the cast from `Box<Point>` to `Box<dyn Shape>` for the argument 1 (`shape`) of the call to `store`",
+        "note: the impl `impl Shape for Circle` exists but doesn't apply to `Point`",
+        "note: the impl `impl<T> Shape for Sphere<T>` exists but doesn't apply to `Point`",
+    ]);
+    // the supertrait's methods come first and `scale` is provided by the trait's default
+    let shape_vtable = checked.ctx.vtables().iter().find(|vtable| vtable.ty == "Circle" && vtable.tait == "Shape").unwrap();
+    assert_eq!(shape_vtable.entries[3..], [
+        VtableEntry::Method { tait: "Named".to_string(), name: "name".to_string(), default: false },
+        VtableEntry::Method { tait: "Shape".to_string(), name: "area".to_string(), default: false },
+        VtableEntry::Method { tait: "Shape".to_string(), name: "scale".to_string(), default: true },
+    ]);
+    // the vtable of `Shape` comes first, followed by the methods of `Colored` as `Named` is laid out already
+    let sprite_vtable = checked.ctx.vtables().iter().find(|vtable| vtable.ty == "Circle" && vtable.tait == "Sprite").unwrap();
+    assert_eq!(sprite_vtable.entries[3..], [
+        VtableEntry::Method { tait: "Named".to_string(), name: "name".to_string(), default: false },
+        VtableEntry::Method { tait: "Shape".to_string(), name: "area".to_string(), default: false },
+        VtableEntry::Method { tait: "Shape".to_string(), name: "scale".to_string(), default: true },
+        VtableEntry::Method { tait: "Colored".to_string(), name: "color".to_string(), default: false },
+        VtableEntry::Method { tait: "Sprite".to_string(), name: "frame".to_string(), default: false },
+    ]);
+    // the impls of generic structs are found as well, so `scale` is the one of the impl
+    let sphere_vtable = checked.ctx.vtables().iter().find(|vtable| vtable.ty == "Sphere<u32>" && vtable.tait == "Shape").unwrap();
+    assert_eq!(sphere_vtable.entries[3..], [
+        VtableEntry::Method { tait: "Named".to_string(), name: "name".to_string(), default: false },
+        VtableEntry::Method { tait: "Shape".to_string(), name: "area".to_string(), default: false },
+        VtableEntry::Method { tait: "Shape".to_string(), name: "scale".to_string(), default: false },
+    ]);
+}
+
+#[test]
+fn test_const_eval() {
+    use crate::const_eval::ConstValue;
+    use crate::parser::ast::NumberType;
+
+    let mut checked = check_file("tests/const_eval.tf");
+    assert_eq!(checked.krate.items.len(), 20);
+    // the cycle is only reported once and the lengths of arrays are compared by their values,
+    // so `[u8; SIDE - 10]` overlaps with `[u8; 2]` and `[u8; 1 + 1]` implements `Packed`
+    assert_eq!(checked.diagnostics, vec![
+        "error: conflicting implementations of trait `Packed` for type `[u8; 2]`",
+        "note: first implementation: `impl Packed for [u8; 2]`",
+        "error: attempt to compute `200_u8 + 100_u8`, which would overflow",
+        "error: attempt to divide `146_u32` by zero",
+        "error: literal out of range for `i8`",
+        "note: the literal `300` does not fit into the type `i8`",
+        "error: cycle detected when evaluating the const `FIRST`",
+        "note: ...which requires evaluating the const `SECOND`...",
+        "note: ...which requires evaluating the const `THIRD`...",
+        "note: ...which again requires evaluating the const `FIRST`, completing the cycle",
+        "error: attempt to divide `12_u32` by zero",
+        "error: attempt to compute `3_usize - 4_usize`, which would overflow",
+        "error: mismatched types: expected `[u8; 2]`, found `[u8; 3]`",
+        "note: the types are required to match because of the type annotation of `triple`",
+        "error: the trait bound `[u8; 3]: Packed` is not satisfied",
+        "note: required by the bound `T: Packed` of `pack`",
+        "note: the impl `impl Packed for [u8; 2]` exists but doesn't apply to `[u8; 3]`",
+        "note: the impl `impl Packed for [u8; 2]` exists but doesn't apply to `[u8; 3]`",
+    ]);
+    // the errors point into the source of the consts, cycles at the whole const
+    let code = checked.ctx.diagnostics.spanned_messages().into_iter().filter_map(|(_, code)| code).collect::<Vec<_>>();
+    assert_eq!(code, vec!["impl Packed for [u8; SIDE - 10]", "impl Packed for [u8; 2]", "200 + 100", "AREA / (SIDE - 12)", "300", "const FIRST: u32 = SECOND + 1;", "SIDE / 0", "3 - 4"]);
+    let mut eval = |name: &str| checked.ctx.consts.eval_const(&name.to_string(), &mut checked.ctx.diagnostics);
+    let side = ConstValue::Number(NumberType::U32(12));
+    assert_eq!(eval("AREA"), Some(ConstValue::Number(NumberType::U32(146))));
+    assert_eq!(eval("IS_BIG"), Some(ConstValue::Bool(true)));
+    assert_eq!(eval("SMALL"), Some(ConstValue::Bool(false)));
+    assert_eq!(eval("WIDE"), Some(ConstValue::Struct {
+        name: "Rect".to_string(),
+        fields: vec![("width".to_string(), ConstValue::Number(NumberType::U32(24))), ("height".to_string(), side)],
+    }));
+    assert_eq!(eval("ZEROS"), Some(ConstValue::Array(vec![ConstValue::Number(NumberType::U32(0)); 4])));
+    assert_eq!(eval("LIMIT"), None);
+}
+
+#[test]
+fn test_const_fn() {
+    use crate::const_eval::ConstValue;
+    use crate::parser::ast::NumberType;
+
+    let mut checked = check_file("tests/const_fn.tf");
+    assert_eq!(checked.krate.items.len(), 14);
+    // the valid const fns `area` and `square` don't contain any runtime operations
+    assert_eq!(checked.diagnostics, vec![
+        "error: closures cannot be created in constant functions",
+        "note: closures can only be called at runtime",
+        "note: in the constant function `invalid`",
+        "error: cannot call the local `callback` in constant functions",
+        "note: only `const fn` items can be called at compile time",
+        "note: in the constant function `invalid`",
+        "error: cannot call non-const fn `now` in constant functions",
+        "note: `now` was inferred to only be callable at runtime",
+        "note: ...because it refers to the static `COUNTER`",
+        "note: in the constant function `invalid`",
+        "error: cannot call non-const fn `random` in constant functions",
+        "note: `random` is declared as `runtime fn`",
+        "note: in the constant function `invalid`",
+        "error: constant functions cannot refer to statics",
+        "note: consider making `COUNTER` a `const`",
+        "note: in the constant function `invalid`",
+        "error: attempt to divide `8_u32` by zero",
+        "note: inside `halve` at `x / (x - x)`",
+        "error: reached the limit of 64 nested calls while evaluating `forever`",
+        "note: inside `forever` at `forever(x + 1)`",
+        "note: `forever` probably calls itself endlessly",
+        "error: cannot call non-const fn `now` in constants",
+        "note: calls in constants are limited to constant functions",
+        "note: `now` was inferred to only be callable at runtime",
+        "note: ...because it refers to the static `COUNTER`",
+    ]);
+    let mut eval = |name: &str| checked.ctx.consts.eval_const(&name.to_string(), &mut checked.ctx.diagnostics);
+    assert_eq!(eval("AREA"), Some(ConstValue::Number(NumberType::U32(130))));
+    assert_eq!(eval("SQUARES"), Some(ConstValue::Array(vec![ConstValue::Number(NumberType::U32(9)); 4])));
+}
+
+#[test]
+fn test_const_infer() {
+    use crate::const_eval::{ConstValue, InferredConstness};
+    use crate::parser::ast::NumberType;
+
+    let mut checked = check_file("tests/const_infer.tf");
+    assert_eq!(checked.krate.items.len(), 14);
+    // `quadruple` calls `double`, which is inferred to be const
+    assert_eq!(checked.diagnostics, vec![
+        "error: cannot call non-const fn `elapsed` in constant functions",
+        "note: `elapsed` was inferred to only be callable at runtime",
+        "note: ...because it calls `ticks`...",
+        "note: ...which refers to the static `COUNTER`",
+        "note: in the constant function `uses_elapsed`",
+        "error: cannot call non-const fn `report` in constants",
+        "note: calls in constants are limited to constant functions",
+        "note: `report` was inferred to only be callable at runtime",
+        "note: ...because it calls `elapsed`...",
+        "note: ...which calls `ticks`...",
+        "note: ...which refers to the static `COUNTER`",
+        "error: cannot call non-const fn `mix` in constants",
+        "note: calls in constants are limited to constant functions",
+        "note: `mix` was inferred to only be callable at runtime",
+        "note: ...because it calls `seed`...",
+        "note: ...which is declared as `runtime fn`",
+    ]);
+    let constness = |name: &str| checked.ctx.consts.inferred_constness(&name.to_string()).cloned();
+    // functions calling each other are inferred together
+    for name in ["double", "quadruple", "even", "odd"] {
+        assert_eq!(constness(name), Some(InferredConstness::Const), "{}", name);
+    }
+    for name in ["ticks", "elapsed", "report", "mix"] {
+        assert!(matches!(constness(name), Some(InferredConstness::Runtime(_))), "{}", name);
+    }
+    // `seed` is declared as `runtime fn`, so it isn't inferred at all
+    assert_eq!(constness("seed"), None);
+    assert_eq!(checked.ctx.consts.eval_const(&"FOUR".to_string(), &mut checked.ctx.diagnostics), Some(ConstValue::Number(NumberType::U32(4))));
+}
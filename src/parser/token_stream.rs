@@ -1,9 +1,13 @@
 use crate::lexer::token::{Token, TokenType};
+use crate::parser::macros::ExpnId;
 use std::error::Error;
 use std::fmt::{Debug, Display, Formatter};
+use std::ops::Range;
 
 pub struct TokenStream {
-    tokens: Box<[Token]>,
+    tokens: Vec<Token>,
+    // the macro expansion each token originates from (if any)
+    expansions: Vec<Option<ExpnId>>,
     cursor: usize,
 }
 
@@ -17,11 +21,40 @@ impl TokenStream {
             }
         }
         Self {
-            tokens: filtered_tokens.into_boxed_slice(),
+            expansions: vec![None; filtered_tokens.len()],
+            tokens: filtered_tokens,
             cursor: 0,
         }
     }
 
+    /// the index of the next token
+    #[inline]
+    pub fn position(&self) -> usize {
+        self.cursor
+    }
+
+    #[inline]
+    pub fn set_position(&mut self, cursor: usize) {
+        self.cursor = cursor;
+    }
+
+    /// returns the macro expansion the token at `idx` was produced by
+    pub fn expansion_at(&self, idx: usize) -> Option<ExpnId> {
+        self.expansions.get(idx).copied().flatten()
+    }
+
+    /// the tokens in `range`, e.g. the ones of a macro call
+    pub fn tokens(&self, range: Range<usize>) -> &[Token] {
+        &self.tokens[range]
+    }
+
+    /// replaces the tokens in `range` (usually a macro call) with the tokens it expanded to
+    pub fn splice(&mut self, range: Range<usize>, tokens: Vec<Token>, expansion: ExpnId) {
+        let len = tokens.len();
+        self.tokens.splice(range.clone(), tokens);
+        self.expansions.splice(range, (0..len).map(|_| Some(expansion)));
+    }
+
     pub fn get_next(&self) -> Option<&Token> {
         self.tokens.get(self.cursor)
     }
@@ -0,0 +1,154 @@
+use crate::parser::ast::{Generic, StructDef, Ty, TyKind, TyOrConstVal};
+use crate::parser::attrs::Mutability;
+use crate::traitsolver::{match_header, named_ty, ImplHeader, TraitManager, BUILTIN_IMPL_PRIMITIVES};
+use std::collections::HashMap;
+
+// some traits are known to the compiler and are (partially) implemented by it:
+// - the auto traits `Send` and `Sync` are implemented for every type whose components implement them, that is the
+//   fields of structs, the elements of arrays and tuples and the types behind references (`&T: Send` requires `T: Sync`).
+//   types can opt out of them with negative impls like `impl !Send for Handle {}`
+// - `Copy` and `Clone` are implemented for shared references, function pointers and arrays of types implementing them
+// - `Sized` is implemented for every type whose size is known, that is every type except for `str` and trait objects
+// the traits are identified by their lang items, so `#[lang = "send"] trait Send {}` refers to the compiler's `Send`.
+// the lang items default to the traits' usual names as there is no standard library defining them
+
+/// the lang items of the traits which are known to the compiler together with the names of the traits by default
+pub const BUILTIN_LANG_ITEMS: [(&str, &str); 5] = [("send", "Send"), ("sync", "Sync"), ("copy", "Copy"), ("clone", "Clone"), ("sized", "Sized")];
+
+#[derive(Copy, Clone, PartialEq)]
+pub(crate) enum BuiltinTrait {
+    Send,
+    Sync,
+    Copy,
+    Clone,
+    Sized,
+}
+
+impl BuiltinTrait {
+
+    /// whether the trait gets implemented structurally and its cycles hold
+    pub(crate) fn is_auto(&self) -> bool {
+        matches!(self, BuiltinTrait::Send | BuiltinTrait::Sync)
+    }
+
+}
+
+/// the fields of a struct defined in the current crate
+pub(crate) struct AdtFields {
+    // the names of the struct's generic params
+    params: Vec<String>,
+    tys: Vec<Ty>,
+}
+
+impl TraitManager {
+
+    /// registers a struct defined in the current crate, see `check_coherence` and `auto_impl_holds`
+    pub fn insert_local_adt(&mut self, def: &StructDef) {
+        self.local_tys.insert(def.name.clone());
+        let params = def.generics.iter().filter_map(|generic| match generic {
+            Generic::Type(ty) => Some(ty.name.clone()),
+            Generic::Constant(_) | Generic::Lifetime(_) => None,
+        }).collect();
+        self.adts.insert(def.name.clone(), AdtFields {
+            params,
+            tys: def.fields.iter().map(|field| field.ty.clone()).collect(),
+        });
+    }
+
+    /// the compiler-known trait the trait named `tait` is (if any)
+    pub(crate) fn builtin_trait(&self, tait: &str) -> Option<BuiltinTrait> {
+        let lang_item = self.lang_items.iter().find(|(_, name)| name.as_str() == tait).map(|(lang_item, _)| lang_item.as_str())?;
+        Some(match lang_item {
+            "send" => BuiltinTrait::Send,
+            "sync" => BuiltinTrait::Sync,
+            "copy" => BuiltinTrait::Copy,
+            "clone" => BuiltinTrait::Clone,
+            "sized" => BuiltinTrait::Sized,
+            _ => return None,
+        })
+    }
+
+    /// whether the trait named `tait` is known to the compiler
+    pub fn is_builtin_trait(&self, tait: &str) -> bool {
+        self.builtin_trait(tait).is_some()
+    }
+
+    /// whether the trait named `tait` is an auto trait, that is `Send` or `Sync`
+    pub fn is_auto_trait(&self, tait: &str) -> bool {
+        self.builtin_trait(tait).is_some_and(|builtin| builtin.is_auto())
+    }
+
+    /// whether `ty` implements the trait `tait` either through an impl or (for compiler-known traits) by the compiler
+    pub(crate) fn impl_holds(&self, ty: &Ty, tait: &Ty, env: &[(Ty, Ty)]) -> bool {
+        // trait objects implement their trait together with its supertraits
+        if let TyKind::Dyn(dyn_ty) = &ty.kind {
+            if self.elaborate(&dyn_ty.tait).contains(tait) {
+                return true;
+            }
+        }
+        let builtin = match self.builtin_trait(&tait.kind.simple_ty_name()) {
+            Some(builtin) => builtin,
+            None => return self.select_impl_in(ty, tait, env).is_some(),
+        };
+        match builtin {
+            BuiltinTrait::Send | BuiltinTrait::Sync => self.negative_impl(ty, tait).is_none()
+                && (self.select_impl_in(ty, tait, env).is_some() || self.auto_impl_holds(ty, tait, builtin, env)),
+            BuiltinTrait::Copy | BuiltinTrait::Clone => self.select_impl_in(ty, tait, env).is_some() || match &ty.kind {
+                TyKind::Ref(rf) => rf.mutability == Mutability::Immut,
+                TyKind::Array(array) => self.has_impl_in(&array.ty, tait, env),
+                TyKind::Func(_) => true,
+                TyKind::Owned(_) | TyKind::Projection(_) | TyKind::Dyn(_) => false,
+            },
+            BuiltinTrait::Sized => !matches!(ty.kind.get_owned(), Some(owned) if owned.name == "str") && !matches!(ty.kind, TyKind::Dyn(_)),
+        }
+    }
+
+    /// the negative impl like `impl !Send for Handle {}` which applies to `ty` (if any)
+    pub(crate) fn negative_impl(&self, ty: &Ty, tait: &Ty) -> Option<&ImplHeader> {
+        self.impls.get(&tait.kind.simple_ty_name()).into_iter().flatten()
+            .find(|header| header.negative && match_header(header, ty, tait, &mut HashMap::new()))
+    }
+
+    /// whether all components of `ty` implement the auto trait `tait`
+    fn auto_impl_holds(&self, ty: &Ty, tait: &Ty, builtin: BuiltinTrait, env: &[(Ty, Ty)]) -> bool {
+        match self.auto_components(ty, tait, builtin) {
+            Some(components) => components.iter().all(|(ty, tait)| self.has_impl_in(ty, tait, env)),
+            None => false,
+        }
+    }
+
+    /// the goals the auto trait `tait` requires to hold for the components of `ty`,
+    /// e.g. `[(u32, Send), (T, Send)]` for `struct Foo<T> { a: u32, b: &T }` and `Send`,
+    /// types which aren't made up of components (like generic params) only implement the auto traits they are bounded by
+    pub(crate) fn auto_components(&self, ty: &Ty, tait: &Ty, builtin: BuiltinTrait) -> Option<Vec<(Ty, Ty)>> {
+        Some(match &ty.kind {
+            TyKind::Owned(owned) => {
+                if owned.generics.is_empty() && (BUILTIN_IMPL_PRIMITIVES.contains(&owned.name.as_str()) || owned.name == "str") {
+                    return Some(vec![]);
+                }
+                let adt = self.adts.get(&owned.name)?;
+                let args = owned.generics.iter().filter_map(|generic| match generic {
+                    TyOrConstVal::Ty(ty) => Some(ty.clone()),
+                    TyOrConstVal::ConstVal(_) | TyOrConstVal::Lifetime(_) => None,
+                }).collect::<Vec<_>>();
+                // nothing is known about the fields of a type whose generic args are missing
+                if args.len() != adt.params.len() {
+                    return None;
+                }
+                let substs = adt.params.iter().cloned().zip(args).collect::<HashMap<_, _>>();
+                adt.tys.iter().map(|field| (field.substitute(&substs), tait.clone())).collect()
+            }
+            // `&T` can be sent to another thread if `T` can be shared between threads
+            TyKind::Ref(rf) if builtin == BuiltinTrait::Send && rf.mutability == Mutability::Immut => {
+                let sync = self.lang_item("sync").cloned().unwrap_or_else(|| "Sync".to_string());
+                vec![((*rf.ty).clone(), named_ty(sync))]
+            }
+            TyKind::Ref(rf) => vec![((*rf.ty).clone(), tait.clone())],
+            TyKind::Array(array) => vec![(array.ty.clone(), tait.clone())],
+            TyKind::Func(_) => vec![],
+            // the type behind a trait object is unknown, so it only implements the auto traits which are supertraits of its trait
+            TyKind::Projection(_) | TyKind::Dyn(_) => return None,
+        })
+    }
+
+}
@@ -0,0 +1,184 @@
+use crate::diagnostics::span::Span;
+use crate::parser::ast::{Ty, TyKind, TyOrConstVal};
+use crate::traitsolver::{mentions, named_ty, same_len, ImplHeader, TraitManager};
+use std::collections::HashMap;
+
+// coherence makes sure that there is at most one impl which applies to any goal `ty: Trait`:
+// two impls overlap if their headers unify, e.g. `impl<T: Copy> Shape for T` and `impl Shape for u8`
+// overlap in `u8: Shape` while `impl Shape for Vec<u8>` and `impl Shape for Vec<u16>` don't overlap.
+// the generic params of both impls are renamed apart first, so `T` of the first impl and `T` of the second one
+// are unified independently and an overlap is only reported if none of the bounds which mention no params anymore
+// rules the overlap out (`impl<T: Copy> Shape for T` doesn't overlap with `impl Shape for Circle` if `Circle: !Copy`).
+// the orphan rule additionally requires every impl to implement a local trait or to be for a local type,
+// as otherwise another crate could provide the same impl
+// see: https://rustc-dev-guide.rust-lang.org/coherence.html
+
+/// a violation of coherence, the impls are referred to by their headers, e.g. `impl<T> Shape for T`,
+/// together with the spans of the headers in the source
+pub enum CoherenceError {
+    /// two impls of `tait` apply to `ty`, params which stay generic are displayed as `_`
+    Overlap {
+        tait: String,
+        ty: Ty,
+        first: (String, Span),
+        second: (String, Span),
+    },
+    /// an impl of a foreign trait for a foreign type
+    Orphan {
+        imp: (String, Span),
+        tait: String,
+        ty: Ty,
+    },
+}
+
+impl TraitManager {
+
+    /// checks all impls for overlaps and violations of the orphan rule
+    pub fn check_coherence(&self) -> Vec<CoherenceError> {
+        let mut errors = vec![];
+        // sorted so the errors get reported in the same order every time
+        let mut taits = self.impls.keys().collect::<Vec<_>>();
+        taits.sort();
+        for tait in taits {
+            let impls = &self.impls[tait];
+            for (idx, second) in impls.iter().enumerate() {
+                if !second.builtin && !self.is_local_impl(second) {
+                    errors.push(CoherenceError::Orphan {
+                        imp: (second.to_string(), second.span),
+                        tait: tait.clone(),
+                        ty: second.self_ty.clone(),
+                    });
+                }
+                for first in &impls[..idx] {
+                    if first.builtin && second.builtin {
+                        continue;
+                    }
+                    if let Some(ty) = self.overlap(first, second) {
+                        errors.push(CoherenceError::Overlap {
+                            tait: tait.clone(),
+                            ty,
+                            first: (first.to_string(), first.span),
+                            second: (second.to_string(), second.span),
+                        });
+                    }
+                }
+            }
+        }
+        errors
+    }
+
+    /// unifies the headers of `first` and `second` and returns the type both of them apply to (if any)
+    fn overlap(&self, first: &ImplHeader, second: &ImplHeader) -> Option<Ty> {
+        let (first_renames, first_vars) = rename_params(first, 0);
+        let (second_renames, second_vars) = rename_params(second, 1);
+        let vars = first_vars.into_iter().chain(second_vars).collect::<Vec<_>>();
+
+        let mut substs = HashMap::new();
+        let self_ty = first.self_ty.substitute(&first_renames);
+        if !unify(&self_ty, &second.self_ty.substitute(&second_renames), &vars, &mut substs)
+            || !unify(&first.tait.substitute(&first_renames), &second.tait.substitute(&second_renames), &vars, &mut substs) {
+            return None;
+        }
+
+        let obligations = first.obligations.iter().map(|obligation| (obligation, &first_renames))
+            .chain(second.obligations.iter().map(|obligation| (obligation, &second_renames)));
+        for ((ty, bound), renames) in obligations {
+            let ty = resolve(&ty.substitute(renames), &substs);
+            let bound = resolve(&bound.substitute(renames), &substs);
+            // bounds on types which are still generic could be satisfied by some type
+            // FIXME: check whether there is any impl at all which could satisfy them
+            if vars.iter().any(|var| mentions(&ty, var) || mentions(&bound, var)) {
+                continue;
+            }
+            if !self.has_impl(&ty, &bound) {
+                return None;
+            }
+        }
+
+        let placeholders = vars.iter().map(|var| (var.clone(), named_ty("_".to_string()))).collect();
+        Some(resolve(&self_ty, &substs).substitute(&placeholders))
+    }
+
+    /// whether the impl is allowed by the orphan rule, that is whether its trait or its self type
+    /// (or one of the args of its trait) are defined in the current crate
+    fn is_local_impl(&self, header: &ImplHeader) -> bool {
+        self.ty_dependencies.contains_key(&header.tait.kind.simple_ty_name())
+            || self.is_local_ty(&header.self_ty, &header.params)
+            || header.tait.kind.get_generics().iter().any(|generic| matches!(generic, TyOrConstVal::Ty(ty) if self.is_local_ty(ty, &header.params)))
+    }
+
+    /// whether `ty` is defined in the current crate, references to local types and objects of local traits count as local as well
+    fn is_local_ty(&self, ty: &Ty, params: &[String]) -> bool {
+        match &ty.kind {
+            TyKind::Owned(owned) => !params.contains(&owned.name) && self.local_tys.contains(&owned.name),
+            TyKind::Ref(rf) => self.is_local_ty(&rf.ty, params),
+            TyKind::Dyn(dyn_ty) => self.ty_dependencies.contains_key(&dyn_ty.tait.kind.simple_ty_name()),
+            _ => false,
+        }
+    }
+
+}
+
+/// renames the generic params of `header` so they don't clash with the ones of the impl it's compared to,
+/// e.g. `T` becomes `T#0`
+fn rename_params(header: &ImplHeader, idx: usize) -> (HashMap<String, Ty>, Vec<String>) {
+    let vars = header.params.iter().map(|param| format!("{}#{}", param, idx)).collect::<Vec<_>>();
+    let renames = header.params.iter().cloned().zip(vars.iter().map(|var| named_ty(var.clone()))).collect();
+    (renames, vars)
+}
+
+/// replaces the variables in `ty` by the types they were unified with
+fn resolve(ty: &Ty, substs: &HashMap<String, Ty>) -> Ty {
+    let mut ty = ty.clone();
+    // variables may be bound to types containing other variables, the occurs check guarantees that this terminates
+    loop {
+        let resolved = ty.substitute(substs);
+        if resolved == ty {
+            return ty;
+        }
+        ty = resolved;
+    }
+}
+
+/// the name of `ty` if it's one of the variables `vars`
+fn as_var<'a>(ty: &'a Ty, vars: &[String]) -> Option<&'a String> {
+    match ty.kind.get_owned() {
+        Some(owned) if owned.generics.is_empty() && vars.contains(&owned.name) => Some(&owned.name),
+        _ => None,
+    }
+}
+
+/// unifies `left` and `right` where both of them may contain the variables `vars`
+fn unify(left: &Ty, right: &Ty, vars: &[String], substs: &mut HashMap<String, Ty>) -> bool {
+    let (left, right) = (resolve(left, substs), resolve(right, substs));
+    match (as_var(&left, vars), as_var(&right, vars)) {
+        (Some(left_var), Some(right_var)) if left_var == right_var => true,
+        (Some(var), _) => bind(var, &right, substs),
+        (_, Some(var)) => bind(var, &left, substs),
+        _ => match (&left.kind, &right.kind) {
+            (TyKind::Owned(left_owned), TyKind::Owned(right_owned)) => {
+                left_owned.name == right_owned.name
+                    && left_owned.generics.len() == right_owned.generics.len()
+                    && left_owned.generics.iter().zip(right_owned.generics.iter()).all(|generics| match generics {
+                        (TyOrConstVal::Ty(left), TyOrConstVal::Ty(right)) => unify(left, right, vars, substs),
+                        (TyOrConstVal::Lifetime(_), TyOrConstVal::Lifetime(_)) => true,
+                        // FIXME: unify const params as well
+                        (TyOrConstVal::Ty(param), TyOrConstVal::ConstVal(_)) | (TyOrConstVal::ConstVal(_), TyOrConstVal::Ty(param)) => as_var(param, vars).is_some(),
+                        (left, right) => left == right,
+                    })
+            }
+            (TyKind::Ref(left_ref), TyKind::Ref(right_ref)) => left_ref.mutability == right_ref.mutability && unify(&left_ref.ty, &right_ref.ty, vars, substs),
+            (TyKind::Array(left_array), TyKind::Array(right_array)) => same_len(left_array, right_array) && unify(&left_array.ty, &right_array.ty, vars, substs),
+            _ => left == right,
+        },
+    }
+}
+
+fn bind(var: &String, ty: &Ty, substs: &mut HashMap<String, Ty>) -> bool {
+    // `T = Vec<T>` has no finite solution
+    if mentions(ty, var) {
+        return false;
+    }
+    substs.insert(var.clone(), ty.clone());
+    true
+}
@@ -0,0 +1,151 @@
+use crate::parser::ast::Ty;
+use crate::traitsolver::{named_ty, TraitManager};
+use std::collections::HashMap;
+
+// goals get evaluated recursively, proving `Vec<u8>: Clone` via `impl<T: Clone> Clone for Vec<T>` requires proving `u8: Clone`.
+// the goals which are currently being evaluated are kept on a stack, so a goal which depends on itself is detected:
+// `impl<T: A> B for T` and `impl<T: B> A for T` result in the cycle `u8: A` -> `u8: B` -> `u8: A`.
+// such cycles are errors for most traits (there is no impl to start from, so nothing can be proven inductively),
+// but they hold for auto traits, e.g. `struct List { next: &List }` is `Send` if all of its fields are (coinduction).
+// goals which keep on growing without repeating, like `u8: A` -> `Vec<u8>: A` -> `Vec<Vec<u8>>: A` -> ..., are
+// stopped by the recursion limit instead.
+// the results of evaluated goals get cached, except for the ones which were evaluated while assuming the result of
+// a goal further up the stack (or while running into an error), as they might not hold on their own
+// see: https://rustc-dev-guide.rust-lang.org/traits/caching.html
+
+/// the default for the maximum number of nested goals, this can be changed with `#![recursion_limit = "N"]`
+pub const DEFAULT_RECURSION_LIMIT: usize = 128;
+
+/// a goal `ty: tait` together with the bounds it was evaluated under in a form which doesn't depend on the naming of
+/// generic params, e.g. both `Vec<T>: Clone` with `T: Clone` and `Vec<U>: Clone` with `U: Clone` become `Vec<^0>: Clone` with `^0: Clone`
+#[derive(Clone, PartialEq, Eq, Hash)]
+struct CanonicalGoal {
+    goal: String,
+    env: Vec<String>,
+}
+
+/// the state of the goals which are currently being evaluated
+#[derive(Default)]
+pub(crate) struct EvalState {
+    cache: HashMap<CanonicalGoal, bool>,
+    // the goals which are currently being evaluated together with a readable form of them
+    // and whether their cycles hold
+    stack: Vec<(CanonicalGoal, String, bool)>,
+    // the lowest index in `stack` whose result got assumed by a cycle, the results of the goals above it can't be cached
+    cycle_head: Option<usize>,
+    errors: Vec<SolverError>,
+}
+
+#[derive(Clone, PartialEq)]
+pub enum SolverError {
+    /// the goal depends on itself, the goals which lead back to it are listed in `cycle`, starting with `goal` itself
+    Cycle {
+        goal: String,
+        cycle: Vec<String>,
+    },
+    /// more than `limit` nested goals were required to prove `goal`
+    Overflow {
+        goal: String,
+        limit: usize,
+    },
+}
+
+impl TraitManager {
+
+    pub fn set_recursion_limit(&mut self, limit: usize) {
+        self.recursion_limit = limit;
+    }
+
+    /// returns the cycles and overflows the solver ran into since the last call
+    pub fn take_errors(&self) -> Vec<SolverError> {
+        std::mem::take(&mut self.eval.borrow_mut().errors)
+    }
+
+    /// the number of errors the solver ran into since the last call of `take_errors`
+    pub(crate) fn num_errors(&self) -> usize {
+        self.eval.borrow().errors.len()
+    }
+
+    /// forgets the errors after the first `len` ones, e.g. the ones which were only found again while explaining a goal
+    pub(crate) fn truncate_errors(&self, len: usize) {
+        self.eval.borrow_mut().errors.truncate(len);
+    }
+
+    /// evaluates whether `ty` implements `tait` under `env` by selecting an impl (see `impl_holds`),
+    /// detects cycles and caches the result if it doesn't depend on any other goal which is still being evaluated
+    pub(crate) fn evaluate(&self, ty: &Ty, tait: &Ty, env: &[(Ty, Ty)]) -> bool {
+        let goal = canonicalize(ty, tait, env);
+        let depth = {
+            let mut eval = self.eval.borrow_mut();
+            if let Some(holds) = eval.cache.get(&goal) {
+                return *holds;
+            }
+            if let Some(idx) = eval.stack.iter().position(|(other, _, _)| other == &goal) {
+                eval.cycle_head = Some(eval.cycle_head.map_or(idx, |head| head.min(idx)));
+                let coinductive = eval.stack[idx..].iter().all(|(_, _, coinductive)| *coinductive);
+                if !coinductive {
+                    let cycle = eval.stack[idx..].iter().map(|(_, readable, _)| readable.clone()).collect::<Vec<_>>();
+                    eval.errors.push(SolverError::Cycle {
+                        goal: cycle[0].clone(),
+                        cycle,
+                    });
+                }
+                return coinductive;
+            }
+            if eval.stack.len() >= self.recursion_limit {
+                // none of the goals on the stack could be proven completely
+                eval.cycle_head = Some(0);
+                eval.errors.push(SolverError::Overflow {
+                    goal: format!("{}: {}", ty.to_string(), tait.to_string()),
+                    limit: self.recursion_limit,
+                });
+                return false;
+            }
+            let coinductive = self.is_auto_trait(&tait.kind.simple_ty_name());
+            eval.stack.push((goal.clone(), format!("{}: {}", ty.to_string(), tait.to_string()), coinductive));
+            eval.stack.len() - 1
+        };
+        let errors_before = self.eval.borrow().errors.len();
+
+        let holds = self.impl_holds(ty, tait, env);
+
+        let mut eval = self.eval.borrow_mut();
+        eval.stack.pop();
+        let provisional = match eval.cycle_head {
+            Some(head) if head < depth => true,
+            // the cycle is completed once its head is evaluated
+            Some(_) => {
+                eval.cycle_head = None;
+                false
+            }
+            None => false,
+        };
+        if !provisional && eval.errors.len() == errors_before {
+            eval.cache.insert(goal, holds);
+        }
+        holds
+    }
+
+}
+
+/// renames the generic params (the types `env` makes assumptions about) to `^0`, `^1`, ... in the order `env` mentions them
+fn canonicalize(ty: &Ty, tait: &Ty, env: &[(Ty, Ty)]) -> CanonicalGoal {
+    let mut params: Vec<String> = vec![];
+    for (assumed, _) in env {
+        if let Some(owned) = assumed.kind.get_owned() {
+            if owned.generics.is_empty() && !params.contains(&owned.name) {
+                params.push(owned.name.clone());
+            }
+        }
+    }
+    let renames = params.iter().enumerate().map(|(idx, param)| (param.clone(), named_ty(format!("^{}", idx)))).collect::<HashMap<_, _>>();
+    let mut env = env.iter()
+        .map(|(assumed, bound)| format!("{}: {}", assumed.substitute(&renames).to_string(), bound.substitute(&renames).to_string()))
+        .collect::<Vec<_>>();
+    env.sort();
+    env.dedup();
+    CanonicalGoal {
+        goal: format!("{}: {}", ty.substitute(&renames).to_string(), tait.substitute(&renames).to_string()),
+        env,
+    }
+}
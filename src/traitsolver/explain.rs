@@ -0,0 +1,120 @@
+use crate::diagnostics::span::Span;
+use crate::parser::ast::Ty;
+use crate::traitsolver::{instantiate_obligations, match_header, TraitManager};
+use std::collections::HashMap;
+
+// when a goal doesn't hold, the solver is asked again why that is the case, which results in a proof tree:
+// every impl whose header matches the goal is a candidate which failed because some of its obligations don't hold,
+// these obligations are explained recursively, so `Empire<Civilization<Continent>>: Rule` can be traced back to
+// `Civilization<Continent>: CivBase` and from there to `Continent: Peaceful`.
+// goals without any candidate list the impls of their trait which exist but don't apply (near misses) instead.
+// auto traits are explained by the components of the type which don't implement them or by the negative impl opting out.
+// goals which are already being explained further up the tree aren't explained again, so cycles like
+// `u8: A` -> `u8: B` -> `u8: A` end at the repeated goal. the solver only gets asked again about goals whose
+// errors (cycles and overflows) were already found while evaluating the root goal, so they're dropped
+
+/// the maximum depth of the obligations which get explained, the deeper ones are only mentioned
+const MAX_EXPLAIN_DEPTH: usize = 8;
+
+/// explains why the goal `ty: tait` doesn't hold
+pub struct ProofTree {
+    pub(crate) ty: String,
+    pub(crate) tait: String,
+    /// the impls whose headers match the goal together with their obligations which don't hold
+    pub(crate) candidates: Vec<FailedCandidate>,
+    /// the impls of the trait whose headers don't match the goal, e.g. `impl Shape for Circle` for `u8: Shape`
+    pub(crate) near_misses: Vec<String>,
+    /// the negative impl which opts the type out of the auto trait, e.g. `impl !Send for Handle`
+    pub(crate) negative: Option<String>,
+}
+
+impl ProofTree {
+
+    /// the tree of a goal which isn't explained any further
+    fn leaf(ty: &Ty, tait: &Ty) -> Self {
+        Self {
+            ty: ty.to_string(),
+            tait: tait.to_string(),
+            candidates: vec![],
+            near_misses: vec![],
+            negative: None,
+        }
+    }
+
+}
+
+pub struct FailedCandidate {
+    /// what requires the obligations, e.g. "the impl `impl<T: Clone> Clone for Vec<T>`"
+    pub(crate) required_by: String,
+    /// the span of the impl which requires the obligations, `NONE` for auto traits and impls without a span
+    pub(crate) span: Span,
+    pub(crate) failed: Vec<ProofTree>,
+}
+
+impl TraitManager {
+
+    /// builds the proof tree of the goal `ty: tait` under `env`, this is only meaningful if the goal doesn't hold
+    pub fn explain(&self, ty: &Ty, tait: &Ty, env: &[(Ty, Ty)]) -> ProofTree {
+        let errors = self.num_errors();
+        let tree = self.explain_goal(ty, tait, env, &mut vec![]);
+        self.truncate_errors(errors);
+        tree
+    }
+
+    /// `stack` holds the goals which are currently being explained
+    fn explain_goal(&self, ty: &Ty, tait: &Ty, env: &[(Ty, Ty)], stack: &mut Vec<(Ty, Ty)>) -> ProofTree {
+        let mut tree = ProofTree::leaf(ty, tait);
+        let goal = (ty.clone(), tait.clone());
+        if stack.len() >= MAX_EXPLAIN_DEPTH || stack.contains(&goal) {
+            return tree;
+        }
+        stack.push(goal);
+        if let Some(builtin) = self.builtin_trait(&tait.kind.simple_ty_name()).filter(|builtin| builtin.is_auto()) {
+            if let Some(negative) = self.negative_impl(ty, tait) {
+                tree.negative = Some(negative.to_string());
+                stack.pop();
+                return tree;
+            }
+            if let Some(components) = self.auto_components(ty, tait, builtin) {
+                tree.candidates.push(FailedCandidate {
+                    required_by: if ty.kind.get_owned().is_some() { format!("the fields of `{}`", ty.to_string()) } else { format!("`{}`", ty.to_string()) },
+                    span: Span::NONE,
+                    failed: self.explain_failed(components, env, stack),
+                });
+            }
+        }
+        for header in self.impls.get(&tait.kind.simple_ty_name()).into_iter().flatten() {
+            if header.negative {
+                continue;
+            }
+            let mut substs = HashMap::new();
+            if !match_header(header, ty, tait, &mut substs) {
+                // the built-in impls would only clutter the notes
+                if !header.builtin {
+                    tree.near_misses.push(header.to_string());
+                }
+                continue;
+            }
+            tree.candidates.push(FailedCandidate {
+                required_by: format!("the impl `{}`", header),
+                span: header.span,
+                failed: match instantiate_obligations(header, &substs) {
+                    Ok(obligations) => self.explain_failed(obligations, env, stack),
+                    // the bounds of unbound params never hold, there is nothing more to explain about them
+                    Err(unbound) => unbound.iter().map(|(ty, bound)| ProofTree::leaf(ty, bound)).collect(),
+                },
+            });
+        }
+        stack.pop();
+        tree
+    }
+
+    /// explains the `obligations` which don't hold
+    fn explain_failed(&self, obligations: Vec<(Ty, Ty)>, env: &[(Ty, Ty)], stack: &mut Vec<(Ty, Ty)>) -> Vec<ProofTree> {
+        obligations.into_iter()
+            .filter(|(ty, bound)| !self.has_impl_in(ty, bound, env))
+            .map(|(ty, bound)| self.explain_goal(&ty, &bound, env, stack))
+            .collect()
+    }
+
+}
@@ -1,5 +1,19 @@
-use crate::parser::ast::{AstNode, Ty, TyOrConstVal};
-use std::collections::HashMap;
+use crate::diagnostics::span::Span;
+use crate::parser::ast::{AdtImpl, ArrayTy, Generic, ItemKind, OwnedTy, TraitDef, Ty, TyKind, TyOrConstVal, WherePredicate};
+use crate::parser::derive::DERIVABLE_TRAITS;
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
+use std::fmt::{Display, Formatter};
+
+mod builtin;
+mod coherence;
+mod eval;
+mod explain;
+
+pub use builtin::BUILTIN_LANG_ITEMS;
+pub use coherence::CoherenceError;
+pub use eval::{SolverError, DEFAULT_RECURSION_LIMIT};
+pub use explain::ProofTree;
 
 // FIXME: maybe helpful: https://rustc-dev-guide.rust-lang.org/traits/resolution.html
 // https://smallcultfollowing.com/babysteps/blog/2017/01/26/lowering-rust-traits-to-logic/
@@ -24,113 +38,426 @@ pub struct TraitManager {
     // trait Tait: Tait2 + Tait3 {}
     // this represents a mapping from Tait to a list of [Tait2, Tait3] which are the dependencies of Tait
     ty_dependencies: HashMap<String, TraitEntry>,
-    /// impl trait for ty
-    /// -> map<simple trait name, (trait, list[any{(ty, generic obligations), obligations}])>
-    impl_constraints: HashMap<String, TraitImplEntry>,
-    deferred_checks: HashMap<String, Vec<Ty>>,
-    /// map<trait, list[ty]>
-    used_impls: HashMap<Ty, Vec<Ty>>,
+    /// impl<T: Clone> Trait<T> for Vec<T> {}
+    /// -> map<simple trait name, list[impl headers]>
+    impls: HashMap<String, Vec<ImplHeader>>,
+    /// impl trait for ty { type Item = ...; const N: usize = ...; }
+    /// -> map<simple ty name, list[associated items provided by the impls for ty]>
+    assoc_items: HashMap<String, Vec<ImplAssocItems>>,
+    /// #[lang = "add"] trait Add {}
+    /// -> map<lang item, trait name>
+    lang_items: HashMap<String, String>,
+    /// the names of the types defined in the current crate
+    local_tys: HashSet<String>,
+    /// map<struct name, the types of its fields>, the auto traits are implemented based on them
+    adts: HashMap<String, builtin::AdtFields>,
+    /// the maximum number of nested goals
+    recursion_limit: usize,
+    /// the cache and the stack of the goals which are being evaluated, see `evaluate`
+    eval: RefCell<eval::EvalState>,
 }
 
 struct TraitEntry {
     tait: Ty,
     dependencies: Vec<Ty>,
-    ctx: HashMap<String, Vec<Ty>>,
 }
 
-struct TraitImplEntry {
+struct ImplAssocItems {
     tait: Ty,
-    goals: Vec<(GoalTarget, HashMap<String, Vec<Ty>>)>,
+    ty: Ty,
+    // the names of the impl's generic params
+    generics: Vec<String>,
+    tys: HashMap<String, Ty>,
 }
 
-pub enum GenericGoalTarget {
-    Val {
-        ty: Ty,
-        generics: Vec<GenericGoalTarget>,
-    },
-    Const {
-        val: AstNode,
-    },
-    Obligation {
-        constraints: Vec<Ty>,
-    },
+/// the header of a trait impl, e.g. `impl<T: Clone> Trait<T> for Vec<T> where Vec<T>: Debug`
+pub struct ImplHeader {
+    pub(crate) tait: Ty,
+    pub(crate) self_ty: Ty,
+    // the names of the impl's generic params
+    pub(crate) params: Vec<String>,
+    // the bounds which have to hold for the impl to apply, e.g. `[(T, Clone), (Vec<T>, Debug)]`
+    pub(crate) obligations: Vec<(Ty, Ty)>,
+    // whether the impl is provided by the compiler itself
+    pub(crate) builtin: bool,
+    // whether the impl opts out of an auto trait, e.g. `impl !Send for Handle {}`
+    pub(crate) negative: bool,
+    // the span of the impl's header in the source, `NONE` for built-in and derived impls
+    pub(crate) span: Span,
 }
 
-pub enum GoalTarget {
-    Val {
-        ty: Ty,
-        generics: Vec<GoalTarget>,
-    },
-    Obligation {
-        constraints: Vec<Ty>,
-    },
+/// the header as it would be written, e.g. `impl<T: Clone> Trait<T> for Vec<T> where Vec<T>: Debug`
+impl Display for ImplHeader {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let mut params = vec![];
+        let mut where_clause = vec![];
+        for param in &self.params {
+            let bounds = self.obligations.iter()
+                .filter(|(ty, _)| matches!(ty.kind.get_owned(), Some(owned) if &owned.name == param && owned.generics.is_empty()))
+                .map(|(_, bound)| bound.to_string())
+                .collect::<Vec<_>>();
+            params.push(if bounds.is_empty() { param.clone() } else { format!("{}: {}", param, bounds.join(" + ")) });
+        }
+        for (ty, bound) in &self.obligations {
+            if !matches!(ty.kind.get_owned(), Some(owned) if self.params.contains(&owned.name) && owned.generics.is_empty()) {
+                where_clause.push(format!("{}: {}", ty.to_string(), bound.to_string()));
+            }
+        }
+        let params = if params.is_empty() { String::new() } else { format!("<{}>", params.join(", ")) };
+        let where_clause = if where_clause.is_empty() { String::new() } else { format!(" where {}", where_clause.join(", ")) };
+        write!(f, "impl{} {}{} for {}{}", params, if self.negative { "!" } else { "" }, self.tait.to_string(), self.self_ty.to_string(), where_clause)
+    }
 }
 
 impl TraitManager {
-    pub fn insert_trait(&mut self, tait: Ty, dependencies: Vec<Ty>, ctx: HashMap<String, Vec<Ty>>) {
-        self.ty_dependencies.insert(tait.kind.simple_ty_name(), v);
+    pub fn new() -> Self {
+        Self {
+            ty_dependencies: HashMap::new(),
+            impls: HashMap::new(),
+            assoc_items: HashMap::new(),
+            lang_items: BUILTIN_LANG_ITEMS.iter().map(|(lang_item, tait)| (lang_item.to_string(), tait.to_string())).collect(),
+            local_tys: HashSet::new(),
+            adts: HashMap::new(),
+            recursion_limit: DEFAULT_RECURSION_LIMIT,
+            eval: RefCell::new(eval::EvalState::default()),
+        }
+    }
+
+    pub fn insert_trait(&mut self, tait: Ty, dependencies: Vec<Ty>) {
+        self.ty_dependencies.insert(tait.kind.simple_ty_name(), TraitEntry { tait, dependencies });
+    }
+
+    /// registers a trait definition together with its supertraits
+    pub fn insert_trait_def(&mut self, def: &TraitDef) {
+        let generics = def.generics.iter().filter_map(|generic| match generic {
+            Generic::Type(ty) => Some(TyOrConstVal::Ty(named_ty(ty.name.clone()))),
+            Generic::Constant(_) | Generic::Lifetime(_) => None,
+        }).collect();
+        let tait = Ty {
+            kind: TyKind::Owned(Box::new(OwnedTy {
+                name: def.name.clone(),
+                generics,
+            })),
+        };
+        self.insert_trait(tait, def.req_sub_traits.to_vec());
     }
 
-    pub fn insert_impl(&mut self, tait: &Ty, obligations: GoalTarget, ctx: HashMap<String, Vec<Ty>>) {
+    pub fn insert_impl(&mut self, header: ImplHeader) {
         // FIXME: trait Trait<T: Bound1> {}
         // FIXME: for: impl<K: Bound2> Trait<K> for Ty {}
         // FIXME: check if Bound2 is at least as strict as Bound1
         // FIXME: more generally: (check if bounds for generics passed to Tait's generics are at least as strict as the bounds of Tait's generics themselves)
+        self.impls.entry(header.tait.kind.simple_ty_name()).or_default().push(header);
+    }
+
+    /// registers the trait (or struct) `tait` as the lang item `name`, e.g. as the trait which overloads `+` for `add`
+    /// or as the struct which owns the values behind `Box<dyn Trait>` for `owned_box`
+    pub fn insert_lang_item(&mut self, name: String, tait: String) {
+        self.lang_items.insert(name, tait);
+    }
+
+    /// returns the name of the trait (or struct) which was registered as the lang item `name`
+    pub fn lang_item(&self, name: &str) -> Option<&String> {
+        self.lang_items.get(name)
+    }
+
+    /// registers the impls of the derivable built-in traits for primitive types
+    pub fn insert_builtin_impls(&mut self) {
+        for tait in DERIVABLE_TRAITS {
+            let tait = named_ty(tait.to_string());
+            for primitive in BUILTIN_IMPL_PRIMITIVES {
+                self.insert_impl(builtin_impl(tait.clone(), primitive));
+            }
+        }
+        // `str` is unsized, so it can only be used behind references
+        for tait in ["Debug", "PartialEq"] {
+            self.insert_impl(builtin_impl(named_ty(tait.to_string()), "str"));
+        }
+    }
 
-        // FIXME: this system is incomplete, how should we handle things like `impl<T: Clone> Trait for Test<T> {`?
-        self.impl_constraints.entry(tait.kind.simple_ty_name()).or_insert_with(|| TraitImplEntry { tait: tait.clone(), goals: vec![] }).goals.push((obligations, ctx));
+    /// registers a trait impl of a user defined type
+    pub fn insert_adt_impl(&mut self, adt_impl: &AdtImpl) {
+        let tait = if let Some(tait) = &adt_impl.impl_trait {
+            tait
+        } else {
+            return;
+        };
+        let params = adt_impl.generics.iter().filter_map(|generic| match generic {
+            Generic::Type(ty) => Some(ty.name.clone()),
+            Generic::Constant(constant) => Some(constant.name.clone()),
+            Generic::Lifetime(_) => None,
+        }).collect();
+        self.insert_impl(ImplHeader {
+            tait: tait.clone(),
+            self_ty: adt_impl.ty.clone(),
+            params,
+            obligations: collect_obligations(&adt_impl.generics, &adt_impl.where_clause),
+            builtin: false,
+            negative: adt_impl.negative,
+            span: adt_impl.span,
+        });
     }
 
     pub fn has_impl(&self, ty: &Ty, tait: &Ty) -> bool {
+        self.has_impl_in(ty, tait, &[])
+    }
+
+    /// whether `ty` implements `tait` assuming the bounds in `env` hold, the bounds of the generic params of
+    /// the item which is currently being checked are passed as `env` (see `elaborate`)
+    pub fn has_impl_in(&self, ty: &Ty, tait: &Ty, env: &[(Ty, Ty)]) -> bool {
+        let assumed = env.iter().any(|(assumed_ty, bound)| {
+            assumed_ty == ty && bound.kind.simple_ty_name() == tait.kind.simple_ty_name()
+                // a bound without args is satisfied by any args, e.g. `T: Add` by `T: Add<u8>`
+                && (tait.kind.get_generics().is_empty() || bound == tait)
+        });
+        assumed || self.evaluate(ty, tait, env)
+    }
+
+    /// returns `bound` together with all of its (transitive) supertraits
+    /// e.g. `[Test, Clone, Copy]` for `trait Test: Clone + Copy`
+    pub fn elaborate(&self, bound: &Ty) -> Vec<Ty> {
+        let mut elaborated = vec![bound.clone()];
+        let mut idx = 0;
+        while idx < elaborated.len() {
+            if let Some(entry) = self.ty_dependencies.get(&elaborated[idx].kind.simple_ty_name()) {
+                // the supertraits may refer to the trait's generics, e.g. `trait Sub<T>: Super<T>`
+                let substs = entry.tait.kind.get_generics().iter().zip(elaborated[idx].kind.get_generics().iter())
+                    .filter_map(|generics| match generics {
+                        (TyOrConstVal::Ty(param), TyOrConstVal::Ty(arg)) => Some((param.kind.simple_ty_name(), arg.clone())),
+                        _ => None,
+                    })
+                    .collect::<HashMap<_, _>>();
+                for dependency in &entry.dependencies {
+                    let dependency = dependency.substitute(&substs);
+                    // this also protects against cycles between supertraits
+                    if !elaborated.contains(&dependency) {
+                        elaborated.push(dependency);
+                    }
+                }
+            }
+            idx += 1;
+        }
+        elaborated
+    }
+
+    /// whether the solver knows about a trait named `tait`, either because it was defined, because it has impls
+    /// or because it's known to the compiler
+    pub fn is_known_trait(&self, tait: &str) -> bool {
+        self.ty_dependencies.contains_key(tait) || self.impls.contains_key(tait) || self.is_builtin_trait(tait)
+    }
+
+    /// selects the impl of `tait` which applies to `ty` by matching `ty` and the args of `tait` against the impl headers,
+    /// this binds the impl's generic params whose bounds then have to be proven recursively
+    /// e.g. `Vec<u8>: Trait` selects `impl<T: Clone> Trait for Vec<T>` with `T = u8` as `u8: Clone` holds,
+    /// the bounds in `env` are assumed to hold, the types the impl's generic params got bound to are returned
+    pub fn select_impl_in(&self, ty: &Ty, tait: &Ty, env: &[(Ty, Ty)]) -> Option<HashMap<String, Ty>> {
         // we use `simple_ty_name` instead of `to_string` because we need to ignore the naming of generics in case of
         // impl<K> Trait<K> for Ty {}
-        if let Some(ty_val) = self.impl_constraints.get(&tait.kind.simple_ty_name()) {
-            'outer: for (imp, ctx) in &ty_val.goals {
-                match &imp {
-                    // case: impl<T: X + Y + Z> Trait for T {}
-                    GoalTarget::Obligation { constraints } => {
-                        for constraint in constraints {
-                            if !self.has_impl(ty, constraint) {
-                                continue 'outer;
-                            }
-                        }
-                        return true;
-                    },
-                    // case: impl<T: X + Y + Z, K> Trait for Ty<T, K> {}
-                    GoalTarget::Val { ty: val_ty, generics } => {
-                        assert!(val_ty.kind.get_owned().unwrap().generics.len() == generics.len());
-                        // if parent types don't match then don't even check generics
-                        if val_ty.kind.simple_ty_name() != ty.kind.simple_ty_name() {
-                            return false;
-                        }
-                        if !self.check_generics(ty, ctx) {
-                            continue 'outer;
-                        }
-                        return true;
-                    },
+        for header in self.impls.get(&tait.kind.simple_ty_name()).into_iter().flatten() {
+            let mut substs = HashMap::new();
+            if !header.negative && match_header(header, ty, tait, &mut substs) && self.obligations_hold(header, &substs, env) {
+                return Some(substs);
+            }
+        }
+        None
+    }
+
+    fn obligations_hold(&self, header: &ImplHeader, substs: &HashMap<String, Ty>, env: &[(Ty, Ty)]) -> bool {
+        instantiate_obligations(header, substs).is_ok_and(|obligations| obligations.iter().all(|(ty, bound)| self.has_impl_in(ty, bound, env)))
+    }
+
+    /// registers the associated types provided by a trait impl
+    pub fn insert_assoc_items(&mut self, adt_impl: &AdtImpl) {
+        let tait = if let Some(tait) = &adt_impl.impl_trait {
+            tait.clone()
+        } else {
+            // inherent impls can't provide associated types
+            return;
+        };
+        let mut tys = HashMap::new();
+        for item in &*adt_impl.methods {
+            if let ItemKind::TyAlias(alias) = item {
+                tys.insert(alias.name.clone(), alias.ty.clone());
+            }
+        }
+        let generics = adt_impl.generics.iter().filter_map(|generic| match generic {
+            Generic::Type(ty) => Some(ty.name.clone()),
+            Generic::Constant(constant) => Some(constant.name.clone()),
+            Generic::Lifetime(_) => None,
+        }).collect();
+        self.assoc_items.entry(adt_impl.ty.kind.simple_ty_name()).or_default().push(ImplAssocItems {
+            tait,
+            ty: adt_impl.ty.clone(),
+            generics,
+            tys,
+        });
+    }
+
+    /// resolves the projection `ty::name` to the type the matching impl provides for it
+    pub fn resolve_projection(&self, ty: &Ty, name: &String) -> Option<Ty> {
+        let mut found = None;
+        for entry in self.assoc_items.get(&ty.kind.simple_ty_name())? {
+            if let Some(assoc_ty) = entry.tys.get(name) {
+                let mut bindings = HashMap::new();
+                if !match_impl_ty(&entry.ty, ty, &entry.generics, &mut bindings) {
+                    continue;
+                }
+                if found.is_some() {
+                    // FIXME: report ambiguous projections (this requires `<ty as Trait>::name` to be supported)
+                    return None;
                 }
+                found = Some(assoc_ty.substitute(&bindings));
             }
         }
-        false
+        found
     }
 
-    fn check_generics(&self, ty: &Ty, ctx: &HashMap<String, Vec<Ty>>) -> bool {
-        for generic in &ty.kind.get_owned().unwrap().generics {
-            if !match generic {
-                TyOrConstVal::ConstVal(val) => todo!(),
-                // FIXME: we need to be able to check this ty's generics as well!
-                TyOrConstVal::Ty(generic_ty) => {
-                    if let Some(val) = generic_ty.kind.get_owned() {
-                        if let Some(constraints) = ctx.get(&val.name) {
-                            assert!(val.generics.is_empty());
-                            
-                        }
-                    }
-                },
-            } {
-                return false;
+    /// finds the impls of the trait named `tait` for `ty` and returns the implemented trait together with the impl's
+    /// associated types, e.g. `impl<T> Add<T> for Wrapper<T> { type Output = T; }` results in `(Add<u8>, {Output: u8})` for `Wrapper<u8>`
+    pub fn find_impls(&self, ty: &Ty, tait: &str) -> Vec<(Ty, HashMap<String, Ty>)> {
+        let mut impls = vec![];
+        for entry in self.assoc_items.get(&ty.kind.simple_ty_name()).into_iter().flatten() {
+            if entry.tait.kind.simple_ty_name() != tait {
+                continue;
             }
+            let mut bindings = HashMap::new();
+            if match_impl_ty(&entry.ty, ty, &entry.generics, &mut bindings) {
+                let tys = entry.tys.iter().map(|(name, assoc_ty)| (name.clone(), assoc_ty.substitute(&bindings))).collect();
+                impls.push((entry.tait.substitute(&bindings), tys));
+            }
+        }
+        impls
+    }
+}
+
+const BUILTIN_IMPL_PRIMITIVES: [&str; 15] = ["u8", "u16", "u32", "u64", "u128", "usize", "i8", "i16", "i32", "i64", "i128", "isize", "f32", "f64", "bool"];
+
+fn builtin_impl(tait: Ty, primitive: &str) -> ImplHeader {
+    ImplHeader {
+        tait,
+        self_ty: named_ty(primitive.to_string()),
+        params: vec![],
+        obligations: vec![],
+        builtin: true,
+        negative: false,
+        span: Span::NONE,
+    }
+}
+
+fn named_ty(name: String) -> Ty {
+    Ty {
+        kind: TyKind::Owned(Box::new(OwnedTy {
+            name,
+            generics: Box::new([]),
+        })),
+    }
+}
+
+/// matches the self type and trait of `header` against the goal `ty: tait`, binding the impl's generic params
+fn match_header(header: &ImplHeader, ty: &Ty, tait: &Ty, substs: &mut HashMap<String, Ty>) -> bool {
+    let self_matches = match_impl_ty(&header.self_ty, ty, &header.params, substs);
+    // a bound without args applies to all impls of the trait, e.g. `T: Add` for `impl Add<u8> for T`
+    match tait.kind.get_owned() {
+        Some(owned) if owned.generics.is_empty() => self_matches,
+        _ => self_matches && match_impl_ty(&header.tait, tait, &header.params, substs),
+    }
+}
+
+/// matches the self type of an impl against `ty`, binding the impl's generic params
+/// e.g. `Vec<T>` matches `Vec<u8>` with `T = u8` but `Vec<u16>` doesn't match `Vec<u8>`
+fn match_impl_ty(impl_ty: &Ty, ty: &Ty, params: &[String], bindings: &mut HashMap<String, Ty>) -> bool {
+    match (&impl_ty.kind, &ty.kind) {
+        (TyKind::Owned(impl_owned), _) if impl_owned.generics.is_empty() && params.contains(&impl_owned.name) => {
+            if let Some(bound) = bindings.get(&impl_owned.name) {
+                return bound == ty;
+            }
+            bindings.insert(impl_owned.name.clone(), ty.clone());
+            true
+        }
+        (TyKind::Owned(impl_owned), TyKind::Owned(owned)) => {
+            impl_owned.name == owned.name
+                && impl_owned.generics.len() == owned.generics.len()
+                && impl_owned.generics.iter().zip(owned.generics.iter()).all(|generics| match generics {
+                    (TyOrConstVal::Ty(impl_generic), TyOrConstVal::Ty(generic)) => match_impl_ty(impl_generic, generic, params, bindings),
+                    (TyOrConstVal::Lifetime(_), TyOrConstVal::Lifetime(_)) => true,
+                    // FIXME: bind const params as well
+                    (TyOrConstVal::Ty(impl_generic), TyOrConstVal::ConstVal(_)) => matches!(impl_generic.kind.get_owned(), Some(param) if params.contains(&param.name)),
+                    (impl_generic, generic) => impl_generic == generic,
+                })
+        }
+        (TyKind::Ref(impl_ref), TyKind::Ref(rf)) => impl_ref.mutability == rf.mutability && match_impl_ty(&impl_ref.ty, &rf.ty, params, bindings),
+        (TyKind::Array(impl_array), TyKind::Array(array)) => same_len(impl_array, array) && match_impl_ty(&impl_array.ty, &array.ty, params, bindings),
+        _ => impl_ty == ty,
+    }
+}
+
+/// whether both arrays have the same length, lengths which weren't evaluated are compared by the way they are written
+pub(crate) fn same_len(first: &ArrayTy, second: &ArrayTy) -> bool {
+    match (first.len(), second.len()) {
+        (Some(first), Some(second)) => first == second,
+        _ => first.amount == second.amount,
+    }
+}
+
+/// collects the bounds of generic params and where clauses as `(ty, bound)` pairs,
+/// e.g. `<T: Clone> ... where Vec<T>: Debug` results in `[(T, Clone), (Vec<T>, Debug)]`
+fn collect_obligations(generics: &[Generic], where_clause: &[WherePredicate]) -> Vec<(Ty, Ty)> {
+    let mut obligations = vec![];
+    for generic in generics {
+        if let Generic::Type(ty) = generic {
+            obligations.extend(ty.required_traits.iter().map(|bound| (named_ty(ty.name.clone()), bound.clone())));
+        }
+    }
+    for predicate in where_clause {
+        if let WherePredicate::Ty(predicate) = predicate {
+            obligations.extend(predicate.required_traits.iter().map(|bound| (predicate.ty.clone(), bound.clone())));
+        }
+    }
+    obligations
+}
+
+/// the bounds which have to hold, e.g. `[(T, Clone), (Vec<T>, Debug)]`
+type Obligations = Vec<(Ty, Ty)>;
+
+/// the obligations of `header` with its generic params replaced by `substs`
+fn instantiate_obligations(header: &ImplHeader, substs: &HashMap<String, Ty>) -> Result<Obligations, Obligations> {
+    let unbound = header.params.iter().filter(|param| !substs.contains_key(*param)).collect::<Vec<_>>();
+    // params which don't appear in the header can't be bound, so nothing is known about them and their bounds can't be proven
+    let (unbound, obligations): (Vec<_>, Vec<_>) = header.obligations.iter()
+        .map(|(ty, bound)| (ty.substitute(substs), bound.substitute(substs)))
+        .partition(|(ty, bound)| unbound.iter().any(|param| mentions(ty, param) || mentions(bound, param)));
+    if unbound.is_empty() {
+        Ok(obligations)
+    } else {
+        Err(unbound)
+    }
+}
+
+/// whether the generic param `param` is part of `ty`
+pub(crate) fn mentions(ty: &Ty, param: &String) -> bool {
+    match &ty.kind {
+        TyKind::Owned(owned) => &owned.name == param || owned.generics.iter().any(|generic| matches!(generic, TyOrConstVal::Ty(ty) if mentions(ty, param))),
+        TyKind::Ref(rf) => mentions(&rf.ty, param),
+        TyKind::Array(array) => mentions(&array.ty, param),
+        TyKind::Func(func) => func.args.iter().any(|arg| mentions(arg, param)) || func.ret.iter().any(|ret| mentions(ret, param)),
+        TyKind::Projection(projection) => mentions(&projection.ty, param),
+        TyKind::Dyn(dyn_ty) => mentions(&dyn_ty.tait, param),
+    }
+}
+
+/// collects the trait bounds of generic params from both their definition and the where clause
+/// e.g. `<T: Clone> ... where T: Copy, Vec<T>: Debug` results in `{T: [Clone, Copy], Vec<T>: [Debug]}`
+pub fn collect_bounds(generics: &[Generic], where_clause: &[WherePredicate]) -> HashMap<String, Vec<Ty>> {
+    let mut bounds: HashMap<String, Vec<Ty>> = HashMap::new();
+    for generic in generics {
+        if let Generic::Type(ty) = generic {
+            bounds.entry(ty.name.clone()).or_default().extend(ty.required_traits.iter().cloned());
+        }
+    }
+    for predicate in where_clause {
+        if let WherePredicate::Ty(predicate) = predicate {
+            bounds.entry(predicate.ty.to_string()).or_default().extend(predicate.required_traits.iter().cloned());
         }
-        true
     }
+    bounds
 }
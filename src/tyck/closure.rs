@@ -0,0 +1,141 @@
+use std::collections::HashSet;
+use crate::parser::ast::{ArrayInst, AstNode, ClosureNode, LocalAssign, StmtKind};
+use crate::parser::attrs::CaptureBy;
+use crate::tyck::{Environment, Ty};
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum CaptureKind {
+    Ref,
+    MutRef,
+    Value,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Capture {
+    pub name: String,
+    pub ty: Ty,
+    pub kind: CaptureKind,
+    /// whether the closure's body assigns to the captured value
+    pub mutated: bool,
+}
+
+/// collects all locals of the surrounding scopes which are used inside the closure's body
+/// and decides how each of them has to be captured:
+/// - `move` closures capture everything by value
+/// - locals which get assigned to inside the body are captured by mutable reference
+/// - everything else is captured by reference
+pub fn analyze_captures(env: &Environment, closure: &ClosureNode) -> Vec<Capture> {
+    let mut collector = CaptureCollector {
+        env,
+        bound: vec![closure.args.iter().map(|(name, _)| name.clone()).collect()],
+        captures: vec![],
+    };
+    collector.visit(&closure.body);
+
+    let mut captures = collector.captures;
+    if closure.capture == CaptureBy::Value {
+        for capture in captures.iter_mut() {
+            capture.kind = CaptureKind::Value;
+        }
+    }
+    captures
+}
+
+struct CaptureCollector<'a> {
+    env: &'a Environment,
+    // a stack of the names bound inside the closure itself (its args and its locals)
+    bound: Vec<HashSet<String>>,
+    captures: Vec<Capture>,
+}
+
+impl CaptureCollector<'_> {
+
+    fn is_bound(&self, name: &String) -> bool {
+        self.bound.iter().any(|scope| scope.contains(name))
+    }
+
+    fn record(&mut self, name: &String, mutated: bool) {
+        if self.is_bound(name) || !self.env.is_local_var(name) {
+            return;
+        }
+        if let Some(capture) = self.captures.iter_mut().find(|capture| &capture.name == name) {
+            if mutated {
+                capture.mutated = true;
+                capture.kind = CaptureKind::MutRef;
+            }
+            return;
+        }
+        self.captures.push(Capture {
+            name: name.clone(),
+            ty: self.env.resolve_var(name).unwrap(),
+            kind: if mutated { CaptureKind::MutRef } else { CaptureKind::Ref },
+            mutated,
+        });
+    }
+
+    fn visit(&mut self, node: &AstNode) {
+        match node {
+            AstNode::Number(_) | AstNode::StrLit(_) | AstNode::BoolLit(_) | AstNode::Error => {}
+            AstNode::Ident(name) => self.record(name, false),
+            AstNode::BinaryExpr(expr) => {
+                let assigns = expr.op.is_assign();
+                match &expr.lhs {
+                    AstNode::Ident(name) if assigns => self.record(name, true),
+                    lhs => self.visit(lhs),
+                }
+                self.visit(&expr.rhs);
+            }
+            AstNode::CallExpr(call) => {
+                // calling a captured closure requires it to be captured as well
+                self.record(&call.callee, false);
+                for arg in &*call.args {
+                    self.visit(arg);
+                }
+            }
+            AstNode::Block(block) => {
+                self.bound.push(HashSet::new());
+                for stmt in &*block.stmts {
+                    match stmt {
+                        // items can't capture anything
+                        StmtKind::Item(_) => {}
+                        StmtKind::LocalAssign(LocalAssign::Assign(assign)) => {
+                            self.visit(&assign.val);
+                            self.record(&assign.name, true);
+                        }
+                        StmtKind::LocalAssign(LocalAssign::DecAssign(assign)) => {
+                            self.visit(&assign.val.val);
+                            self.bound.last_mut().unwrap().insert(assign.val.name.clone());
+                        }
+                        StmtKind::Expr(expr) | StmtKind::Semi(expr) => self.visit(expr),
+                        StmtKind::Empty => {}
+                    }
+                }
+                self.bound.pop();
+            }
+            AstNode::StructConstructor(constructor) => {
+                for (_, val) in &*constructor.fields {
+                    self.visit(val);
+                }
+                if let Some(base) = &constructor.base {
+                    self.visit(base);
+                }
+            }
+            AstNode::ArrayInst(ArrayInst::List(list)) => {
+                for val in &*list.vals {
+                    self.visit(val);
+                }
+            }
+            AstNode::ArrayInst(ArrayInst::Short(short)) => {
+                self.visit(&short.val);
+                self.visit(&short.amount);
+            }
+            AstNode::Closure(closure) => {
+                // whatever a nested closure captures has to be captured by the outer one as well
+                self.bound.push(closure.args.iter().map(|(name, _)| name.clone()).collect());
+                self.visit(&closure.body);
+                self.bound.pop();
+            }
+        }
+    }
+
+}
@@ -0,0 +1,332 @@
+use std::collections::{HashMap, HashSet};
+use crate::const_eval::InferredConstness;
+use crate::parser::ast::{ArrayInst, AstNode, Block, FunctionNode, LocalAssign, StmtKind};
+use crate::parser::attrs::Constness;
+use crate::tyck::TyCtx;
+
+// `const fn`s may be called at compile time, so their bodies may only contain operations the const evaluator can execute:
+// `const fn area(side: u32) -> u32 { side * side }` is fine, but calling a function which isn't a `const fn` as well,
+// calling closures or function pointers (their bodies are unknown), creating closures and reading statics
+// (their values may change at runtime) is only possible at runtime.
+// functions without a `const` or `runtime` modifier may be either, they are const if their bodies are fine for a `const fn`.
+// as functions calling each other (e.g. `fn even(n: u32) -> bool` and `fn odd(n: u32) -> bool`) depend on each other's constness,
+// the constness is inferred for the strongly connected components of the call graph, starting with the callees:
+// a component is runtime-only as soon as one of its functions contains a runtime operation or calls a runtime-only function
+// see: https://en.wikipedia.org/wiki/Tarjan%27s_strongly_connected_components_algorithm
+
+/// an operation inside of a function's body which can only be executed at runtime
+#[derive(Debug, Clone, PartialEq)]
+pub enum RuntimeOp {
+    /// `draw(shape)` where `draw` isn't a `const fn`
+    Call(String),
+    /// `callback(1)` where `callback` is a local holding a closure or a function pointer
+    IndirectCall(String),
+    /// `|x| x + 1`
+    Closure(String),
+    /// `COUNTER + 1` where `COUNTER` is a static
+    Static(String),
+}
+
+impl RuntimeOp {
+
+    /// describes the operation as done by a function, e.g. "refers to the static `COUNTER`"
+    fn describe(&self) -> String {
+        match self {
+            RuntimeOp::Call(callee) => format!("calls `{}`", callee),
+            RuntimeOp::IndirectCall(callee) => format!("calls the local `{}`", callee),
+            RuntimeOp::Closure(closure) => format!("creates the closure `{}`", closure),
+            RuntimeOp::Static(name) => format!("refers to the static `{}`", name),
+        }
+    }
+
+}
+
+/// why a function without a `const` or `runtime` modifier can only be called at runtime
+#[derive(Debug, Clone, PartialEq)]
+enum RuntimeReason {
+    /// the function's body contains the operation itself
+    Op(RuntimeOp),
+    /// the function calls another one which can only be called at runtime
+    Calls(String),
+}
+
+/// collects all operations inside the body of `func` which can't be executed at compile time
+pub fn runtime_ops(ctx: &TyCtx, func: &FunctionNode) -> Vec<RuntimeOp> {
+    let mut collector = RuntimeOpCollector {
+        ctx,
+        bound: vec![func.header.args.iter().map(|(name, _)| name.clone()).collect()],
+        ops: vec![],
+    };
+    collector.visit_block(&func.body);
+    collector.ops
+}
+
+/// reports all operations inside the body of a `const fn` which can't be executed at compile time
+pub fn check_const_fn(ctx: &mut TyCtx, func: &FunctionNode) {
+    if func.modifiers.constness != Constness::Const {
+        return;
+    }
+    for op in runtime_ops(ctx, func) {
+        let (input, msg, notes) = match &op {
+            RuntimeOp::Call(callee) => {
+                let constness = ctx.env.resolve_func(callee).map(|callee| callee.modifiers.constness);
+                let notes = match (constness, ctx.consts.inferred_constness(callee)) {
+                    // the callee's body is fine for a `const fn`
+                    (Some(Constness::Undefined), Some(InferredConstness::Const)) => continue,
+                    (Some(Constness::Rt), _) => vec![format!("`{}` is declared as `runtime fn`", callee)],
+                    (_, Some(InferredConstness::Runtime(notes))) => notes.clone(),
+                    _ => vec![format!("consider declaring `{}` as `const fn`", callee)],
+                };
+                (callee.clone(), format!("cannot call non-const fn `{}` in constant functions", callee), notes)
+            }
+            RuntimeOp::IndirectCall(callee) => (
+                callee.clone(),
+                format!("cannot call the local `{}` in constant functions", callee),
+                vec!["only `const fn` items can be called at compile time".to_string()],
+            ),
+            RuntimeOp::Closure(closure) => (
+                closure.clone(),
+                "closures cannot be created in constant functions".to_string(),
+                vec!["closures can only be called at runtime".to_string()],
+            ),
+            RuntimeOp::Static(name) => (
+                name.clone(),
+                "constant functions cannot refer to statics".to_string(),
+                vec![format!("consider making `{}` a `const`", name)],
+            ),
+        };
+        let mut diagnostic = ctx.diagnostics.diagnostic(input);
+        diagnostic.error(msg);
+        for note in notes {
+            diagnostic.note(note);
+        }
+        diagnostic.note(format!("in the constant function `{}`", func.header.name));
+        diagnostic.build();
+    }
+}
+
+struct RuntimeOpCollector<'a> {
+    ctx: &'a TyCtx,
+    // a stack of the names bound inside the function (its args and its locals)
+    bound: Vec<HashSet<String>>,
+    ops: Vec<RuntimeOp>,
+}
+
+impl RuntimeOpCollector<'_> {
+
+    fn is_bound(&self, name: &String) -> bool {
+        self.bound.iter().any(|scope| scope.contains(name))
+    }
+
+    fn record(&mut self, op: RuntimeOp) {
+        if !self.ops.contains(&op) {
+            self.ops.push(op);
+        }
+    }
+
+    fn visit_block(&mut self, block: &Block) {
+        self.bound.push(HashSet::new());
+        for stmt in &*block.stmts {
+            match stmt {
+                // items are checked on their own
+                StmtKind::Item(_) => {}
+                StmtKind::LocalAssign(LocalAssign::Assign(assign)) => self.visit(&assign.val),
+                StmtKind::LocalAssign(LocalAssign::DecAssign(assign)) => {
+                    self.visit(&assign.val.val);
+                    self.bound.last_mut().unwrap().insert(assign.val.name.clone());
+                }
+                StmtKind::Expr(expr) | StmtKind::Semi(expr) => self.visit(expr),
+                StmtKind::Empty => {}
+            }
+        }
+        self.bound.pop();
+    }
+
+    fn visit(&mut self, node: &AstNode) {
+        match node {
+            AstNode::Number(_) | AstNode::StrLit(_) | AstNode::BoolLit(_) | AstNode::Error => {}
+            AstNode::Ident(name) => {
+                // consts are known at compile time, everything else which is global is a static
+                if !self.is_bound(name) && self.ctx.env.resolve_var(name).is_some() && !self.ctx.consts.is_const(name) {
+                    self.record(RuntimeOp::Static(name.clone()));
+                }
+            }
+            AstNode::BinaryExpr(expr) => {
+                self.visit(&expr.lhs);
+                self.visit(&expr.rhs);
+            }
+            AstNode::CallExpr(call) => {
+                if self.is_bound(&call.callee) {
+                    self.record(RuntimeOp::IndirectCall(call.callee.clone()));
+                } else if let Some(callee) = self.ctx.env.resolve_func(&call.callee) {
+                    if callee.modifiers.constness != Constness::Const {
+                        self.record(RuntimeOp::Call(call.callee.clone()));
+                    }
+                }
+                for arg in &*call.args {
+                    self.visit(arg);
+                }
+            }
+            AstNode::Block(block) => self.visit_block(block),
+            AstNode::StructConstructor(constructor) => {
+                for (_, val) in &*constructor.fields {
+                    self.visit(val);
+                }
+                if let Some(base) = &constructor.base {
+                    self.visit(base);
+                }
+            }
+            AstNode::ArrayInst(ArrayInst::List(list)) => {
+                for val in &*list.vals {
+                    self.visit(val);
+                }
+            }
+            AstNode::ArrayInst(ArrayInst::Short(short)) => {
+                self.visit(&short.val);
+                self.visit(&short.amount);
+            }
+            AstNode::Closure(_) => self.record(RuntimeOp::Closure(node.to_string())),
+        }
+    }
+
+}
+
+/// infers the constness of all functions without a `const` or `runtime` modifier,
+/// the evaluator and the checks of `const fn`s use the results, so this has to be done before checking any items
+pub fn infer_constness(ctx: &mut TyCtx) {
+    let mut names = ctx.env.func_names();
+    names.sort();
+    let funcs = names.iter()
+        .filter_map(|name| ctx.env.resolve_func(name))
+        .filter(|func| func.modifiers.constness == Constness::Undefined)
+        .map(|func| (func.header.name.clone(), runtime_ops(ctx, func)))
+        .collect::<Vec<_>>();
+    let ops = funcs.iter().cloned().collect::<HashMap<_, _>>();
+
+    let mut reasons: HashMap<String, RuntimeReason> = HashMap::new();
+    // the components are found callees first, so the constness of all functions outside of a component is known already
+    for component in strongly_connected_components(&funcs) {
+        // within a component, a function is runtime-only if it calls one of the others which was found to be runtime-only
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for name in &component {
+                if reasons.contains_key(name) {
+                    continue;
+                }
+                let reason = ops[name].iter().find_map(|op| match op {
+                    RuntimeOp::Call(callee) => match ctx.env.resolve_func(callee).map(|callee| callee.modifiers.constness) {
+                        Some(Constness::Rt) => Some(RuntimeReason::Calls(callee.clone())),
+                        _ if reasons.contains_key(callee) => Some(RuntimeReason::Calls(callee.clone())),
+                        _ => None,
+                    },
+                    op => Some(RuntimeReason::Op(op.clone())),
+                });
+                if let Some(reason) = reason {
+                    reasons.insert(name.clone(), reason);
+                    changed = true;
+                }
+            }
+        }
+    }
+
+    for (name, _) in &funcs {
+        let constness = if reasons.contains_key(name) {
+            InferredConstness::Runtime(explain_runtime_fn(&reasons, name))
+        } else {
+            InferredConstness::Const
+        };
+        ctx.consts.insert_inferred_constness(name.clone(), constness);
+    }
+}
+
+/// the chain of calls leading from `name` to the operation which makes it runtime-only
+fn explain_runtime_fn(reasons: &HashMap<String, RuntimeReason>, name: &String) -> Vec<String> {
+    let mut notes = vec![format!("`{}` was inferred to only be callable at runtime", name)];
+    let mut curr = name;
+    let mut first = true;
+    loop {
+        let prefix = if first { "...because it" } else { "...which" };
+        first = false;
+        match &reasons[curr] {
+            RuntimeReason::Op(op) => {
+                notes.push(format!("{} {}", prefix, op.describe()));
+                break;
+            }
+            RuntimeReason::Calls(callee) => {
+                notes.push(format!("{} calls `{}`...", prefix, callee));
+                if !reasons.contains_key(callee) {
+                    // the callee was declared as `runtime fn`
+                    notes.push("...which is declared as `runtime fn`".to_string());
+                    break;
+                }
+                curr = callee;
+            }
+        }
+    }
+    notes
+}
+
+/// finds the strongly connected components of the call graph of `funcs` using tarjan's algorithm,
+/// the components are returned in reverse topological order, i.e. the callees come before their callers
+fn strongly_connected_components(funcs: &[(String, Vec<RuntimeOp>)]) -> Vec<Vec<String>> {
+    struct Tarjan<'a> {
+        edges: HashMap<&'a String, Vec<&'a String>>,
+        indices: HashMap<&'a String, usize>,
+        low_links: HashMap<&'a String, usize>,
+        stack: Vec<&'a String>,
+        components: Vec<Vec<String>>,
+    }
+
+    impl<'a> Tarjan<'a> {
+
+        fn visit(&mut self, name: &'a String) {
+            let idx = self.indices.len();
+            self.indices.insert(name, idx);
+            self.low_links.insert(name, idx);
+            self.stack.push(name);
+            for callee in self.edges[name].clone() {
+                if !self.indices.contains_key(callee) {
+                    self.visit(callee);
+                    let low_link = self.low_links[name].min(self.low_links[callee]);
+                    self.low_links.insert(name, low_link);
+                } else if self.stack.contains(&callee) {
+                    let low_link = self.low_links[name].min(self.indices[callee]);
+                    self.low_links.insert(name, low_link);
+                }
+            }
+            if self.low_links[name] == self.indices[name] {
+                let mut component = vec![];
+                loop {
+                    let member = self.stack.pop().unwrap();
+                    component.push(member.clone());
+                    if member == name {
+                        break;
+                    }
+                }
+                component.reverse();
+                self.components.push(component);
+            }
+        }
+
+    }
+
+    let names = funcs.iter().map(|(name, _)| name).collect::<HashSet<_>>();
+    let mut tarjan = Tarjan {
+        // only the calls of other functions whose constness has to be inferred matter
+        edges: funcs.iter().map(|(name, ops)| (name, ops.iter().filter_map(|op| match op {
+            RuntimeOp::Call(callee) if names.contains(callee) => Some(names.get(callee).copied().unwrap()),
+            _ => None,
+        }).collect())).collect(),
+        indices: HashMap::new(),
+        low_links: HashMap::new(),
+        stack: vec![],
+        components: vec![],
+    };
+    for (name, _) in funcs {
+        if !tarjan.indices.contains_key(name) {
+            tarjan.visit(name);
+        }
+    }
+    tarjan.components
+}
@@ -0,0 +1,220 @@
+use std::collections::HashMap;
+use crate::parser::ast::{AdtImpl, FunctionHeader, Generic, ItemKind, OwnedTy, Ty, TyKind, TyOrConstVal};
+use crate::parser::attrs::Mutability;
+use crate::tyck::{Adt, TyCtx, DEFAULT_PATH};
+
+/// checks that an impl of a trait provides exactly the items the trait requires
+/// (with signatures matching the trait's ones) and that all of the trait's supertraits are implemented as well
+pub fn check_trait_impl(ctx: &mut TyCtx, adt_impl: &AdtImpl) {
+    let impl_trait = adt_impl.impl_trait.as_ref().unwrap();
+    let trait_name = impl_trait.kind.simple_ty_name();
+    let impl_name = format!("impl {} for {}", impl_trait.to_string(), adt_impl.ty.to_string());
+    let tait = if let Some(tait) = ctx.env.resolve_trait(&DEFAULT_PATH.to_string(), &trait_name) {
+        tait.clone()
+    } else if ctx.traits.is_builtin_trait(&trait_name) {
+        // the traits known to the compiler don't have to be defined, there are no items to check against then
+        return;
+    } else {
+        report(ctx, &impl_name, format!("cannot find trait `{}` in this scope", trait_name));
+        return;
+    };
+
+    // `Self` and the trait's generics have to be replaced in the trait's signatures
+    // in order to be able to compare them to the ones in the impl
+    let mut substitutions = HashMap::new();
+    substitutions.insert("Self".to_string(), adt_impl.ty.clone());
+    for (param, arg) in generic_names(&tait.generics).into_iter().zip(impl_trait.kind.get_generics().iter()) {
+        if let TyOrConstVal::Ty(arg) = arg {
+            substitutions.insert(param, arg.clone());
+        }
+    }
+
+    for item in &*adt_impl.methods {
+        match item {
+            ItemKind::FunctionDef(func) => {
+                let method = if let Some(method) = tait.methods.iter().find(|method| method.header.name == func.header.name) {
+                    method
+                } else {
+                    report(ctx, &impl_name, format!("method `{}` is not a member of trait `{}`", func.header.name, trait_name));
+                    continue;
+                };
+                if let Err(mismatch) = check_signature(ctx, &method.header, &func.header, &substitutions) {
+                    report(ctx, &impl_name, format!("method `{}` doesn't match the trait's signature: {}", func.header.name, mismatch));
+                }
+            }
+            ItemKind::TyAlias(alias) => {
+                if !tait.assoc_tys.iter().any(|assoc_ty| assoc_ty.name == alias.name) {
+                    report(ctx, &impl_name, format!("type `{}` is not a member of trait `{}`", alias.name, trait_name));
+                }
+            }
+            ItemKind::ConstVal(val) => {
+                let name = val.left();
+                let assoc_const = if let Some(assoc_const) = tait.assoc_consts.iter().find(|assoc_const| &assoc_const.name == name) {
+                    assoc_const
+                } else {
+                    report(ctx, &impl_name, format!("const `{}` is not a member of trait `{}`", name, trait_name));
+                    continue;
+                };
+                let expected = ctx.normalize_ast_ty(&assoc_const.ty.substitute(&substitutions));
+                if expected != ctx.normalize_ast_ty(&val.ty) {
+                    report(ctx, &impl_name, format!("const `{}` has type `{}` but the trait expects `{}`", name, val.ty.to_string(), expected.to_string()));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    for method in &*tait.methods {
+        let implemented = adt_impl.methods.iter().any(|item| matches!(item, ItemKind::FunctionDef(func) if func.header.name == method.header.name));
+        if !implemented && method.default.is_none() {
+            report(ctx, &impl_name, format!("missing implementation of method `{}`", method.header.name));
+        }
+    }
+    for assoc_ty in &*tait.assoc_tys {
+        if !adt_impl.methods.iter().any(|item| matches!(item, ItemKind::TyAlias(alias) if alias.name == assoc_ty.name)) {
+            report(ctx, &impl_name, format!("missing type `{}`", assoc_ty.name));
+        }
+    }
+    for assoc_const in tait.assoc_consts.iter().filter(|assoc_const| assoc_const.default.is_none()) {
+        if !adt_impl.methods.iter().any(|item| matches!(item, ItemKind::ConstVal(val) if val.left() == &assoc_const.name)) {
+            report(ctx, &impl_name, format!("missing const `{}`", assoc_const.name));
+        }
+    }
+
+    // the supertraits may be implemented thanks to the bounds of the impl's generics
+    let outer_generics = ctx.push_generics(&adt_impl.generics, &adt_impl.where_clause);
+    for sub_trait in &*tait.req_sub_traits {
+        // FIXME: check built-in traits once they are known to the compiler
+        if !ctx.traits.is_known_trait(&sub_trait.kind.simple_ty_name()) {
+            continue;
+        }
+        let sub_trait = sub_trait.substitute(&substitutions);
+        if !ctx.implements(&adt_impl.ty, &sub_trait) {
+            report(ctx, &impl_name, format!("the trait `{}` requires `{}` to be implemented for `{}` as well", trait_name, sub_trait.to_string(), adt_impl.ty.to_string()));
+        }
+    }
+    ctx.restore_generics(outer_generics);
+}
+
+fn report(ctx: &mut TyCtx, item: &str, error: String) {
+    let mut diagnostic = ctx.diagnostics.diagnostic(item.to_string());
+    diagnostic.error(error);
+    diagnostic.build();
+}
+
+/// checks that a negative impl opts out of an auto trait and doesn't provide any items
+pub fn check_negative_impl(ctx: &mut TyCtx, adt_impl: &AdtImpl) {
+    let trait_name = adt_impl.impl_trait.as_ref().unwrap().kind.simple_ty_name();
+    let impl_name = format!("impl !{} for {}", trait_name, adt_impl.ty.to_string());
+    if !ctx.traits.is_auto_trait(&trait_name) {
+        report(ctx, &impl_name, format!("negative impls are only allowed for auto traits like `Send` and `Sync`, but `{}` isn't one", trait_name));
+    }
+    if !adt_impl.methods.is_empty() {
+        report(ctx, &impl_name, "negative impls can't have any items".to_string());
+    }
+}
+
+/// checks that every field of a struct implements the trait which gets derived for it
+pub fn check_derived_impl(ctx: &mut TyCtx, adt_impl: &AdtImpl) {
+    let tait = adt_impl.impl_trait.as_ref().unwrap();
+    let trait_name = tait.kind.simple_ty_name();
+    let def = match ctx.env.resolve_adt(&DEFAULT_PATH.to_string(), &adt_impl.ty.kind.simple_ty_name()) {
+        Some((Adt::Struct(def), _)) => def.clone(),
+        None => {
+            report(ctx, &format!("#[derive({})]", trait_name), format!("cannot find the type `{}` which derives `{}`", adt_impl.ty.to_string(), trait_name));
+            return;
+        }
+    };
+    let params = generic_names(&def.generics);
+    for field in &*def.fields {
+        if implements_derivable(ctx, &params, &field.ty, tait) {
+            continue;
+        }
+        let mut diagnostic = ctx.diagnostics.diagnostic(format!("{}: {}", field.name, field.ty.to_string()));
+        diagnostic.error(format!("the trait `{}` is not implemented for `{}`", trait_name, field.ty.to_string()));
+        diagnostic.note(format!("required for the field `{}` of `{}` because of `#[derive({})]`", field.name, def.name, trait_name));
+        diagnostic.build();
+    }
+    if trait_name == "Copy" {
+        let clone = Ty {
+            kind: TyKind::Owned(Box::new(OwnedTy {
+                name: "Clone".to_string(),
+                generics: Box::new([]),
+            })),
+        };
+        if !ctx.traits.has_impl(&adt_impl.ty, &clone) {
+            let mut diagnostic = ctx.diagnostics.diagnostic(format!("#[derive(Copy)] struct {}", def.name));
+            diagnostic.error(format!("the trait `Clone` is not implemented for `{}`", adt_impl.ty.to_string()));
+            diagnostic.note("`Copy` requires `Clone` to be implemented as well".to_string());
+            diagnostic.build();
+        }
+    }
+}
+
+/// checks whether `ty` implements one of the derivable built-in traits
+fn implements_derivable(ctx: &TyCtx, params: &[String], ty: &Ty, tait: &Ty) -> bool {
+    match &ty.kind {
+        // the derived impl requires generic params to implement the trait
+        TyKind::Owned(owned) if owned.generics.is_empty() && params.contains(&owned.name) => true,
+        TyKind::Owned(_) => ctx.traits.has_impl(ty, tait),
+        TyKind::Ref(rf) => match tait.kind.simple_ty_name().as_str() {
+            "Clone" | "Copy" => rf.mutability == Mutability::Immut,
+            "Default" => false,
+            _ => implements_derivable(ctx, params, &rf.ty, tait),
+        },
+        TyKind::Array(array) => implements_derivable(ctx, params, &array.ty, tait),
+        TyKind::Func(_) => tait.kind.simple_ty_name() != "Default",
+        // FIXME: check projections once they can be normalized here
+        TyKind::Projection(_) => true,
+        TyKind::Dyn(_) => ctx.traits.has_impl(ty, tait),
+    }
+}
+
+/// compares the signature of a method in an impl with the one of the trait it's declared in
+fn check_signature(ctx: &TyCtx, expected: &FunctionHeader, actual: &FunctionHeader, substitutions: &HashMap<String, Ty>) -> Result<(), String> {
+    let expected_generics = generic_names(&expected.generics);
+    let actual_generics = generic_names(&actual.generics);
+    if expected_generics.len() != actual_generics.len() {
+        return Err(format!("expected {} generic params but found {}", expected_generics.len(), actual_generics.len()));
+    }
+    // the method's own generics may be named differently in the impl, so we use the trait's names for both
+    let mut actual_substitutions = HashMap::new();
+    // `Self` refers to the impl's type inside of the impl as well, e.g. in the receiver `&self`
+    actual_substitutions.insert("Self".to_string(), substitutions["Self"].clone());
+    for (actual, expected) in actual_generics.into_iter().zip(expected_generics) {
+        actual_substitutions.insert(actual, Ty {
+            kind: TyKind::Owned(Box::new(OwnedTy {
+                name: expected,
+                generics: Box::new([]),
+            })),
+        });
+    }
+
+    if expected.args.len() != actual.args.len() {
+        return Err(format!("expected {} args but found {}", expected.args.len(), actual.args.len()));
+    }
+    for ((name, expected_ty), (_, actual_ty)) in expected.args.iter().zip(actual.args.iter()) {
+        let expected_ty = ctx.normalize_ast_ty(&expected_ty.substitute(substitutions));
+        let actual_ty = ctx.normalize_ast_ty(&actual_ty.substitute(&actual_substitutions));
+        if expected_ty != actual_ty {
+            return Err(format!("expected the arg `{}` to be of type `{}` but found `{}`", name, expected_ty.to_string(), actual_ty.to_string()));
+        }
+    }
+
+    let expected_ret = expected.ret.as_ref().map(|ret| ctx.normalize_ast_ty(&ret.substitute(substitutions)));
+    let actual_ret = actual.ret.as_ref().map(|ret| ctx.normalize_ast_ty(&ret.substitute(&actual_substitutions)));
+    if expected_ret != actual_ret {
+        let to_string = |ret: Option<Ty>| ret.map_or("()".to_string(), |ret| ret.to_string());
+        return Err(format!("expected the return type `{}` but found `{}`", to_string(expected_ret), to_string(actual_ret)));
+    }
+    Ok(())
+}
+
+/// the names of the type and const params among `generics`
+fn generic_names(generics: &[Generic]) -> Vec<String> {
+    generics.iter().filter_map(|generic| match generic {
+        Generic::Type(ty) => Some(ty.name.clone()),
+        Generic::Constant(constant) => Some(constant.name.clone()),
+        Generic::Lifetime(_) => None,
+    }).collect()
+}
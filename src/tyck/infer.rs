@@ -0,0 +1,344 @@
+use std::collections::HashMap;
+use crate::tyck::{ArrayTy, FnPtrTy, PrimitiveTy, RefTy, StructField, StructTy, Ty};
+
+// type inference in the style of Hindley-Milner:
+// every type that isn't known upfront (unannotated locals, literals, ...) gets a fresh type variable
+// and every place that requires two types to be equal (calls, assignments, returns, operators, ...)
+// adds a constraint which gets solved right away by unifying both types.
+// type variables are stored in a union-find structure, so unifying two variables is cheap
+// and integer/float literals are only defaulted (to `i32`/`f64`) once all constraints of a body are solved
+// see: https://en.wikipedia.org/wiki/Hindley%E2%80%93Milner_type_system
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct TyVid(pub usize);
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum InferKind {
+    Ty,    // any type
+    Int,   // an integer literal
+    Float, // a float literal
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct InferTy {
+    pub id: TyVid,
+    pub kind: InferKind,
+}
+
+/// describes why a type variable was created or why a constraint exists,
+/// e.g. "integer literal `5`" or "return type of `foo`"
+#[derive(Debug, Clone, PartialEq)]
+pub struct Origin(pub String);
+
+#[derive(Debug)]
+struct VarData {
+    parent: TyVid,
+    rank: usize,
+    kind: InferKind,
+    value: Option<Ty>,
+    // where the variable was created
+    origin: Origin,
+    // the constraint which determined the variable's value (if any)
+    bound_by: Option<Origin>,
+}
+
+/// two types which were required to be equal but aren't
+#[derive(Debug)]
+pub struct TypeError {
+    pub expected: Ty,
+    pub found: Ty,
+    // what required the types to be equal
+    pub origin: Origin,
+    // where the types themselves come from
+    pub expected_origin: Option<Origin>,
+    pub found_origin: Option<Origin>,
+}
+
+#[derive(Default, Debug)]
+pub struct InferCtx {
+    vars: Vec<VarData>,
+}
+
+impl InferCtx {
+
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// the number of variables created so far, this can be passed to `default_vars` later on
+    pub fn num_vars(&self) -> usize {
+        self.vars.len()
+    }
+
+    pub fn fresh(&mut self, kind: InferKind, origin: Origin) -> Ty {
+        let id = TyVid(self.vars.len());
+        self.vars.push(VarData {
+            parent: id,
+            rank: 0,
+            kind,
+            value: None,
+            origin,
+            bound_by: None,
+        });
+        Ty::Infer(InferTy {
+            id,
+            kind,
+        })
+    }
+
+    fn find(&mut self, var: TyVid) -> TyVid {
+        let parent = self.vars[var.0].parent;
+        if parent == var {
+            return var;
+        }
+        let root = self.find(parent);
+        // path compression
+        self.vars[var.0].parent = root;
+        root
+    }
+
+    /// replaces the outermost type variable by its value (if it has one),
+    /// additionally returns the origin of the type
+    pub fn shallow_resolve(&mut self, ty: &Ty) -> (Ty, Option<Origin>) {
+        if let Ty::Infer(var) = ty {
+            let root = self.find(var.id);
+            let data = &self.vars[root.0];
+            return match &data.value {
+                Some(value) => {
+                    let (value, origin) = (value.clone(), data.bound_by.clone().unwrap_or_else(|| data.origin.clone()));
+                    let (value, inner_origin) = self.shallow_resolve(&value);
+                    (value, inner_origin.or(Some(origin)))
+                }
+                None => (Ty::Infer(InferTy { id: root, kind: data.kind }), Some(data.origin.clone())),
+            };
+        }
+        (ty.clone(), None)
+    }
+
+    /// replaces all type variables inside `ty` by their values, unbound variables are kept as is
+    pub fn resolve_fully(&mut self, ty: &Ty) -> Ty {
+        match self.shallow_resolve(ty).0 {
+            Ty::Ref(rf) => Ty::Ref(RefTy {
+                lt: rf.lt,
+                mutability: rf.mutability,
+                ty: Box::new(self.resolve_fully(&rf.ty)),
+            }),
+            Ty::Array(array) => Ty::Array(ArrayTy {
+                elem_ty: Box::new(self.resolve_fully(&array.elem_ty)),
+                len: array.len,
+            }),
+            Ty::FnPtr(func) => Ty::FnPtr(FnPtrTy {
+                args: func.args.iter().map(|arg| self.resolve_fully(arg)).collect(),
+                ret: Box::new(self.resolve_fully(&func.ret)),
+            }),
+            Ty::Closure(mut closure) => {
+                closure.args = closure.args.iter().map(|arg| self.resolve_fully(arg)).collect();
+                closure.ret = Box::new(self.resolve_fully(&closure.ret));
+                Ty::Closure(closure)
+            }
+            Ty::Struct(mut strukt) => {
+                strukt.generics = strukt.generics.iter().map(|generic| self.resolve_fully(generic)).collect();
+                for field in strukt.fields.iter_mut() {
+                    field.ty = self.resolve_fully(&field.ty);
+                }
+                Ty::Struct(strukt)
+            }
+            ty => ty,
+        }
+    }
+
+    /// requires `expected` and `found` to be the same type
+    pub fn unify(&mut self, expected: &Ty, found: &Ty, origin: &Origin) -> Result<(), Box<TypeError>> {
+        if self.unify_inner(expected, found, origin) {
+            return Ok(());
+        }
+        let (expected, expected_origin) = self.shallow_resolve(expected);
+        let (found, found_origin) = self.shallow_resolve(found);
+        Err(Box::new(TypeError {
+            expected: self.resolve_fully(&expected),
+            found: self.resolve_fully(&found),
+            origin: origin.clone(),
+            expected_origin,
+            found_origin,
+        }))
+    }
+
+    fn unify_inner(&mut self, expected: &Ty, found: &Ty, origin: &Origin) -> bool {
+        let (expected, _) = self.shallow_resolve(expected);
+        let (found, _) = self.shallow_resolve(found);
+        match (&expected, &found) {
+            // errors were already reported
+            (Ty::Error, _) | (_, Ty::Error) => true,
+            (Ty::Infer(left), Ty::Infer(right)) => self.union(left.id, right.id),
+            (Ty::Infer(var), ty) | (ty, Ty::Infer(var)) => self.bind(var.id, ty, origin),
+            // FIXME: get rid of this once we have proper inference
+            (Ty::Unresolved(unresolved), _) | (_, Ty::Unresolved(unresolved)) if unresolved.name == "_" => true,
+            (Ty::Unresolved(left), Ty::Unresolved(right)) => left.name == right.name && left.generics == right.generics,
+            // the fields of adts are lowered without access to the other adts
+            (Ty::Unresolved(unresolved), ty) | (ty, Ty::Unresolved(unresolved)) => match ty {
+                Ty::Struct(strukt) => unresolved.name == strukt.name,
+                Ty::Enum(enum_ty) => unresolved.name == enum_ty.name,
+                Ty::Union(union_ty) => unresolved.name == union_ty.name,
+                _ => false,
+            },
+            (Ty::Primitive(left), Ty::Primitive(right)) => left == right,
+            (Ty::Ref(left), Ty::Ref(right)) => left.mutability == right.mutability && self.unify_inner(&left.ty, &right.ty, origin),
+            // lengths which aren't known match any length, their evaluation errors were already reported
+            (Ty::Array(left), Ty::Array(right)) => {
+                let lens_match = left.len.zip(right.len).is_none_or(|(left, right)| left == right);
+                self.unify_inner(&left.elem_ty, &right.elem_ty, origin) && lens_match
+            }
+            (Ty::FnPtr(_) | Ty::Closure(_), Ty::FnPtr(_) | Ty::Closure(_)) => {
+                let (args, ret) = expected.fn_sig().unwrap();
+                let (other_args, other_ret) = found.fn_sig().unwrap();
+                if args.len() != other_args.len() {
+                    return false;
+                }
+                // all of them get unified, so we learn as much as possible even if some don't match
+                let mut ret_matches = self.unify_inner(ret, other_ret, origin);
+                for (arg, other) in args.iter().zip(other_args.iter()) {
+                    ret_matches &= self.unify_inner(arg, other, origin);
+                }
+                ret_matches
+            }
+            (Ty::Struct(left), Ty::Struct(right)) => {
+                if left.name != right.name || left.generics.len() != right.generics.len() {
+                    return false;
+                }
+                // all of them get unified, so we learn as much as possible even if some don't match
+                let mut matches = true;
+                for (arg, other) in left.generics.iter().zip(right.generics.iter()) {
+                    matches &= self.unify_inner(arg, other, origin);
+                }
+                matches
+            }
+            (Ty::Enum(left), Ty::Enum(right)) => left.name == right.name,
+            (Ty::Union(left), Ty::Union(right)) => left.name == right.name,
+            (left, right) => left == right,
+        }
+    }
+
+    fn union(&mut self, left: TyVid, right: TyVid) -> bool {
+        let (left, right) = (self.find(left), self.find(right));
+        if left == right {
+            return true;
+        }
+        let kind = match (self.vars[left.0].kind, self.vars[right.0].kind) {
+            (InferKind::Ty, kind) | (kind, InferKind::Ty) => kind,
+            (InferKind::Int, InferKind::Int) => InferKind::Int,
+            (InferKind::Float, InferKind::Float) => InferKind::Float,
+            _ => return false,
+        };
+        // union by rank, the root keeps its origin
+        let (root, child) = if self.vars[left.0].rank >= self.vars[right.0].rank { (left, right) } else { (right, left) };
+        if self.vars[root.0].rank == self.vars[child.0].rank {
+            self.vars[root.0].rank += 1;
+        }
+        self.vars[child.0].parent = root;
+        if self.vars[root.0].kind != kind {
+            // literals are the more useful origin to point at
+            self.vars[root.0].origin = self.vars[child.0].origin.clone();
+        }
+        self.vars[root.0].kind = kind;
+        true
+    }
+
+    fn bind(&mut self, var: TyVid, ty: &Ty, origin: &Origin) -> bool {
+        let root = self.find(var);
+        if self.occurs(root, ty) {
+            // FIXME: report infinite types separately
+            return false;
+        }
+        let compatible = match self.vars[root.0].kind {
+            InferKind::Ty => true,
+            InferKind::Int => is_int_ty(ty),
+            InferKind::Float => is_float_ty(ty),
+        };
+        if !compatible {
+            return false;
+        }
+        let data = &mut self.vars[root.0];
+        data.value = Some(ty.clone());
+        data.bound_by = Some(origin.clone());
+        true
+    }
+
+    /// checks whether `var` is part of `ty`, binding it to `ty` would result in an infinite type
+    fn occurs(&mut self, var: TyVid, ty: &Ty) -> bool {
+        match self.shallow_resolve(ty).0 {
+            Ty::Infer(other) => self.find(other.id) == var,
+            Ty::Ref(rf) => self.occurs(var, &rf.ty),
+            Ty::Array(array) => self.occurs(var, &array.elem_ty),
+            ty @ (Ty::FnPtr(_) | Ty::Closure(_)) => {
+                let (args, ret) = ty.fn_sig().unwrap();
+                args.iter().any(|arg| self.occurs(var, arg)) || self.occurs(var, ret)
+            }
+            Ty::Struct(strukt) => strukt.generics.iter().any(|generic| self.occurs(var, generic)),
+            _ => false,
+        }
+    }
+
+    /// defaults all unbound integer and float variables created since `start` to `i32` and `f64`,
+    /// this has to happen after all constraints got solved as the literals could be constrained later on
+    pub fn default_vars(&mut self, start: usize) {
+        for idx in start..self.vars.len() {
+            let root = self.find(TyVid(idx));
+            let data = &mut self.vars[root.0];
+            if data.value.is_some() {
+                continue;
+            }
+            data.value = match data.kind {
+                InferKind::Ty => continue,
+                InferKind::Int => Some(Ty::Primitive(PrimitiveTy::from_name("i32").unwrap())),
+                InferKind::Float => Some(Ty::Primitive(PrimitiveTy::from_name("f64").unwrap())),
+            };
+        }
+    }
+
+}
+
+fn is_int_ty(ty: &Ty) -> bool {
+    match ty {
+        Ty::Primitive(primitive) => matches!(primitive, PrimitiveTy::MachineSizedInt(_) | PrimitiveTy::SizedInt(_) | PrimitiveTy::UnsizedInt),
+        _ => false,
+    }
+}
+
+fn is_float_ty(ty: &Ty) -> bool {
+    match ty {
+        Ty::Primitive(primitive) => matches!(primitive, PrimitiveTy::SizedFloat(_)),
+        _ => false,
+    }
+}
+
+/// replaces the generic params of a function by the given types (usually fresh variables),
+/// e.g. `fn(T) -> Vec<T>` with `T = ?0` becomes `fn(?0) -> Vec<?0>`
+pub fn instantiate(ty: &Ty, params: &HashMap<String, Ty>) -> Ty {
+    match ty {
+        Ty::Unresolved(unresolved) if unresolved.generics.is_empty() && params.contains_key(&unresolved.name) => params[&unresolved.name].clone(),
+        Ty::Ref(rf) => Ty::Ref(RefTy {
+            lt: rf.lt.clone(),
+            mutability: rf.mutability,
+            ty: Box::new(instantiate(&rf.ty, params)),
+        }),
+        Ty::Array(array) => Ty::Array(ArrayTy {
+            elem_ty: Box::new(instantiate(&array.elem_ty, params)),
+            len: array.len,
+        }),
+        Ty::FnPtr(func) => Ty::FnPtr(FnPtrTy {
+            args: func.args.iter().map(|arg| instantiate(arg, params)).collect(),
+            ret: Box::new(instantiate(&func.ret, params)),
+        }),
+        Ty::Struct(strukt) => Ty::Struct(StructTy {
+            vis: strukt.vis,
+            name: strukt.name.clone(),
+            generics: strukt.generics.iter().map(|generic| instantiate(generic, params)).collect(),
+            fields: strukt.fields.iter().map(|field| StructField {
+                vis: field.vis,
+                name: field.name.clone(),
+                ty: instantiate(&field.ty, params),
+            }).collect(),
+        }),
+        ty => ty.clone(),
+    }
+}
@@ -0,0 +1,251 @@
+use std::collections::{HashMap, HashSet};
+use crate::diagnostics::builder::{DiagnosticBuilder, DiagnosticSubBuilder};
+use crate::parser::ast::{FunctionHeader, Generic, ItemKind, Lifetime, Ty, TyKind, TyOrConstVal, WherePredicate};
+
+/// checks the lifetimes used in the signatures of an item (and of the methods it contains):
+/// - every lifetime has to be declared by the item (or the impl/trait it's part of)
+/// - `&'a &'b T` is only valid if `'b: 'a` is known to hold
+/// - elided lifetimes in return types require exactly one lifetime in the args
+pub fn check_item_lifetimes(item: &ItemKind, diagnostics: &mut DiagnosticBuilder) {
+    let root = LifetimeScope::default();
+    match item {
+        ItemKind::FunctionDef(func) => check_fn(&root, &func.header, diagnostics),
+        ItemKind::StructDef(def) => {
+            let scope = root.nested(&def.name, &def.generics, &def.where_clause, diagnostics);
+            for field in &*def.fields {
+                scope.check_ty(&def.name, &field.ty, diagnostics);
+            }
+        }
+        ItemKind::TraitDef(def) => {
+            let scope = root.nested(&def.name, &def.generics, &def.where_clause, diagnostics);
+            for method in &*def.methods {
+                check_fn(&scope, &method.header, diagnostics);
+            }
+        }
+        ItemKind::StructImpl(adt_impl) => {
+            let name = adt_impl.ty.to_string();
+            let scope = root.nested(&name, &adt_impl.generics, &adt_impl.where_clause, diagnostics);
+            scope.check_ty(&name, &adt_impl.ty, diagnostics);
+            if let Some(impl_trait) = &adt_impl.impl_trait {
+                scope.check_ty(&name, impl_trait, diagnostics);
+            }
+            for item in &*adt_impl.methods {
+                match item {
+                    ItemKind::FunctionDef(func) => check_fn(&scope, &func.header, diagnostics),
+                    ItemKind::TyAlias(alias) => scope.nested(&alias.name, &alias.generics, &[], diagnostics).check_ty(&alias.name, &alias.ty, diagnostics),
+                    _ => {}
+                }
+            }
+        }
+        ItemKind::TyAlias(alias) => root.nested(&alias.name, &alias.generics, &[], diagnostics).check_ty(&alias.name, &alias.ty, diagnostics),
+        ItemKind::StaticVal(_) | ItemKind::ConstVal(_) => {}
+    }
+}
+
+fn check_fn(parent: &LifetimeScope, header: &FunctionHeader, diagnostics: &mut DiagnosticBuilder) {
+    let scope = parent.nested(&header.name, &header.generics, &header.where_clause, diagnostics);
+    for (_, ty) in &*header.args {
+        scope.check_ty(&header.name, ty, diagnostics);
+    }
+    let ret = if let Some(ret) = &header.ret {
+        ret
+    } else {
+        return;
+    };
+    scope.check_ty(&header.name, ret, diagnostics);
+
+    // FIXME: elided lifetimes of `&self` receivers take precedence once they are supported
+    let mut elided_ret = 0;
+    collect_lifetimes(ret, &mut |lt| if lt.is_none() {
+        elided_ret += 1;
+    });
+    if elided_ret == 0 {
+        return;
+    }
+    // every elided lifetime in the args is a distinct lifetime on its own
+    let mut inputs = HashSet::new();
+    let mut elided_inputs = 0;
+    for (_, ty) in &*header.args {
+        collect_lifetimes(ty, &mut |lt| match lt {
+            Some(lt) => {
+                inputs.insert(lt.to_string().to_string());
+            }
+            None => elided_inputs += 1,
+        });
+    }
+    if inputs.len() + elided_inputs != 1 {
+        let mut diagnostic = report(diagnostics, &header.name, format!("missing lifetime specifier in the return type `{}`", ret.to_string()));
+        diagnostic.note(format!("it has to be specified explicitly as there are {} lifetimes in the args", inputs.len() + elided_inputs));
+        diagnostic.build();
+    }
+}
+
+/// starts a lifetime error inside of `item`
+fn report<'a>(diagnostics: &'a mut DiagnosticBuilder, item: &str, error: String) -> DiagnosticSubBuilder<'a> {
+    let mut diagnostic = diagnostics.diagnostic(item.to_string());
+    diagnostic.error(error);
+    diagnostic
+}
+
+/// calls `f` for every reference's lifetime (`None` if it is elided) which is part of `ty`
+/// without descending into fn pointers as they have their own elision rules
+fn collect_lifetimes<'a>(ty: &'a Ty, f: &mut impl FnMut(Option<&'a Lifetime>)) {
+    match &ty.kind {
+        TyKind::Ref(rf) => {
+            f(rf.lt.as_ref().filter(|lt| **lt != Lifetime::Inferred));
+            collect_lifetimes(&rf.ty, f);
+        }
+        TyKind::Array(array) => collect_lifetimes(&array.ty, f),
+        TyKind::Owned(owned) => {
+            for generic in &*owned.generics {
+                match generic {
+                    TyOrConstVal::Ty(generic) => collect_lifetimes(generic, f),
+                    TyOrConstVal::Lifetime(lt) => f(Some(lt).filter(|lt| **lt != Lifetime::Inferred)),
+                    TyOrConstVal::ConstVal(_) => {}
+                }
+            }
+        }
+        TyKind::Func(_) => {}
+        TyKind::Projection(projection) => collect_lifetimes(&projection.ty, f),
+        TyKind::Dyn(dyn_ty) => collect_lifetimes(&dyn_ty.tait, f),
+    }
+}
+
+/// the lifetimes which are in scope for an item together with the known outlives relations between them
+#[derive(Default, Clone)]
+struct LifetimeScope {
+    declared: HashSet<String>,
+    /// `'b: 'a` results in `'a` being part of the entry for `'b`
+    outlives: HashMap<String, HashSet<String>>,
+}
+
+impl LifetimeScope {
+
+    /// creates the scope of an item which is nested inside `self`, e.g. a method inside an impl
+    fn nested(&self, item: &String, generics: &[Generic], where_clause: &[WherePredicate], diagnostics: &mut DiagnosticBuilder) -> Self {
+        let mut scope = self.clone();
+        for generic in generics {
+            if let Generic::Lifetime(generic) = generic {
+                if let Lifetime::Custom(name) = &generic.lt {
+                    if !scope.declared.insert(name.clone()) {
+                        report(diagnostics, item, format!("the lifetime `'{}` is declared multiple times", name)).build();
+                    }
+                }
+            }
+        }
+        for generic in generics {
+            match generic {
+                Generic::Lifetime(generic) => scope.add_outlives(item, &generic.lt, &generic.constraints, diagnostics),
+                Generic::Type(ty) => {
+                    for bound in &*ty.required_traits {
+                        scope.check_ty(item, bound, diagnostics);
+                    }
+                    for lt in &*ty.required_lifetimes {
+                        scope.check_declared(item, lt, diagnostics);
+                    }
+                }
+                Generic::Constant(constant) => scope.check_ty(item, &constant.ty, diagnostics),
+            }
+        }
+        for predicate in where_clause {
+            match predicate {
+                WherePredicate::Lifetime(predicate) => {
+                    scope.check_declared(item, &predicate.lt, diagnostics);
+                    scope.add_outlives(item, &predicate.lt, &predicate.constraints, diagnostics);
+                }
+                WherePredicate::Ty(predicate) => {
+                    scope.check_ty(item, &predicate.ty, diagnostics);
+                    for bound in &*predicate.required_traits {
+                        scope.check_ty(item, bound, diagnostics);
+                    }
+                    for lt in &*predicate.required_lifetimes {
+                        scope.check_declared(item, lt, diagnostics);
+                    }
+                }
+            }
+        }
+        scope
+    }
+
+    fn add_outlives(&mut self, item: &str, lt: &Lifetime, constraints: &[Lifetime], diagnostics: &mut DiagnosticBuilder) {
+        for constraint in constraints {
+            self.check_declared(item, constraint, diagnostics);
+            // `'static` outlives everything anyways and inferred lifetimes don't tell us anything
+            if let (Lifetime::Custom(longer), Lifetime::Custom(_) | Lifetime::Static) = (lt, constraint) {
+                self.outlives.entry(longer.clone()).or_default().insert(constraint.to_string().to_string());
+            }
+        }
+    }
+
+    fn check_declared(&self, item: &str, lt: &Lifetime, diagnostics: &mut DiagnosticBuilder) {
+        if let Lifetime::Custom(name) = lt {
+            if !self.declared.contains(name) {
+                report(diagnostics, item, format!("use of undeclared lifetime `'{}`", name)).build();
+            }
+        }
+    }
+
+    /// checks whether `longer: shorter` holds, either directly or transitively
+    fn outlives(&self, longer: &Lifetime, shorter: &Lifetime) -> bool {
+        let (longer, shorter) = match (longer, shorter) {
+            (Lifetime::Static, _) => return true,
+            (Lifetime::Custom(longer), Lifetime::Custom(_) | Lifetime::Static) => (longer.as_str(), shorter.to_string()),
+            // elided and inferred lifetimes get picked in a way that makes them valid
+            _ => return true,
+        };
+        let mut visited = HashSet::new();
+        let mut pending = vec![longer];
+        while let Some(curr) = pending.pop() {
+            if curr == shorter {
+                return true;
+            }
+            if !visited.insert(curr) {
+                continue;
+            }
+            if let Some(shorter_lts) = self.outlives.get(curr) {
+                pending.extend(shorter_lts.iter().map(|lt| lt.as_str()));
+            }
+        }
+        false
+    }
+
+    fn check_ty(&self, item: &String, ty: &Ty, diagnostics: &mut DiagnosticBuilder) {
+        match &ty.kind {
+            TyKind::Ref(rf) => {
+                if let Some(lt) = &rf.lt {
+                    self.check_declared(item, lt, diagnostics);
+                    // the referenced value has to live at least as long as the reference itself
+                    if let TyKind::Ref(inner) = &rf.ty.kind {
+                        if let Some(inner_lt) = &inner.lt {
+                            if !self.outlives(inner_lt, lt) {
+                                report(diagnostics, item, format!("the reference type `{}` requires `'{}: '{}` which isn't known to hold", ty.to_string(), inner_lt.to_string(), lt.to_string())).build();
+                            }
+                        }
+                    }
+                }
+                self.check_ty(item, &rf.ty, diagnostics);
+            }
+            TyKind::Array(array) => self.check_ty(item, &array.ty, diagnostics),
+            TyKind::Owned(owned) => {
+                for generic in &*owned.generics {
+                    match generic {
+                        TyOrConstVal::Ty(generic) => self.check_ty(item, generic, diagnostics),
+                        TyOrConstVal::Lifetime(lt) => self.check_declared(item, lt, diagnostics),
+                        TyOrConstVal::ConstVal(_) => {}
+                    }
+                }
+            }
+            TyKind::Func(func) => {
+                for arg in &*func.args {
+                    self.check_ty(item, arg, diagnostics);
+                }
+                if let Some(ret) = &func.ret {
+                    self.check_ty(item, ret, diagnostics);
+                }
+            }
+            TyKind::Projection(projection) => self.check_ty(item, &projection.ty, diagnostics),
+            TyKind::Dyn(dyn_ty) => self.check_ty(item, &dyn_ty.tait, diagnostics),
+        }
+    }
+
+}
@@ -25,32 +25,627 @@
 
 // https://github.com/audulus/lyte
 
-use crate::parser::attrs::{Mutability, Visibility};
+use crate::diagnostics::builder::{DiagnosticBuilder, DiagnosticSubBuilder};
+use crate::diagnostics::source::SourceFile;
+use crate::diagnostics::span::Span;
+use crate::diagnostics::suggest::find_best_match;
+use crate::parser::attrs::{deprecation, find_attr, is_lint_allowed, lang_item, Attribute, CaptureBy, Mutability, Visibility};
+use std::fmt::{Display, Formatter};
 use std::collections::HashMap;
+use std::rc::Rc;
 use std::string::ToString;
-use lazy_static::lazy_static;
 use crate::parser::ast;
-use crate::parser::ast::{ArrayInst, AstNode, FunctionNode, StmtKind, StructDef, AdtImpl, TyKind, TyOrConstVal, ItemKind, LocalAssign};
+use crate::const_eval::ConstEvaluator;
+use crate::parser::ast::{ArrayInst, AstNode, FunctionNode, StmtKind, StructDef, AdtImpl, TyKind, TyOrConstVal, ItemKind, LocalAssign, FuncTyKind, Generic, TyAlias, TraitDef, FunctionHeader, Block, NumberLit, NumberType, CallExprNode, WherePredicate, StructConstructor};
+use crate::traitsolver::{collect_bounds, CoherenceError, ProofTree, SolverError, TraitManager};
+use crate::tyck::closure::{analyze_captures, Capture};
+use crate::tyck::constness::{check_const_fn, infer_constness};
+use crate::parser::derive::DERIVED_ATTR;
+use crate::parser::macros::PRELUDE_INTRINSICS;
+use crate::tyck::impls::{check_derived_impl, check_negative_impl, check_trait_impl};
+use crate::tyck::infer::{instantiate, InferCtx, InferKind, InferTy, Origin, TypeError};
+use crate::tyck::lifetimes::check_item_lifetimes;
+use crate::tyck::objects::{check_dyn_ty, upcast};
+use crate::tyck::ops::tyck_bin_op;
+
+pub(crate) mod closure;
+mod constness;
+mod impls;
+mod infer;
+mod lifetimes;
+pub(crate) mod objects;
+mod ops;
+
+pub use objects::Vtable;
 
 pub const DEFAULT_PATH: &str = ""; // TODO: get rid of this once paths are properly implemented!
 
+/// the maximum number of nested alias expansions and projections that get resolved while normalizing a type
+const MAX_NORMALIZATION_DEPTH: usize = 64;
+
+/// the reasons for which a type can't be normalized
+#[derive(Debug)]
+pub enum NormalizeError {
+    RecursionLimit(String),
+    AliasArgs {
+        alias: String,
+        expected: usize,
+        found: usize,
+    },
+}
+
+fn is_ident(name: &str) -> bool {
+    !name.is_empty() && name.chars().all(|c| c.is_alphanumeric() || c == '_')
+}
+
 pub struct TyCtx {
     pub(crate) env: Environment,
+    pub(crate) traits: TraitManager,
+    pub(crate) diagnostics: DiagnosticBuilder,
+    pub(crate) infcx: InferCtx,
+    // the `#[allow(lint)]` attributes of the items which are currently being checked
+    allowed_lints: Vec<Attribute>,
+    // the generic params (and `Self`) which are in scope for the items which are currently being checked
+    generic_params: Vec<GenericParam>,
+    // the trait bounds of calls which can only be checked once the types of the body were inferred
+    pending_bounds: Vec<PendingBound>,
+    // the vtables of all casts to trait objects
+    vtables: Vec<Vtable>,
+    pub(crate) consts: ConstEvaluator,
+    // the evaluated lengths of array types by the way they are written, see `eval_array_lens`
+    array_lens: HashMap<String, Option<usize>>,
+    // the file the items which are being checked were parsed from
+    source: Rc<SourceFile>,
 }
 
-lazy_static! {
-    pub static ref EMPTY: TyCtx = TyCtx {
-        env: Environment {
-            scopes: vec![],
-            adts_by_path: HashMap::new(),
-            adt_impls_by_path: HashMap::new(),
-            funcs_by_path: HashMap::new(),
-        },
-    };
+struct GenericParam {
+    name: String,
+    bounds: Vec<ast::Ty>,
+}
+
+/// requires `ty` to implement the trait `bound`
+struct PendingBound {
+    ty: Ty,
+    bound: ast::Ty,
+    origin: Origin,
 }
 
 impl TyCtx {
 
+    /// a ctx without any items, this is used to resolve the types of
+    /// values which may not depend on any other values (e.g. consts)
+    pub fn empty() -> Self {
+        Self {
+            env: Environment::new(),
+            traits: {
+                let mut traits = TraitManager::new();
+                traits.insert_builtin_impls();
+                traits
+            },
+            diagnostics: DiagnosticBuilder::new(),
+            infcx: InferCtx::new(),
+            allowed_lints: vec![],
+            generic_params: vec![],
+            pending_bounds: vec![],
+            vtables: vec![],
+            consts: ConstEvaluator::new(),
+            array_lens: HashMap::new(),
+            source: Rc::new(SourceFile::default()),
+        }
+    }
+
+    /// sets the file the items were parsed from, diagnostics about items which have a span point into it
+    pub fn set_source(&mut self, source: SourceFile) {
+        let source = Rc::new(source);
+        self.consts.set_source(source.clone());
+        self.source = source;
+    }
+
+    /// the vtables of the types which got cast to trait objects, see `Vtable`
+    pub fn vtables(&self) -> &[Vtable] {
+        &self.vtables
+    }
+
+    /// requires `found` to be of type `expected` and reports a diagnostic if it isn't
+    pub(crate) fn unify(&mut self, expected: &Ty, found: &Ty, origin: Origin) {
+        if let Err(error) = self.infcx.unify(expected, found, &origin) {
+            self.report_type_error(*error);
+        }
+    }
+
+    /// requires `found` to be usable where `expected` is expected, this is `unify` except for
+    /// casts to trait objects like `&Circle` to `&dyn Shape` which require `Circle: Shape`
+    pub(crate) fn coerce(&mut self, expected: &Ty, found: &Ty, origin: Origin) {
+        let (target, source) = (self.infcx.resolve_fully(expected), self.infcx.resolve_fully(found));
+        // trait objects can be used behind references and boxes
+        let is_box = |strukt: &StructTy| self.traits.lang_item("owned_box") == Some(&strukt.name);
+        let pointees = match (&target, &source) {
+            (Ty::Ref(target), Ty::Ref(source)) if source.mutability == Mutability::Mut || target.mutability == Mutability::Immut => Some((&*target.ty, &*source.ty)),
+            (Ty::Struct(target), Ty::Struct(source)) if is_box(target) && is_box(source) => target.generics.first().zip(source.generics.first()),
+            _ => None,
+        };
+        // objects can be cast to the objects of their supertraits as well
+        if let Some((target_ty @ Ty::Dyn(target_dyn), source_ty)) = pointees {
+            if !matches!(source_ty, Ty::Infer(_)) && source_ty != target_ty {
+                let origin = Origin(format!("cast from `{}` to `{}` for the {}", source, target, origin.0));
+                upcast(self, source_ty, target_dyn, origin);
+                return;
+            }
+        }
+        self.unify(expected, found, origin);
+    }
+
+    fn report_type_error(&mut self, error: TypeError) {
+        let (expected, found) = (error.expected.to_string(), error.found.to_string());
+        let mut diagnostic = self.diagnostics.diagnostic(error.origin.0.clone());
+        diagnostic.error(format!("mismatched types: expected `{}`, found `{}`", expected, found));
+        diagnostic.note(format!("the types are required to match because of the {}", error.origin.0));
+        if let Some(origin) = error.expected_origin {
+            diagnostic.note(format!("expected `{}` because of the {}", expected, origin.0));
+        }
+        if let Some(origin) = error.found_origin {
+            diagnostic.note(format!("found `{}` because of the {}", found, origin.0));
+        }
+        diagnostic.build();
+    }
+
+    /// defaults the literals whose types couldn't be inferred, this has to be called
+    /// once all constraints of a body were added, `start` is the value of `infcx.num_vars()` before checking the body
+    pub(crate) fn finish_inference(&mut self, start: usize) {
+        self.infcx.default_vars(start);
+        for pending in std::mem::take(&mut self.pending_bounds) {
+            self.check_bound(pending);
+        }
+    }
+
+    fn check_bound(&mut self, pending: PendingBound) {
+        let ty = self.infcx.resolve_fully(&pending.ty);
+        let satisfied = match (&pending.bound.kind, &ty) {
+            // FIXME: report types which couldn't be inferred
+            (_, Ty::Infer(_) | Ty::Error) => true,
+            (TyKind::Func(func), _) => ty.satisfies_fn_bound(func),
+            _ => self.ty_implements(&ty, &pending.bound),
+        };
+        if !satisfied {
+            let proof = ty.to_ast_ty().and_then(|ast_ty| self.explain(&ast_ty, &pending.bound));
+            self.report_unsatisfied_bound(&ty.to_string(), &pending.bound, &pending.origin, proof);
+        }
+    }
+
+    /// whether the inferred type `ty` implements the trait `bound`,
+    /// tuples implement the traits known to the compiler (like `Send` or `Copy`) if all of their fields do
+    fn ty_implements(&self, ty: &Ty, bound: &ast::Ty) -> bool {
+        let builtin = self.traits.is_builtin_trait(&bound.kind.simple_ty_name());
+        match ty {
+            Ty::Infer(_) | Ty::Error => true,
+            Ty::Tuple(tuple) if builtin => tuple.fields.iter().all(|field| self.ty_implements(&field.ty, bound)),
+            Ty::Empty => builtin,
+            _ => match ty.to_ast_ty() {
+                Some(ast_ty) => self.implements(&ast_ty, bound),
+                None => false,
+            },
+        }
+    }
+
+    /// whether `ty` implements the trait `bound`
+    fn implements(&self, ty: &ast::Ty, bound: &ast::Ty) -> bool {
+        if let TyKind::Func(func) = &bound.kind {
+            return self.lower_ty(ty).satisfies_fn_bound(func);
+        }
+        self.traits.has_impl_in(ty, bound, &self.param_env())
+    }
+
+    /// the bounds of the generic params in scope together with their supertraits,
+    /// generic params only implement the traits they are (transitively) bounded by
+    pub(crate) fn param_env(&self) -> Vec<(ast::Ty, ast::Ty)> {
+        let mut env = vec![];
+        for param in &self.generic_params {
+            let ty = ast::Ty {
+                kind: TyKind::Owned(Box::new(ast::OwnedTy {
+                    name: param.name.clone(),
+                    generics: Box::new([]),
+                })),
+            };
+            for bound in &param.bounds {
+                env.extend(self.traits.elaborate(bound).into_iter().map(|bound| (ty.clone(), bound)));
+            }
+        }
+        env
+    }
+
+    /// the proof tree of `ty: bound` which explains why the bound doesn't hold, see `TraitManager::explain`
+    fn explain(&self, ty: &ast::Ty, bound: &ast::Ty) -> Option<ProofTree> {
+        match &bound.kind {
+            TyKind::Func(_) => None,
+            _ => Some(self.traits.explain(ty, bound, &self.param_env())),
+        }
+    }
+
+    fn report_unsatisfied_bound(&mut self, ty: &String, bound: &ast::Ty, origin: &Origin, proof: Option<ProofTree>) {
+        let mut diagnostic = self.diagnostics.diagnostic(origin.0.clone());
+        diagnostic.error(format!("the trait bound `{}: {}` is not satisfied", ty, bound.to_string()));
+        diagnostic.note(format!("required by the {}", origin.0));
+        if let Some(proof) = proof {
+            note_proof_tree(&mut diagnostic, &proof, &self.source);
+        }
+        diagnostic.build();
+    }
+
+    /// checks the generic args of a generic struct or trait against the bounds of its params,
+    /// e.g. `Civilization<u8>` requires `u8: Send + Sync` for `struct Civilization<Continent: Send + Sync>`
+    fn check_generic_args(&mut self, ty: &ast::Ty) {
+        let owned = match ty.kind.get_owned() {
+            Some(owned) if !owned.generics.is_empty() => owned,
+            _ => return,
+        };
+        let path = DEFAULT_PATH.to_string();
+        let (generics, where_clause) = if let Some((Adt::Struct(def), _)) = self.env.resolve_adt(&path, &owned.name) {
+            (def.generics.clone(), def.where_clause.clone())
+        } else if let Some(def) = self.env.resolve_trait(&path, &owned.name) {
+            (def.generics.clone(), def.where_clause.clone())
+        } else {
+            return;
+        };
+        let params = generics.iter().filter_map(|generic| match generic {
+            Generic::Type(ty) => Some(ty.name.clone()),
+            Generic::Constant(_) | Generic::Lifetime(_) => None,
+        }).collect::<Vec<_>>();
+        let args = owned.generics.iter().filter_map(|generic| match generic {
+            TyOrConstVal::Ty(ty) => Some(self.normalize_ast_ty(ty)),
+            TyOrConstVal::ConstVal(_) | TyOrConstVal::Lifetime(_) => None,
+        }).collect::<Vec<_>>();
+        if params.len() != args.len() {
+            // FIXME: report the wrong number of generic args
+            return;
+        }
+        // the bounds may refer to the other params, e.g. `struct Foo<A, B: Into<A>>`
+        let substs = params.iter().cloned().zip(args.iter().cloned()).collect::<HashMap<_, _>>();
+        let bounds = collect_bounds(&generics, &where_clause);
+        for (param, arg) in params.iter().zip(args.iter()) {
+            for bound in bounds.get(param).into_iter().flatten() {
+                let bound = bound.substitute(&substs);
+                if !self.implements(arg, &bound) {
+                    let origin = Origin(format!("bound `{}: {}` of `{}`", param, bound.to_string(), owned.name));
+                    let proof = self.explain(arg, &bound);
+                    self.report_unsatisfied_bound(&arg.to_string(), &bound, &origin, proof);
+                }
+            }
+        }
+    }
+
+    /// checks the generic args of the traits used as bounds of `generics` and `where_clause`, see `check_generic_args`
+    pub(crate) fn check_bound_args(&mut self, generics: &[Generic], where_clause: &[WherePredicate]) {
+        for bound in collect_bounds(generics, where_clause).values().flatten() {
+            self.check_generic_args(bound);
+        }
+    }
+
+    /// allows all lints listed in `#[allow(...)]` attributes until `restore_lints` gets called
+    /// with the returned value
+    pub(crate) fn allow_lints(&mut self, attrs: &[Attribute]) -> usize {
+        let prev = self.allowed_lints.len();
+        self.allowed_lints.extend(attrs.iter().filter(|attr| attr.name() == "allow").cloned());
+        prev
+    }
+
+    /// whether `lint` was allowed on one of the items which are currently being checked (or on the crate)
+    pub(crate) fn is_lint_allowed(&self, lint: &str) -> bool {
+        is_lint_allowed(&self.allowed_lints, lint)
+    }
+
+    pub(crate) fn restore_lints(&mut self, prev: usize) {
+        self.allowed_lints.truncate(prev);
+    }
+
+    /// brings the type params of `generics` together with their bounds into scope until `restore_generics` gets called
+    /// with the returned value
+    pub(crate) fn push_generics(&mut self, generics: &[Generic], where_clause: &[WherePredicate]) -> usize {
+        let prev = self.generic_params.len();
+        let mut bounds = collect_bounds(generics, where_clause);
+        self.generic_params.extend(generics.iter().filter_map(|generic| match generic {
+            Generic::Type(ty) => Some(GenericParam {
+                name: ty.name.clone(),
+                bounds: bounds.remove(&ty.name).unwrap_or_default(),
+            }),
+            Generic::Constant(_) | Generic::Lifetime(_) => None,
+        }));
+        prev
+    }
+
+    /// brings `Self` into scope, e.g. inside of traits `Self` is bounded by the trait itself
+    pub(crate) fn push_self_param(&mut self, bounds: Vec<ast::Ty>) {
+        self.generic_params.push(GenericParam {
+            name: "Self".to_string(),
+            bounds,
+        });
+    }
+
+    fn is_generic_param(&self, name: &String) -> bool {
+        self.generic_params.iter().any(|param| &param.name == name)
+    }
+
+    /// the signature of the function trait the generic param `name` is bounded by as a function pointer,
+    /// e.g. `fn(u32) -> u32` for `F: FnMut(u32) -> u32`
+    fn fn_bound_of(&self, name: &String) -> Option<Ty> {
+        let param = self.generic_params.iter().rev().find(|param| &param.name == name)?;
+        param.bounds.iter().find_map(|bound| match &bound.kind {
+            TyKind::Func(func) => Some(Ty::FnPtr(FnPtrTy {
+                args: func.args.iter().map(|arg| self.lower_ty(arg)).collect(),
+                ret: Box::new(func.ret.as_ref().map_or(Ty::Empty, |ret| self.lower_ty(ret))),
+            })),
+            _ => None,
+        })
+    }
+
+    pub(crate) fn restore_generics(&mut self, prev: usize) {
+        self.generic_params.truncate(prev);
+    }
+
+    /// lowers an ast type after expanding all the aliases and projections contained within it,
+    /// types which contain errors are lowered to `Ty::Error`
+    pub fn lower_ty(&self, ty: &ast::Ty) -> Ty {
+        match self.try_normalize_ast_ty(ty) {
+            Ok(ty) => self.resolve_adts(Ty::from_ast_ty(ty.kind)),
+            Err(_) => Ty::Error,
+        }
+    }
+
+    /// replaces the names of user defined types by the types themselves
+    /// and unknown names (which were already reported by `check_ty_names`) by `Ty::Error`
+    fn resolve_adts(&self, ty: Ty) -> Ty {
+        match ty {
+            Ty::Unresolved(unresolved) => match self.resolve_named_ty(&DEFAULT_PATH.to_string(), &unresolved.name) {
+                Some(Ty::Struct(strukt)) if !self.is_generic_param(&unresolved.name) => {
+                    let args = unresolved.generics.iter().filter_map(|generic| match generic {
+                        TyOrConstVal::Ty(ty) => Some(self.resolve_adts(Ty::from_ast_ty(ty.kind.clone()))),
+                        TyOrConstVal::ConstVal(_) | TyOrConstVal::Lifetime(_) => None,
+                    }).collect();
+                    Ty::Struct(strukt.with_args(args))
+                }
+                Some(adt) if !self.is_generic_param(&unresolved.name) => adt.clone(),
+                // projections and function trait bounds aren't plain names
+                _ if is_ident(&unresolved.name) && !self.is_known_ty_name(&unresolved.name) => Ty::Error,
+                _ => Ty::Unresolved(unresolved),
+            },
+            Ty::Ref(rf) => Ty::Ref(RefTy {
+                lt: rf.lt,
+                mutability: rf.mutability,
+                ty: Box::new(self.resolve_adts(*rf.ty)),
+            }),
+            Ty::Array(array) => Ty::Array(ArrayTy {
+                elem_ty: Box::new(self.resolve_adts(*array.elem_ty)),
+                len: array.len,
+            }),
+            Ty::FnPtr(func) => Ty::FnPtr(FnPtrTy {
+                args: func.args.into_vec().into_iter().map(|arg| self.resolve_adts(arg)).collect(),
+                ret: Box::new(self.resolve_adts(*func.ret)),
+            }),
+            ty => ty,
+        }
+    }
+
+    /// reports all names inside `ty` which neither refer to a type nor to a generic param in scope,
+    /// this should be called once for every type written in the source
+    pub(crate) fn check_ty_names(&mut self, ty: &ast::Ty) {
+        match &ty.kind {
+            // trait objects can be used behind references and boxes
+            TyKind::Ref(rf) => match &rf.ty.kind {
+                TyKind::Dyn(dyn_ty) => check_dyn_ty(self, dyn_ty),
+                _ => self.check_ty_names(&rf.ty),
+            },
+            TyKind::Array(array) => {
+                self.check_ty_names(&array.ty);
+                if let Some(amount) = &array.amount {
+                    self.eval_array_len(amount, ty.to_string());
+                }
+            }
+            TyKind::Owned(owned) => {
+                let is_box = self.traits.lang_item("owned_box") == Some(&owned.name);
+                for generic in &*owned.generics {
+                    match generic {
+                        TyOrConstVal::Ty(ast::Ty { kind: TyKind::Dyn(dyn_ty) }) if is_box => check_dyn_ty(self, dyn_ty),
+                        TyOrConstVal::Ty(ty) => self.check_ty_names(ty),
+                        TyOrConstVal::ConstVal(val) => {
+                            self.consts.eval(val, None, ty.to_string(), &mut self.diagnostics);
+                        }
+                        TyOrConstVal::Lifetime(_) => {}
+                    }
+                }
+                if !self.is_known_ty_name(&owned.name) {
+                    self.report_unknown_ty(&owned.name);
+                } else if self.env.resolve_ty_alias(&DEFAULT_PATH.to_string(), &owned.name).is_some() {
+                    if let Err(error @ NormalizeError::AliasArgs { .. }) = self.try_normalize_ast_ty(ty) {
+                        self.report_normalize_error(ty, error);
+                    }
+                } else {
+                    self.check_generic_args(ty);
+                }
+            }
+            TyKind::Func(func) => {
+                for arg in &*func.args {
+                    self.check_ty_names(arg);
+                }
+                if let Some(ret) = &func.ret {
+                    self.check_ty_names(ret);
+                }
+            }
+            // the projected name can only be checked once the base type is known
+            TyKind::Projection(projection) => self.check_ty_names(&projection.ty),
+            TyKind::Dyn(dyn_ty) => {
+                let mut diagnostic = self.diagnostics.diagnostic(ty.to_string());
+                diagnostic.error(format!("the size for values of type `{}` cannot be known at compilation time", ty.to_string()));
+                diagnostic.note(format!("trait objects can only be used behind a pointer like `&{}`", ty.to_string()));
+                diagnostic.build();
+                check_dyn_ty(self, dyn_ty);
+            }
+        }
+    }
+
+    /// evaluates the lengths of the arrays inside `ty`, so they are known once it gets lowered,
+    /// and returns `ty` with the lengths replaced by their values
+    fn eval_array_lens(&mut self, ty: &ast::Ty) -> ast::Ty {
+        let kind = match &ty.kind {
+            TyKind::Ref(rf) => TyKind::Ref(Box::new(ast::RefTy {
+                lt: rf.lt.clone(),
+                mutability: rf.mutability,
+                ty: Box::new(self.eval_array_lens(&rf.ty)),
+            })),
+            TyKind::Array(array) => {
+                if let Some(amount) = &array.amount {
+                    self.eval_array_len(amount, ty.to_string());
+                }
+                TyKind::Array(Box::new(ast::ArrayTy {
+                    ty: self.eval_array_lens(&array.ty),
+                    amount: self.evaluated_amount(array),
+                }))
+            }
+            TyKind::Owned(owned) => TyKind::Owned(Box::new(ast::OwnedTy {
+                name: owned.name.clone(),
+                generics: owned.generics.iter().map(|generic| match generic {
+                    TyOrConstVal::Ty(ty) => TyOrConstVal::Ty(self.eval_array_lens(ty)),
+                    generic => generic.clone(),
+                }).collect(),
+            })),
+            kind => kind.clone(),
+        };
+        ast::Ty { kind }
+    }
+
+    /// evaluates the length `amount` of the array type `input`, the lengths are cached by the way
+    /// they are written, so each one gets evaluated (and its errors reported) only once
+    fn eval_array_len(&mut self, amount: &AstNode, input: String) -> Option<usize> {
+        if let Some(len) = self.array_lens.get(&amount.to_string()) {
+            return *len;
+        }
+        let len = self.consts.eval_usize(amount, input, &mut self.diagnostics);
+        self.array_lens.insert(amount.to_string(), len);
+        len
+    }
+
+    /// the length of `array` as a literal if it was evaluated already, see `eval_array_len`
+    fn evaluated_amount(&self, array: &ast::ArrayTy) -> Option<AstNode> {
+        match array.amount.as_ref().and_then(|amount| self.array_lens.get(&amount.to_string())) {
+            Some(Some(len)) => Some(len_literal(*len)),
+            _ => array.amount.clone(),
+        }
+    }
+
+    fn is_known_ty_name(&self, name: &String) -> bool {
+        name == "_"
+            || PrimitiveTy::from_name(name).is_some()
+            || self.is_generic_param(name)
+            || self.env.resolve_adt(&DEFAULT_PATH.to_string(), name).is_some()
+            || self.env.resolve_ty_alias(&DEFAULT_PATH.to_string(), name).is_some()
+    }
+
+    fn report_unknown_ty(&mut self, name: &String) {
+        let path = DEFAULT_PATH.to_string();
+        let mut candidates = PRIMITIVE_TY_NAMES.iter().map(|name| name.to_string()).collect::<Vec<_>>();
+        candidates.extend(self.generic_params.iter().map(|param| param.name.clone()));
+        candidates.extend(self.env.adts_by_path.get(&path).into_iter().flat_map(|adts| adts.keys().cloned()));
+        candidates.extend(self.env.ty_aliases_by_path.get(&path).into_iter().flat_map(|aliases| aliases.keys().cloned()));
+        let suggestion = find_best_match(name, candidates.iter().map(|candidate| candidate.as_str()));
+
+        let mut diagnostic = self.diagnostics.diagnostic(name.clone());
+        diagnostic.error(format!("unknown type `{}`", name));
+        if let Some(suggestion) = suggestion {
+            diagnostic.note(format!("a type with a similar name exists: `{}`", suggestion));
+        }
+        diagnostic.build();
+    }
+
+    /// expands type aliases and resolves projections on types whose impls are known
+    /// e.g. for `type Alias<T> = Wrapper<T>;` `Alias<u8>` gets turned into `Wrapper<u8>`,
+    /// types which can't be normalized are returned as is (the error gets reported where the alias is defined or used)
+    pub fn normalize_ast_ty(&self, ty: &ast::Ty) -> ast::Ty {
+        self.try_normalize_ast_ty(ty).unwrap_or_else(|_| ty.clone())
+    }
+
+    pub fn try_normalize_ast_ty(&self, ty: &ast::Ty) -> Result<ast::Ty, NormalizeError> {
+        self.normalize_ast_ty_with_depth(ty, 0)
+    }
+
+    fn normalize_ast_ty_with_depth(&self, ty: &ast::Ty, depth: usize) -> Result<ast::Ty, NormalizeError> {
+        if depth > MAX_NORMALIZATION_DEPTH {
+            return Err(NormalizeError::RecursionLimit(ty.to_string()));
+        }
+        let kind = match &ty.kind {
+            TyKind::Ref(rf) => TyKind::Ref(Box::new(ast::RefTy {
+                lt: rf.lt.clone(),
+                mutability: rf.mutability,
+                ty: Box::new(self.normalize_ast_ty_with_depth(&rf.ty, depth)?),
+            })),
+            TyKind::Array(array) => TyKind::Array(Box::new(ast::ArrayTy {
+                ty: self.normalize_ast_ty_with_depth(&array.ty, depth)?,
+                amount: self.evaluated_amount(array),
+            })),
+            TyKind::Owned(owned) => {
+                let generics = owned.generics.iter().map(|generic| Ok(match generic {
+                    TyOrConstVal::Ty(ty) => TyOrConstVal::Ty(self.normalize_ast_ty_with_depth(ty, depth)?),
+                    TyOrConstVal::ConstVal(val) => TyOrConstVal::ConstVal(val.clone()),
+                    TyOrConstVal::Lifetime(lt) => TyOrConstVal::Lifetime(lt.clone()),
+                })).collect::<Result<Vec<_>, _>>()?;
+                if let Some(alias) = self.env.resolve_ty_alias(&DEFAULT_PATH.to_string(), &owned.name) {
+                    let params = alias.generics.iter().filter_map(|generic| match generic {
+                        Generic::Type(ty) => Some(ty.name.clone()),
+                        _ => None,
+                    }).collect::<Vec<_>>();
+                    let args = generics.iter().filter_map(|generic| match generic {
+                        TyOrConstVal::Ty(ty) => Some(ty.clone()),
+                        TyOrConstVal::ConstVal(_) | TyOrConstVal::Lifetime(_) => None,
+                    }).collect::<Vec<_>>();
+                    if params.len() != args.len() {
+                        return Err(NormalizeError::AliasArgs {
+                            alias: alias.name.clone(),
+                            expected: params.len(),
+                            found: args.len(),
+                        });
+                    }
+                    let substitutions = params.into_iter().zip(args).collect::<HashMap<_, _>>();
+                    return self.normalize_ast_ty_with_depth(&alias.ty.substitute(&substitutions), depth + 1);
+                }
+                TyKind::Owned(Box::new(ast::OwnedTy {
+                    name: owned.name.clone(),
+                    generics: generics.into_boxed_slice(),
+                }))
+            }
+            TyKind::Func(func) => TyKind::Func(Box::new(ast::FuncTy {
+                kind: func.kind,
+                args: func.args.iter().map(|arg| self.normalize_ast_ty_with_depth(arg, depth)).collect::<Result<_, _>>()?,
+                ret: func.ret.as_ref().map(|ret| self.normalize_ast_ty_with_depth(ret, depth)).transpose()?,
+            })),
+            TyKind::Dyn(dyn_ty) => TyKind::Dyn(Box::new(ast::DynTy {
+                tait: self.normalize_ast_ty_with_depth(&dyn_ty.tait, depth)?,
+            })),
+            TyKind::Projection(projection) => {
+                let base = self.normalize_ast_ty_with_depth(&projection.ty, depth)?;
+                if let Some(resolved) = self.traits.resolve_projection(&base, &projection.name) {
+                    return self.normalize_ast_ty_with_depth(&resolved, depth + 1);
+                }
+                // projections on generic params can only be resolved once the params are known
+                TyKind::Projection(Box::new(ast::ProjectionTy {
+                    ty: base,
+                    name: projection.name.clone(),
+                }))
+            }
+        };
+        Ok(ast::Ty { kind })
+    }
+
+    pub(crate) fn report_normalize_error(&mut self, ty: &ast::Ty, error: NormalizeError) {
+        let mut diagnostic = self.diagnostics.diagnostic(ty.to_string());
+        match error {
+            NormalizeError::RecursionLimit(ty) => {
+                diagnostic.error(format!("reached the recursion limit while normalizing `{}`", ty));
+                diagnostic.note("is there a cycle between type aliases?".to_string());
+            }
+            NormalizeError::AliasArgs { alias, expected, found } => {
+                diagnostic.error(format!("the type alias `{}` takes {} but {} supplied", alias, count(expected, "generic argument"), was_were(found)));
+            }
+        }
+        diagnostic.build();
+    }
+
     pub fn push_scope(&mut self) {
         self.env.push_scope();
     }
@@ -63,75 +658,358 @@ impl TyCtx {
         match local_assign {
             LocalAssign::Assign(assign) => {
                 let ty = tyck_node(self, &assign.val);
-                self.env.define_var(assign.name.clone(), ty);
+                if let Some(var_ty) = self.env.resolve_var(&assign.name) {
+                    self.unify(&var_ty, &ty, Origin(format!("assignment to `{}`", assign.name)));
+                } else {
+                    self.env.define_var(assign.name.clone(), ty);
+                }
             }
             LocalAssign::DecAssign(assign) => {
-                let ty = tyck_node(self, &assign.val.val);
+                let mut ty = tyck_node(self, &assign.val.val);
+                if let Some(annotation) = &assign.ty {
+                    self.check_ty_names(annotation);
+                    let annotation = self.lower_ty(annotation);
+                    self.coerce(&annotation, &ty, Origin(format!("type annotation of `{}`", assign.val.name)));
+                    ty = annotation;
+                }
                 self.env.define_var(assign.val.name.clone(), ty);
             }
         }
     }
 
-    pub fn resolve_ty(&self, ast_node: &AstNode) -> Option<Ty> {
+    pub fn resolve_ty(&mut self, ast_node: &AstNode) -> Option<Ty> {
         match ast_node {
-            AstNode::Number(_) => Some(Ty::Primitive(PrimitiveTy::UnsizedInt)),
-            AstNode::Ident(ident) => self.env.resolve_var(ident),
-            AstNode::BinaryExpr(expr) => {
-                // FIXME: support different return types (as in different from the base type)
-                let expected_ty = self.resolve_ty(&expr.lhs);
-                if let Some(expected_ty) = &expected_ty {
-                    let rhs_ty = self.resolve_ty(&expr.rhs);
-                    if let Some(rhs_ty) = rhs_ty {
-                        if !expected_ty.could_be(&rhs_ty) {
-                            panic!("Types of BinaryExpr differ: lhs: {:?} rhs: {:?}", expected_ty, rhs_ty);
+            AstNode::Number(number) => Some(match number.val {
+                NumberType::F32(_) | NumberType::F64(_) => self.infcx.fresh(InferKind::Float, Origin(format!("float literal `{}`", number.val))),
+                _ => self.infcx.fresh(InferKind::Int, Origin(format!("integer literal `{}`", number.val))),
+            }),
+            AstNode::StrLit(_) => Some(Ty::Ref(RefTy {
+                lt: Some(Lifetime::Static),
+                mutability: Mutability::Immut,
+                ty: Box::new(Ty::Primitive(PrimitiveTy::Str)),
+            })),
+            AstNode::Ident(ident) => match self.env.resolve_var(ident) {
+                Some(ty) => Some(ty),
+                None => {
+                    let candidates = self.env.var_names();
+                    self.report_unknown_name("value", ident, candidates);
+                    Some(Ty::Error)
+                }
+            },
+            AstNode::BoolLit(_) => Some(Ty::Primitive(PrimitiveTy::Bool)),
+            AstNode::Error => Some(Ty::Error),
+            AstNode::BinaryExpr(expr) => Some(tyck_bin_op(self, expr)),
+            AstNode::CallExpr(call) => {
+                if let Some(func) = self.env.resolve_func(&call.callee) {
+                    if let Some(note) = deprecation(&func.attrs) {
+                        if !self.is_lint_allowed("deprecated") {
+                            let mut diagnostic = self.diagnostics.diagnostic(call.callee.clone());
+                            diagnostic.warn(format!("use of deprecated function `{}`", call.callee));
+                            if let Some(note) = note {
+                                diagnostic.note(note.clone());
+                            }
+                            diagnostic.build();
+                        }
+                    }
+                    let func = func.clone();
+                    let params = self.instantiate_generics(call, &func.header);
+                    self.check_arg_count(call, func.header.args.len());
+                    for (idx, arg) in call.args.iter().enumerate() {
+                        let arg = tyck_node(self, arg);
+                        if let Some((name, param)) = func.header.args.get(idx) {
+                            let param = instantiate(&self.lower_callee_ty(&func.header, param), &params);
+                            self.coerce(&param, &arg, Origin(format!("argument {} (`{}`) of the call to `{}`", idx + 1, name, call.callee)));
                         }
-                    } else {
-                        panic!("Can't resolve type of rhs of BinaryExpr!");
                     }
+                    let ret = func.header.ret.as_ref().map_or(Ty::Empty, |ty| self.lower_callee_ty(&func.header, ty));
+                    return Some(instantiate(&ret, &params));
                 }
-                expected_ty
-            }
-            AstNode::CallExpr(call) => {
-                self.env.resolve_func(&call.callee).map(|x| x.header.ret.clone().map(|ty| Ty::from_ast_ty(ty.kind, None))).flatten()
+                // the callee may also be a local holding a closure or a function pointer
+                let args = call.args.iter().map(|arg| tyck_node(self, arg)).collect::<Vec<_>>();
+                if PRELUDE_INTRINSICS.contains(&call.callee.as_str()) && self.env.resolve_var(&call.callee).is_none() {
+                    return Some(Ty::Empty);
+                }
+                let callee = match self.env.resolve_var(&call.callee) {
+                    Some(callee) => callee,
+                    None => {
+                        let candidates = self.env.func_names().into_iter().chain(self.env.var_names()).collect();
+                        self.report_unknown_name("function", &call.callee, candidates);
+                        return Some(Ty::Error);
+                    }
+                };
+                let callee = match self.infcx.shallow_resolve(&callee).0 {
+                    // we don't know anything about the callee yet, so it has to be a function of the args
+                    var @ Ty::Infer(_) => {
+                        let func = Ty::FnPtr(FnPtrTy {
+                            args: args.iter().map(|_| self.infcx.fresh(InferKind::Ty, Origin(format!("argument of `{}`", call.callee)))).collect(),
+                            ret: Box::new(self.infcx.fresh(InferKind::Ty, Origin(format!("return type of `{}`", call.callee)))),
+                        });
+                        self.unify(&var, &func, Origin(format!("call of `{}`", call.callee)));
+                        func
+                    }
+                    Ty::Error => return Some(Ty::Error),
+                    // generic params like `F: FnMut(u32) -> u32` can be called like their bound
+                    Ty::Unresolved(param) => self.fn_bound_of(&param.name).unwrap_or(Ty::Unresolved(param)),
+                    callee => callee,
+                };
+                let (params, ret) = match callee.fn_sig() {
+                    Some(sig) => sig,
+                    None => {
+                        let mut diagnostic = self.diagnostics.diagnostic(call.callee.clone());
+                        diagnostic.error(format!("`{}` of type `{}` can't be called", call.callee, self.infcx.resolve_fully(&callee)));
+                        diagnostic.build();
+                        return Some(Ty::Error);
+                    }
+                };
+                let (params, ret) = (params.to_vec(), ret.clone());
+                self.check_arg_count(call, params.len());
+                if !call.generics.is_empty() {
+                    let mut diagnostic = self.diagnostics.diagnostic(call.callee.clone());
+                    diagnostic.error(format!("`{}` is a local and can't take generic arguments", call.callee));
+                    diagnostic.build();
+                }
+                for (idx, (param, arg)) in params.iter().zip(args.iter()).enumerate() {
+                    self.coerce(param, arg, Origin(format!("argument {} of the call to `{}`", idx + 1, call.callee)));
+                }
+                Some(ret)
             }
             AstNode::Block(block) => {
-                if let Some(last) = block.stmts.last() {
-                    if let StmtKind::Expr(expr) = last {
-                        self.resolve_ty(expr)
-                    } else {
-                        Some(Ty::Empty)
+                self.push_scope();
+                // items may be used before they are defined
+                for stmt in &*block.stmts {
+                    if let StmtKind::Item(item) = stmt {
+                        self.insert_item_local(item);
+                    }
+                }
+                let mut ret = Some(Ty::Empty);
+                for stmt in &*block.stmts {
+                    match stmt {
+                        StmtKind::Item(item) => tyck_item(self, item),
+                        StmtKind::LocalAssign(local) => self.push_local(local),
+                        StmtKind::Expr(expr) => ret = Some(tyck_node(self, expr)),
+                        StmtKind::Semi(semi) => {
+                            tyck_node(self, semi);
+                        }
+                        StmtKind::Empty => {}
                     }
-                } else {
-                    Some(Ty::Empty)
                 }
+                self.pop_scope();
+                ret
             }
             AstNode::StructConstructor(constructor) => {
                 let ret = self.env.resolve_adt(&DEFAULT_PATH.to_string(), &constructor.name).map(|adt| &adt.1).cloned();
-                println!("struct constr: {:?}", ret);
-                ret
+                if ret.is_none() {
+                    let candidates = self.env.adts_by_path.get(DEFAULT_PATH).into_iter().flat_map(|adts| adts.keys().cloned()).collect();
+                    self.report_unknown_name("struct", &constructor.name, candidates);
+                    for (_, val) in &*constructor.fields {
+                        tyck_node(self, val);
+                    }
+                    return Some(Ty::Error);
+                }
+                Some(self.check_struct_fields(constructor))
             },
             AstNode::ArrayInst(array) => {
                 match array {
                     ArrayInst::List(def) => {
-                        for def in &*def.vals {
-                            if let Some(val) = self.resolve_ty(def) {
-                                return Some(Ty::Array(ArrayTy {
-                                    elem_ty: Box::new(val),
-                                }));
-                            }
+                        let elem_ty = self.infcx.fresh(InferKind::Ty, Origin("array element".to_string()));
+                        for val in &*def.vals {
+                            let val = tyck_node(self, val);
+                            self.unify(&elem_ty, &val, Origin("array element".to_string()));
                         }
-                        None
+                        Some(Ty::Array(ArrayTy {
+                            elem_ty: Box::new(elem_ty),
+                            len: Some(def.vals.len()),
+                        }))
                     }
                     ArrayInst::Short(def) => {
+                        let len = self.consts.eval_usize(&def.amount, ast_node.to_string(), &mut self.diagnostics);
                         self.resolve_ty(&def.val).map(|x| Ty::Array(ArrayTy {
                             elem_ty: Box::new(x),
+                            len,
                         }))
                     }
                 }
             }
+            AstNode::Closure(closure) => {
+                // captures have to be collected before the args are defined as they may shadow outer locals
+                let captures = analyze_captures(&self.env, closure);
+                let args = closure.args.iter().map(|(_, ty)| match ty {
+                    Some(ty) => {
+                        self.check_ty_names(ty);
+                        self.lower_ty(ty)
+                    }
+                    None => self.infcx.fresh(InferKind::Ty, Origin("closure argument".to_string())),
+                }).collect::<Vec<_>>();
+
+                self.push_scope();
+                for ((name, _), ty) in closure.args.iter().zip(args.iter()) {
+                    self.env.define_var(name.clone(), ty.clone());
+                }
+                let body_ty = tyck_node(self, &closure.body);
+                self.pop_scope();
+
+                let ret = match &closure.ret {
+                    Some(ret) => {
+                        self.check_ty_names(ret);
+                        let ret = self.lower_ty(ret);
+                        self.unify(&ret, &body_ty, Origin("return type of the closure".to_string()));
+                        ret
+                    }
+                    None => body_ty,
+                };
+
+                Some(Ty::Closure(ClosureTy {
+                    capture: closure.capture,
+                    args: args.into_boxed_slice(),
+                    ret: Box::new(ret),
+                    captures: captures.into_boxed_slice(),
+                }))
+            }
+        }
+    }
+
+    /// creates the types of the generic params of `func` for one of its calls, they are either
+    /// given explicitly (`f::<u32>()`) or fresh variables which get inferred from the args,
+    /// the trait bounds of the params get checked once they are known
+    fn instantiate_generics(&mut self, call: &CallExprNode, func: &FunctionHeader) -> HashMap<String, Ty> {
+        let param_names = func.generics.iter().filter_map(|generic| match generic {
+            Generic::Type(ty) => Some(ty.name.clone()),
+            Generic::Constant(_) | Generic::Lifetime(_) => None,
+        }).collect::<Vec<_>>();
+        let explicit = call.generics.iter().filter_map(|generic| match generic {
+            TyOrConstVal::Ty(ty) => Some(ty),
+            // FIXME: support const generic args
+            TyOrConstVal::ConstVal(_) | TyOrConstVal::Lifetime(_) => None,
+        }).collect::<Vec<_>>();
+        // the args are only used if all of them are given
+        let use_explicit = explicit.len() == param_names.len();
+        if !explicit.is_empty() && !use_explicit {
+            let mut diagnostic = self.diagnostics.diagnostic(call.callee.clone());
+            diagnostic.error(format!("function `{}` takes {} but {} supplied", call.callee, count(param_names.len(), "generic argument"), was_were(explicit.len())));
+            diagnostic.build();
+        }
+
+        let mut params = HashMap::new();
+        for (idx, name) in param_names.iter().enumerate() {
+            let ty = match explicit.get(idx).filter(|_| use_explicit) {
+                Some(explicit) => {
+                    self.check_ty_names(explicit);
+                    self.lower_ty(explicit)
+                }
+                None => self.infcx.fresh(InferKind::Ty, Origin(format!("type parameter `{}` of `{}`", name, call.callee))),
+            };
+            params.insert(name.clone(), ty);
+        }
+
+        let bounds = collect_bounds(&func.generics, &func.where_clause);
+        for name in &param_names {
+            for bound in bounds.get(name).into_iter().flatten() {
+                self.pending_bounds.push(PendingBound {
+                    ty: params[name].clone(),
+                    bound: bound.clone(),
+                    origin: Origin(format!("bound `{}: {}` of `{}`", name, bound.to_string(), call.callee)),
+                });
+            }
+        }
+        params
+    }
+
+    /// lowers a type from the signature of the called function `func` whose generic params aren't in scope otherwise
+    fn lower_callee_ty(&mut self, func: &FunctionHeader, ty: &ast::Ty) -> Ty {
+        let outer_generics = self.push_generics(&func.generics, &[]);
+        let ty = self.lower_ty(ty);
+        self.restore_generics(outer_generics);
+        ty
+    }
+
+    fn check_arg_count(&mut self, call: &CallExprNode, expected: usize) {
+        if call.args.len() != expected {
+            let mut diagnostic = self.diagnostics.diagnostic(call.callee.clone());
+            diagnostic.error(format!("`{}` takes {} but {} supplied", call.callee, count(expected, "argument"), was_were(call.args.len())));
+            diagnostic.build();
         }
     }
 
+    /// checks the fields of a struct constructor against the struct's definition and returns the type of the constructed struct
+    fn check_struct_fields(&mut self, constructor: &StructConstructor) -> Ty {
+        let (def, struct_ty) = match self.env.resolve_adt(&DEFAULT_PATH.to_string(), &constructor.name) {
+            Some((Adt::Struct(def), Ty::Struct(struct_ty))) => (def.clone(), struct_ty.clone()),
+            _ => return Ty::Error,
+        };
+        // every constructor gets its own set of variables for the struct's generic params
+        let args = def.generics.iter().filter_map(|generic| match generic {
+            Generic::Type(ty) => Some((ty.name.clone(), self.infcx.fresh(InferKind::Ty, Origin(format!("type parameter `{}` of `{}`", ty.name, def.name))))),
+            Generic::Constant(_) | Generic::Lifetime(_) => None,
+        }).collect::<Vec<_>>();
+        let struct_ty = Ty::Struct(struct_ty.with_args(args.iter().map(|(_, arg)| arg.clone()).collect()));
+        let params = args.into_iter().collect::<HashMap<_, _>>();
+        let outer_generics = self.push_generics(&def.generics, &[]);
+        let field_tys = def.fields.iter().map(|field| instantiate(&self.lower_ty(&field.ty), &params)).collect::<Vec<_>>();
+        self.restore_generics(outer_generics);
+        for (name, bounds) in collect_bounds(&def.generics, &def.where_clause) {
+            for bound in bounds {
+                if let Some(ty) = params.get(&name) {
+                    self.pending_bounds.push(PendingBound {
+                        ty: ty.clone(),
+                        origin: Origin(format!("bound `{}: {}` of `{}`", name, bound.to_string(), def.name)),
+                        bound,
+                    });
+                }
+            }
+        }
+
+        let mut assigned: Vec<&String> = vec![];
+        for (name, val) in &*constructor.fields {
+            let val_ty = tyck_node(self, val);
+            if assigned.contains(&name) {
+                let mut diagnostic = self.diagnostics.diagnostic(format!("{} {{ {}: .. }}", def.name, name));
+                diagnostic.error(format!("field `{}` specified more than once", name));
+                diagnostic.build();
+                continue;
+            }
+            assigned.push(name);
+            match def.fields.iter().position(|field| &field.name == name) {
+                Some(idx) => self.coerce(&field_tys[idx], &val_ty, Origin(format!("field `{}` of `{}`", name, def.name))),
+                None => {
+                    // only suggest fields which weren't assigned already
+                    let candidates = def.fields.iter().map(|field| field.name.as_str()).filter(|field| !constructor.fields.iter().any(|(name, _)| name == field));
+                    let suggestion = find_best_match(name, candidates);
+                    let mut diagnostic = self.diagnostics.diagnostic(format!("{} {{ {}: .. }}", def.name, name));
+                    diagnostic.error(format!("struct `{}` has no field named `{}`", def.name, name));
+                    if let Some(suggestion) = suggestion {
+                        diagnostic.note(format!("did you mean `{}`?", suggestion));
+                    }
+                    diagnostic.build();
+                }
+            }
+        }
+
+        if let Some(base) = &constructor.base {
+            // the remaining fields get taken from the base which has to be of the same type
+            let base_ty = tyck_node(self, base);
+            self.unify(&struct_ty, &base_ty, Origin(format!("functional update of `{}`", def.name)));
+            return struct_ty;
+        }
+        let missing = def.fields.iter().filter(|field| !assigned.contains(&&field.name)).map(|field| format!("`{}`", field.name)).collect::<Vec<_>>();
+        if !missing.is_empty() {
+            let mut diagnostic = self.diagnostics.diagnostic(format!("{} {{ .. }}", def.name));
+            diagnostic.error(format!("missing {} {} in initializer of `{}`", if missing.len() == 1 { "field" } else { "fields" }, missing.join(", "), def.name));
+            diagnostic.build();
+        }
+        struct_ty
+    }
+
+    /// reports the use of an unknown name and suggests a similar one out of `candidates` (if there is any)
+    fn report_unknown_name(&mut self, kind: &str, name: &String, candidates: Vec<String>) {
+        let suggestion = find_best_match(name, candidates.iter().map(|candidate| candidate.as_str()));
+        let mut diagnostic = self.diagnostics.diagnostic(name.clone());
+        diagnostic.error(format!("cannot find {} `{}` in this scope", kind, name));
+        if let Some(suggestion) = suggestion {
+            diagnostic.note(format!("a {} with a similar name exists: `{}`", kind, suggestion));
+        }
+        diagnostic.build();
+    }
+
     pub fn resolve_named_ty(&self, path: &String, name: &String) -> Option<&Ty> {
         self.env.adts_by_path.get(path).map(|x| x.get(name).map(|x| &x.1)).flatten()
     }
@@ -139,68 +1017,145 @@ impl TyCtx {
     pub fn insert_item_glob(&mut self, item: &ItemKind) {
         match item {
             ItemKind::StaticVal(val) => {
-                self.env.define_static_var(val.left().clone(), crate::tyck::Ty::from_ast_ty(val.ty.clone().kind, None));
+                self.eval_array_lens(&val.ty);
+                self.env.define_static_var(val.left().clone(), self.lower_ty(&val.ty));
             }
             ItemKind::ConstVal(val) => {
-                let mut ty = crate::tyck::Ty::from_ast_ty(val.ty.clone().kind, None);
-                if let AstNode::BinaryExpr(expr) = &val.val {
-                    println!("try resolve from: {:?}", &expr.rhs);
-                    if let Some(helper) = EMPTY.resolve_ty(&expr.rhs) {
-                        println!("helper: {:?}", helper);
-                        // FIXME: check if `ty` and `helper` are similar!
-                        ty = helper;
-                    }
-                } else {
-                    panic!("Expected to find a BinaryExpr!");
-                }
-                println!("resolved const: {:?}", ty);
-                self.env.define_static_var(val.left().clone(), ty);
+                // the value gets checked against the annotated type once the const itself is checked
+                self.eval_array_lens(&val.ty);
+                self.env.define_static_var(val.left().clone(), self.lower_ty(&val.ty));
             }
             ItemKind::FunctionDef(func) => {
                 self.env.define_static_func(func.header.name.clone(), Box::into_inner(func.clone()));
             }
             ItemKind::StructDef(def) => {
-                println!("define adt!");
-                self.env.define_adt(DEFAULT_PATH.to_string(), def.name.clone(), Adt::Struct(def.clone()));
+                self.traits.insert_local_adt(def);
+                if let Some(lang_item) = lang_item(&def.attrs) {
+                    self.traits.insert_lang_item(lang_item.clone(), def.name.clone());
+                }
+                let mut def = def.clone();
+                for field in def.fields.iter_mut() {
+                    self.eval_array_lens(&field.ty);
+                    field.ty = self.normalize_ast_ty(&field.ty);
+                }
+                self.env.define_adt(DEFAULT_PATH.to_string(), def.name.clone(), Adt::Struct(def));
+            }
+            ItemKind::TraitDef(def) => {
+                if let Some(lang_item) = lang_item(&def.attrs) {
+                    self.traits.insert_lang_item(lang_item.clone(), def.name.clone());
+                }
+                self.traits.insert_trait_def(def);
+                self.env.define_trait(DEFAULT_PATH.to_string(), def.clone());
             }
-            ItemKind::TraitDef(_) => {}
             ItemKind::StructImpl(s_impl) => {
-                // self.env.define_impl(DEFAULT_PATH, s_impl..name.clone(), Adt::Struct(def.clone()));
-                // FIXME: finish this!
+                self.env.define_impl(DEFAULT_PATH.to_string(), s_impl.ty.kind.simple_ty_name(), s_impl.clone());
+                // the solver compares the lengths of arrays, so `[u8; 1 + 1]` matches the impl for `[u8; 2]`
+                let mut s_impl = s_impl.clone();
+                s_impl.ty = self.eval_array_lens(&s_impl.ty);
+                self.traits.insert_adt_impl(&s_impl);
+                self.traits.insert_assoc_items(&s_impl);
+            }
+            ItemKind::TyAlias(alias) => {
+                self.env.define_ty_alias(DEFAULT_PATH.to_string(), alias.clone());
             }
         }
     }
 
+    /// reports overlapping impls and impls which violate the orphan rule, all items have to be inserted beforehand
+    pub fn check_coherence(&mut self) {
+        for error in self.traits.check_coherence() {
+            match error {
+                CoherenceError::Overlap { tait, ty, first: (first, first_span), second: (second, second_span) } => {
+                    // the error points at the second impl and a note at the first one
+                    let (input, span) = source_line(&self.source, second_span, &second);
+                    let mut diagnostic = self.diagnostics.diagnostic(input);
+                    diagnostic.error_spanned(format!("conflicting implementations of trait `{}` for type `{}`", tait, ty.to_string()), span);
+                    let note = format!("first implementation: `{}`", first);
+                    if self.source.contains(first_span) {
+                        let (input, span) = self.source.line(first_span);
+                        diagnostic.note_spanned(note, input, span);
+                    } else {
+                        diagnostic.note(note);
+                    }
+                    diagnostic.build();
+                }
+                CoherenceError::Orphan { imp: (imp, imp_span), tait, ty } => {
+                    let (input, span) = source_line(&self.source, imp_span, &imp);
+                    let mut diagnostic = self.diagnostics.diagnostic(input);
+                    diagnostic.error_spanned("only traits defined in the current crate can be implemented for types defined outside of it".to_string(), span);
+                    diagnostic.note(format!("neither the trait `{}` nor the type `{}` is defined in the current crate", tait, ty.to_string()));
+                    diagnostic.build();
+                }
+            }
+        }
+        self.report_solver_errors();
+    }
+
+    /// infers which functions without a `const` or `runtime` modifier can be called in constants,
+    /// all items have to be inserted beforehand
+    pub fn infer_constness(&mut self) {
+        infer_constness(self);
+    }
+
+    /// reports the cycles and overflows the trait solver ran into while proving bounds
+    pub(crate) fn report_solver_errors(&mut self) {
+        let mut reported = vec![];
+        for error in self.traits.take_errors() {
+            // the same goal may have been evaluated multiple times
+            if reported.contains(&error) {
+                continue;
+            }
+            match &error {
+                SolverError::Cycle { goal, cycle } => {
+                    let mut diagnostic = self.diagnostics.diagnostic(goal.clone());
+                    diagnostic.error(format!("cycle detected when proving `{}`", goal));
+                    for step in cycle.iter().skip(1) {
+                        diagnostic.note(format!("...which requires proving `{}`...", step));
+                    }
+                    diagnostic.note(format!("...which again requires proving `{}`, completing the cycle", goal));
+                    diagnostic.build();
+                }
+                SolverError::Overflow { goal, limit } => {
+                    let mut diagnostic = self.diagnostics.diagnostic(goal.clone());
+                    diagnostic.error(format!("overflow evaluating the requirement `{}`", goal));
+                    diagnostic.note(format!("consider increasing the recursion limit by adding a `#![recursion_limit = \"{}\"]` attribute to the crate", limit * 2));
+                    diagnostic.build();
+                }
+            }
+            reported.push(error);
+        }
+    }
+
     pub fn insert_item_local(&mut self, item: &ItemKind) {
         match item {
             ItemKind::StaticVal(val) => {
-                self.env.define_var(val.left().clone(), crate::tyck::Ty::from_ast_ty(val.ty.clone().kind, None));
+                self.env.define_var(val.left().clone(), self.lower_ty(&val.ty));
             }
             ItemKind::ConstVal(val) => {
-                let mut ty = crate::tyck::Ty::from_ast_ty(val.ty.clone().kind, None);
-                if let AstNode::BinaryExpr(expr) = &val.val {
-                    println!("try resolve from: {:?}", &expr.rhs);
-                    if let Some(helper) = EMPTY.resolve_ty(&expr.rhs) {
-                        println!("helper: {:?}", helper);
-                        // FIXME: check if `ty` and `helper` are similar!
-                        ty = helper;
-                    }
-                } else {
-                    panic!("Expected to find a BinaryExpr!");
-                }
-                println!("resolved const: {:?}", ty);
-                self.env.define_var(val.left().clone(), ty);
+                // the value gets checked against the annotated type once the const itself is checked
+                self.env.define_var(val.left().clone(), self.lower_ty(&val.ty));
             }
             ItemKind::FunctionDef(func) => {
                 self.env.define_func(func.header.name.clone(), Box::into_inner(func.clone()));
             }
-            ItemKind::StructDef(_) => {
-                panic!("You can't define structs in locals");
+            ItemKind::StructDef(def) => {
+                let mut diagnostic = self.diagnostics.diagnostic(format!("struct {}", def.name));
+                diagnostic.error(format!("the struct `{}` can't be defined inside of a function", def.name));
+                diagnostic.note("structs are only supported at the top level for now".to_string());
+                diagnostic.build();
             }
             ItemKind::TraitDef(_) => {}
             ItemKind::StructImpl(s_impl) => {
-                // self.env.define_impl(DEFAULT_PATH, s_impl..name.clone(), Adt::Struct(def.clone()));
-                // FIXME: finish this!
+                // the trait solver and coherence only know about the impls at the top level
+                let (input, span) = source_line(&self.source, s_impl.span, &format!("impl {}", s_impl.ty.to_string()));
+                let mut diagnostic = self.diagnostics.diagnostic(input);
+                diagnostic.error_spanned("impls can't be defined inside of a function".to_string(), span);
+                diagnostic.note("impls are only supported at the top level for now".to_string());
+                diagnostic.build();
+            }
+            ItemKind::TyAlias(alias) => {
+                // FIXME: scope local aliases
+                self.env.define_ty_alias(DEFAULT_PATH.to_string(), alias.clone());
             }
         }
     }
@@ -209,116 +1164,231 @@ impl TyCtx {
 
 pub fn tyck_node(tyck_ctx: &mut TyCtx, node: &AstNode) -> Ty {
     if let Some(ty) = tyck_ctx.resolve_ty(node) {
-        if let Ty::Unresolved(_unresolved) = &ty {
-            panic!("Can't properly resolve type: {:?}", ty);
-        }
+        // names which are still unresolved at this point are generic params,
+        // unknown names were already reported when their types got lowered
         ty
     } else {
-        panic!("Can't resolve type!");
+        let mut diagnostic = tyck_ctx.diagnostics.diagnostic(node.to_string());
+        diagnostic.error("can't resolve the type of this expression".to_string());
+        diagnostic.build();
+        Ty::Error
     }
 }
 
 pub fn tyck_item(tyck_ctx: &mut TyCtx, item: &ItemKind) {
+    check_item_lifetimes(item, &mut tyck_ctx.diagnostics);
     match item {
         ItemKind::StaticVal(val) => {
-            if let Some(resolved) = tyck_ctx.resolve_ty(&val.val) {
-                let resolved = if let Ty::Unresolved(ref ty) = resolved {
-                    if let Some(val) = tyck_ctx.resolve_named_ty(&DEFAULT_PATH.to_string(), &ty.name) {
-                        if let Ty::Unresolved(ty) = val {
-                            panic!("Can't properly resolve: {:?}", ty);
-                        } else {
-                            val.clone()
-                        }
-                    } else {
-                        panic!("Can't resolve ty: {:?}", resolved);
-                    }
-                } else {
-                    resolved
-                };
-                println!("resolved ty: {:?}", resolved);
-            } else {
-                panic!("Outer unresolved!");
-            }
+            tyck_global(tyck_ctx, &val.ty, &val.val);
         }
         ItemKind::ConstVal(val) => {
-            if let Some(resolved) = tyck_ctx.resolve_ty(&val.val) {
-                let resolved = if let Ty::Unresolved(ref ty) = resolved {
-                    if let Some(val) = tyck_ctx.resolve_named_ty(&DEFAULT_PATH.to_string(), &ty.name) {
-                        if let Ty::Unresolved(ty) = val {
-                            panic!("Can't properly resolve: {:?}", ty);
-                        } else {
-                            val.clone()
-                        }
-                    } else {
-                        panic!("Can't resolve ty: {:?}", resolved);
-                    }
-                } else {
-                    resolved
-                };
-                println!("resolved ty: {:?}", resolved);
-            } else {
-                panic!("Outer unresolved!");
-            }
+            tyck_global(tyck_ctx, &val.ty, &val.val);
+            tyck_ctx.consts.eval_const(val.left(), &mut tyck_ctx.diagnostics);
         }
         ItemKind::FunctionDef(func) => {
-            // FIXME: typeck all body statements (and also push a new scope on the scope stack)
-            tyck_ctx.push_scope();
-            for body in &*func.body.stmts {
-                match body {
-                    StmtKind::Item(item) => {
-                        tyck_ctx.insert_item_local(item);
-                    }
-                    StmtKind::LocalAssign(_) => {}
-                    StmtKind::Expr(_) => {}
-                    StmtKind::Semi(_) => {}
-                    StmtKind::Empty => {}
+            tyck_fn(tyck_ctx, &func.attrs, &func.header, &func.body);
+            check_const_fn(tyck_ctx, func);
+        }
+        ItemKind::StructDef(def) => {
+            let outer_generics = tyck_ctx.push_generics(&def.generics, &def.where_clause);
+            tyck_ctx.check_bound_args(&def.generics, &def.where_clause);
+            for field in &*def.fields {
+                tyck_ctx.check_ty_names(&field.ty);
+            }
+            tyck_ctx.restore_generics(outer_generics);
+        }
+        ItemKind::TraitDef(def) => {
+            // FIXME: typeck the trait's signatures!
+            let outer_lints = tyck_ctx.allow_lints(&def.attrs);
+            let outer_generics = tyck_ctx.push_generics(&def.generics, &def.where_clause);
+            tyck_ctx.check_bound_args(&def.generics, &def.where_clause);
+            tyck_ctx.push_self_param(vec![ast::Ty {
+                kind: TyKind::Owned(Box::new(ast::OwnedTy {
+                    name: def.name.clone(),
+                    generics: Box::new([]),
+                })),
+            }]);
+            for method in &*def.methods {
+                if let Some(default) = &method.default {
+                    tyck_fn(tyck_ctx, &method.attrs, &method.header, default);
                 }
             }
-            for body in &*func.body.stmts {
-                match body {
-                    StmtKind::Item(item) => {
-                        tyck_item(tyck_ctx, item);
-                    }
-                    StmtKind::LocalAssign(local) => {
-                        match local {
-                            LocalAssign::Assign(assign) => {
-                                tyck_node(tyck_ctx, &assign.val);
-                            }
-                            LocalAssign::DecAssign(assign) => {
-                                tyck_node(tyck_ctx, &assign.val.val);
-                            }
-                        }
-                        tyck_ctx.push_local(local);
-                    }
-                    StmtKind::Expr(expr) => {
-                        tyck_node(tyck_ctx, expr);
+            tyck_ctx.restore_generics(outer_generics);
+            tyck_ctx.restore_lints(outer_lints);
+        }
+        ItemKind::StructImpl(s_impl) => {
+            if find_attr(&s_impl.attrs, DERIVED_ATTR).is_some() {
+                check_derived_impl(tyck_ctx, s_impl);
+            } else if s_impl.negative {
+                check_negative_impl(tyck_ctx, s_impl);
+            } else if s_impl.impl_trait.is_some() {
+                check_trait_impl(tyck_ctx, s_impl);
+            }
+            let outer_lints = tyck_ctx.allow_lints(&s_impl.attrs);
+            let outer_generics = tyck_ctx.push_generics(&s_impl.generics, &s_impl.where_clause);
+            tyck_ctx.check_bound_args(&s_impl.generics, &s_impl.where_clause);
+            tyck_ctx.check_ty_names(&s_impl.ty);
+            if let Some(impl_trait) = &s_impl.impl_trait {
+                tyck_ctx.check_generic_args(impl_trait);
+            }
+            tyck_ctx.push_self_param(s_impl.impl_trait.iter().cloned().collect());
+            tyck_ctx.push_scope();
+            for item in &*s_impl.methods {
+                tyck_ctx.insert_item_local(item);
+            }
+            for item in &*s_impl.methods {
+                match item {
+                    // the lifetimes of methods were already checked together with the impl's ones
+                    ItemKind::FunctionDef(func) => {
+                        tyck_fn(tyck_ctx, &func.attrs, &func.header, &func.body);
+                        check_const_fn(tyck_ctx, func);
                     }
-                    StmtKind::Semi(semi) => {
-                        tyck_node(tyck_ctx, semi);
+                    // associated consts aren't known to the evaluator by name, so they get evaluated in place
+                    ItemKind::ConstVal(val) => {
+                        tyck_global(tyck_ctx, &val.ty, &val.val);
+                        let value = match &val.val {
+                            AstNode::BinaryExpr(assign) => &assign.rhs,
+                            value => value,
+                        };
+                        tyck_ctx.consts.eval(value, Some(&val.ty), val.val.to_string(), &mut tyck_ctx.diagnostics);
                     }
-                    StmtKind::Empty => {}
+                    item => tyck_item(tyck_ctx, item),
                 }
             }
             tyck_ctx.pop_scope();
+            tyck_ctx.restore_generics(outer_generics);
+            tyck_ctx.restore_lints(outer_lints);
         }
-        ItemKind::StructDef(def) => {
-            for field in &*def.fields {
-                if let Some(ty) = tyck_ctx.resolve_named_ty(&DEFAULT_PATH.to_string(), &field.name) {
-                    if let Ty::Unresolved(unresolved) = ty {
-                        panic!("Can't resolve type Inner: {:?}", unresolved);
-                    }
-                } else {
-                    panic!("Can't resolve type Outer!");
-                }
+        ItemKind::TyAlias(alias) => {
+            let outer_generics = tyck_ctx.push_generics(&alias.generics, &[]);
+            tyck_ctx.check_ty_names(&alias.ty);
+            tyck_ctx.restore_generics(outer_generics);
+            // this makes sure cycles between aliases get detected even if they aren't used anywhere
+            if let Err(error @ NormalizeError::RecursionLimit(_)) = tyck_ctx.try_normalize_ast_ty(&alias.ty) {
+                tyck_ctx.report_normalize_error(&alias.ty, error);
             }
         }
-        ItemKind::TraitDef(_) => {
-            // FIXME: typeck the trait!
+    }
+    tyck_ctx.report_solver_errors();
+}
+
+/// the line of `source` which `span` points into together with `span` relative to it,
+/// code which isn't part of the source (like derived impls) is shown as `fallback` instead
+fn source_line(source: &SourceFile, span: Span, fallback: &str) -> (String, Span) {
+    if source.contains(span) {
+        source.line(span)
+    } else {
+        (fallback.to_string(), Span::NONE)
+    }
+}
+
+/// adds the reasons why the goal of `proof` doesn't hold to `diagnostic`, starting with the obligations of the impls
+/// which could have been used, e.g. "`Civilization<X>` does not implement `CivBase` because `X: Send` is not satisfied, ..."
+fn note_proof_tree(diagnostic: &mut DiagnosticSubBuilder, proof: &ProofTree, source: &SourceFile) {
+    for candidate in &proof.candidates {
+        // the impls written in the source are referred to by their location as well
+        let required_by = if source.contains(candidate.span) {
+            format!("{} at {}", candidate.required_by, source.location(candidate.span))
+        } else {
+            candidate.required_by.clone()
+        };
+        for failed in &candidate.failed {
+            diagnostic.note(format!("`{}` does not implement `{}` because `{}: {}` is not satisfied, required by {}",
+                                    proof.ty, proof.tait, failed.ty, failed.tait, required_by));
+            note_proof_tree(diagnostic, failed, source);
+        }
+    }
+    if let Some(negative) = &proof.negative {
+        diagnostic.note(format!("`{}` explicitly opts out of `{}` with `{}`", proof.ty, proof.tait, negative));
+    }
+    if proof.candidates.is_empty() {
+        for near_miss in &proof.near_misses {
+            diagnostic.note(format!("the impl `{}` exists but doesn't apply to `{}`", near_miss, proof.ty));
+        }
+    }
+}
+
+/// the evaluated length of an array type as it's written, e.g. the `2` in `[u8; 2]`
+fn len_literal(len: usize) -> AstNode {
+    AstNode::Number(NumberLit { val: NumberType::USize(len), span: Span::NONE })
+}
+
+/// formats `amount` together with `noun` in singular or plural, e.g. "1 argument" or "2 arguments"
+fn count(amount: usize, noun: &str) -> String {
+    if amount == 1 {
+        format!("{} {}", amount, noun)
+    } else {
+        format!("{} {}s", amount, noun)
+    }
+}
+
+fn was_were(amount: usize) -> String {
+    if amount == 1 {
+        format!("{} was", amount)
+    } else {
+        format!("{} were", amount)
+    }
+}
+
+/// checks the value of a static or const against its annotated type `ty`,
+/// `val` is the assignment `NAME = value`
+fn tyck_global(tyck_ctx: &mut TyCtx, ty: &ast::Ty, val: &AstNode) {
+    tyck_ctx.check_ty_names(ty);
+    let inference_start = tyck_ctx.infcx.num_vars();
+    tyck_node(tyck_ctx, val);
+    tyck_ctx.finish_inference(inference_start);
+}
+
+fn tyck_fn(tyck_ctx: &mut TyCtx, attrs: &[Attribute], header: &FunctionHeader, body: &Block) {
+    let outer_lints = tyck_ctx.allow_lints(attrs);
+    let inference_start = tyck_ctx.infcx.num_vars();
+    // the type of the trailing expression which gets returned from the function
+    let mut body_ty = Ty::Empty;
+    let outer_generics = tyck_ctx.push_generics(&header.generics, &header.where_clause);
+    tyck_ctx.check_bound_args(&header.generics, &header.where_clause);
+    tyck_ctx.push_scope();
+    for (name, ty) in &*header.args {
+        tyck_ctx.check_ty_names(ty);
+        let ty = tyck_ctx.lower_ty(ty);
+        tyck_ctx.env.define_var(name.clone(), ty);
+    }
+    for stmt in &*body.stmts {
+        match stmt {
+            StmtKind::Item(item) => {
+                tyck_ctx.insert_item_local(item);
+            }
+            StmtKind::LocalAssign(_) => {}
+            StmtKind::Expr(_) => {}
+            StmtKind::Semi(_) => {}
+            StmtKind::Empty => {}
         }
-        ItemKind::StructImpl(_s_impl) => {
-            // FIXME: typeck all body statements
+    }
+    for stmt in &*body.stmts {
+        match stmt {
+            StmtKind::Item(item) => {
+                tyck_item(tyck_ctx, item);
+            }
+            StmtKind::LocalAssign(local) => {
+                tyck_ctx.push_local(local);
+            }
+            StmtKind::Expr(expr) => {
+                body_ty = tyck_node(tyck_ctx, expr);
+            }
+            StmtKind::Semi(semi) => {
+                tyck_node(tyck_ctx, semi);
+            }
+            StmtKind::Empty => {}
         }
     }
+    if let Some(ret) = &header.ret {
+        tyck_ctx.check_ty_names(ret);
+    }
+    let ret = header.ret.as_ref().map_or(Ty::Empty, |ret| tyck_ctx.lower_ty(ret));
+    tyck_ctx.coerce(&ret, &body_ty, Origin(format!("return type of `{}`", header.name)));
+    tyck_ctx.finish_inference(inference_start);
+    tyck_ctx.pop_scope();
+    tyck_ctx.restore_generics(outer_generics);
+    tyck_ctx.restore_lints(outer_lints);
 }
 
 pub struct Environment {
@@ -328,6 +1398,8 @@ pub struct Environment {
     adts_by_path: HashMap<String, HashMap<String, (Adt, Ty)>>,
     adt_impls_by_path: HashMap<String, HashMap<String, Vec<AdtImpl>>>,
     funcs_by_path: HashMap<String, HashMap<String, FunctionNode>>,
+    ty_aliases_by_path: HashMap<String, HashMap<String, TyAlias>>,
+    traits_by_path: HashMap<String, HashMap<String, TraitDef>>,
 }
 
 pub enum Adt {
@@ -335,16 +1407,6 @@ pub enum Adt {
     // Enum(), // TODO: support this!
 }
 
-impl Adt {
-
-    pub fn to_scaffolding(&self) -> TyScaffolding {
-        match self {
-            Adt::Struct(_) => TyScaffolding::Struct,
-        }
-    }
-
-}
-
 #[derive(PartialEq)]
 pub enum Dest {
     Static(Ty),
@@ -366,6 +1428,8 @@ impl Environment {
             adts_by_path: Default::default(),
             adt_impls_by_path: Default::default(),
             funcs_by_path: Default::default(),
+            ty_aliases_by_path: Default::default(),
+            traits_by_path: Default::default(),
         }
     }
 
@@ -396,6 +1460,26 @@ impl Environment {
         return None;
     }
 
+    /// the names of all vars which are currently in scope
+    pub fn var_names(&self) -> Vec<String> {
+        self.scopes.iter().flat_map(|scope| scope.vars.keys().cloned()).collect()
+    }
+
+    /// the names of all functions which are currently in scope
+    pub fn func_names(&self) -> Vec<String> {
+        self.scopes.iter().flat_map(|scope| scope.funcs.keys().cloned()).collect()
+    }
+
+    /// checks whether `var` refers to a local (as opposed to a static) in any of the current scopes
+    pub fn is_local_var(&self, var: &String) -> bool {
+        for scope in self.scopes.iter().rev() {
+            if let Some(dest) = scope.vars.get(var) {
+                return matches!(dest, Dest::Local(_));
+            }
+        }
+        false
+    }
+
     pub fn define_var(&mut self, var: String, ty: Ty) -> bool {
         let mut scope = self.scopes.last_mut().unwrap();
 
@@ -445,23 +1529,45 @@ impl Environment {
             Adt::Struct(s_adt) => Ty::Struct(StructTy {
                 vis: s_adt.visibility.clone(),
                 name: s_adt.name.clone(),
+                generics: s_adt.generics.iter().filter_map(|generic| match generic {
+                    Generic::Type(ty) => Some(Ty::Unresolved(UnresolvedTy {
+                        name: ty.name.clone(),
+                        generics: Box::new([]),
+                    })),
+                    Generic::Constant(_) | Generic::Lifetime(_) => None,
+                }).collect(),
                 fields: {
                     let mut this = Vec::with_capacity(s_adt.fields.len());
                     for x in &*s_adt.fields {
                         this.push(StructField {
                             vis: x.visibility.clone(),
                             name: x.name.clone(),
-                            ty: Ty::from_ast_ty(x.ty.kind.clone(), Some(adt.to_scaffolding())),
+                            ty: Ty::from_ast_ty(x.ty.kind.clone()),
                         });
                     }
                     this.into_boxed_slice()
                 },
             }),
         };
-        println!("gen ty: {:?}", ty);
         self.adts_by_path.entry(path).or_insert_with(|| HashMap::new()).try_insert(name, (adt, ty)).is_ok()
     }
 
+    pub fn resolve_ty_alias(&self, path: &String, name: &String) -> Option<&TyAlias> {
+        self.ty_aliases_by_path.get(path).and_then(|aliases| aliases.get(name))
+    }
+
+    pub fn define_ty_alias(&mut self, path: String, alias: TyAlias) -> bool {
+        self.ty_aliases_by_path.entry(path).or_default().try_insert(alias.name.clone(), alias).is_ok()
+    }
+
+    pub fn resolve_trait(&self, path: &String, name: &String) -> Option<&TraitDef> {
+        self.traits_by_path.get(path).and_then(|traits| traits.get(name))
+    }
+
+    pub fn define_trait(&mut self, path: String, tait: TraitDef) -> bool {
+        self.traits_by_path.entry(path).or_default().try_insert(tait.name.clone(), tait).is_ok()
+    }
+
     pub fn resolve_impls(&self, path: &String, name: &String) -> Option<&Vec<AdtImpl>> {
         if let Some(path) = self.adt_impls_by_path.get(path) {
             if let Some(impls) = path.get(name) {
@@ -487,7 +1593,57 @@ pub enum Ty {
     Array(ArrayTy),
     Primitive(PrimitiveTy),
     Ref(RefTy),
+    FnPtr(FnPtrTy),
+    Closure(ClosureTy),
+    Dyn(DynTy),
     Unresolved(UnresolvedTy),
+    Infer(InferTy), // a type variable which gets resolved during inference
+    Error, // the type of anything erroneous, it's compatible with every other type to avoid follow-up errors
+}
+
+impl Display for Ty {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let raw = match self {
+            Ty::Empty => "()".to_string(),
+            Ty::Enum(enum_ty) => enum_ty.name.clone(),
+            Ty::Struct(strukt) if strukt.generics.is_empty() => strukt.name.clone(),
+            Ty::Struct(strukt) => format!("{}<{}>", strukt.name, strukt.generics.iter().map(|generic| generic.to_string()).collect::<Vec<_>>().join(", ")),
+            Ty::Union(union_ty) => union_ty.name.clone(),
+            Ty::Tuple(tuple) => format!("({})", tuple.fields.iter().map(|field| field.ty.to_string()).collect::<Vec<_>>().join(", ")),
+            Ty::Array(ArrayTy { elem_ty, len: Some(len) }) => format!("[{}; {}]", elem_ty, len),
+            Ty::Array(array) => format!("[{}]", array.elem_ty),
+            Ty::Primitive(primitive) => primitive.to_string(),
+            Ty::Ref(rf) => match rf.mutability {
+                Mutability::Mut => format!("&mut {}", rf.ty),
+                Mutability::Immut => format!("&{}", rf.ty),
+            },
+            Ty::FnPtr(_) | Ty::Closure(_) => {
+                let (args, ret) = self.fn_sig().unwrap();
+                let args = args.iter().map(|arg| arg.to_string()).collect::<Vec<_>>().join(", ");
+                let prefix = if matches!(self, Ty::Closure(_)) { "closure" } else { "fn" };
+                match ret {
+                    Ty::Empty => format!("{}({})", prefix, args),
+                    ret => format!("{}({}) -> {}", prefix, args, ret),
+                }
+            }
+            Ty::Dyn(dyn_ty) => format!("dyn {}", dyn_ty.tait.to_string()),
+            Ty::Unresolved(unresolved) => {
+                if unresolved.generics.is_empty() {
+                    unresolved.name.clone()
+                } else {
+                    let generics = unresolved.generics.iter().map(|generic| generic.to_string()).collect::<Vec<_>>();
+                    format!("{}<{}>", unresolved.name, generics.join(", "))
+                }
+            }
+            Ty::Error => "{error}".to_string(),
+            Ty::Infer(var) => match var.kind {
+                InferKind::Ty => "_".to_string(),
+                InferKind::Int => "{integer}".to_string(),
+                InferKind::Float => "{float}".to_string(),
+            },
+        };
+        f.write_str(&raw)
+    }
 }
 
 impl Ty {
@@ -496,6 +1652,17 @@ impl Ty {
         if self == other {
             return true;
         }
+        // FIXME: get rid of this once we have proper inference
+        if self.is_inferred() || other.is_inferred() {
+            return true;
+        }
+        if let (Ty::FnPtr(_) | Ty::Closure(_), Ty::FnPtr(_) | Ty::Closure(_)) = (self, other) {
+            let (args, ret) = self.fn_sig().unwrap();
+            let (other_args, other_ret) = other.fn_sig().unwrap();
+            return args.len() == other_args.len()
+                && args.iter().zip(other_args.iter()).all(|(arg, other)| arg.could_be(other))
+                && ret.could_be(other_ret);
+        }
         // FIXME: finish this!
         if let Ty::Unresolved(unresolved) = self {
             return match other {
@@ -515,7 +1682,7 @@ impl Ty {
                     str.push(')');
                     &unresolved.name == &str
                 },
-                Ty::Array(_) => todo!(),
+                Ty::Array(_) => false,
                 Ty::Primitive(prim) => &unresolved.name == &prim.to_string(),
                 Ty::Ref(rf) => {
                     let ret = unresolved.name.chars().next() == Some('&');
@@ -530,7 +1697,9 @@ impl Ty {
                     });
                     rf.ty.could_be(&middle)
                 },
+                Ty::FnPtr(_) | Ty::Closure(_) | Ty::Dyn(_) => false,
                 Ty::Unresolved(_) => false,
+                Ty::Infer(_) | Ty::Error => true,
             };
         }
 
@@ -552,7 +1721,7 @@ impl Ty {
                     str.push(')');
                     &unresolved.name == &str
                 },
-                Ty::Array(_) => todo!(),
+                Ty::Array(_) => false,
                 Ty::Primitive(prim) => &unresolved.name == &prim.to_string(),
                 Ty::Ref(rf) => {
                     let ret = unresolved.name.chars().next() == Some('&');
@@ -567,45 +1736,133 @@ impl Ty {
                     });
                     rf.ty.could_be(&middle)
                 },
+                Ty::FnPtr(_) | Ty::Closure(_) | Ty::Dyn(_) => false,
                 Ty::Unresolved(_) => false,
+                Ty::Infer(_) | Ty::Error => true,
             };
         }
 
         false // FIXME: is this correct?
     }
 
-    pub fn from_ast_ty(ast_ty: ast::TyKind, scaffolding: Option<TyScaffolding>) -> Self {
-        if scaffolding.is_some() {
-            println!("has scaffolding: {:?}", scaffolding);
-        }
+    pub fn from_ast_ty(ast_ty: ast::TyKind) -> Self {
         match ast_ty {
             TyKind::Ref(rf) => Ty::Ref(RefTy {
                 lt: rf.lt.map(|lt| Lifetime::from_ast_lt(lt)),
                 mutability: rf.mutability,
-                ty: Box::new(Self::from_ast_ty(rf.ty.kind, scaffolding)),
+                ty: Box::new(Self::from_ast_ty(rf.ty.kind)),
             }),
             TyKind::Array(array) => {
                 Ty::Array(ArrayTy {
-                    elem_ty: Box::new(Self::from_ast_ty(array.ty.kind, scaffolding)),
-                    // FIXME: add len field
+                    len: array.len(),
+                    elem_ty: Box::new(Self::from_ast_ty(array.ty.kind)),
                 })
             }
             TyKind::Owned(owned) => {
+                if owned.generics.is_empty() {
+                    if let Some(primitive) = PrimitiveTy::from_name(&owned.name) {
+                        return Ty::Primitive(primitive);
+                    }
+                }
                 Ty::Unresolved(UnresolvedTy {
                     name: owned.name,
                     generics: owned.generics,
                 })
             }
+            TyKind::Projection(projection) => {
+                // projections which couldn't be normalized are treated like the generic params they are based on
+                Ty::Unresolved(UnresolvedTy {
+                    name: TyKind::Projection(projection).to_string(),
+                    generics: Box::new([]),
+                })
+            }
+            TyKind::Dyn(dyn_ty) => Ty::Dyn(DynTy {
+                tait: dyn_ty.tait,
+            }),
+            TyKind::Func(func) => {
+                if func.kind != FuncTyKind::Ptr {
+                    // the function traits can only be used as bounds
+                    return Ty::Unresolved(UnresolvedTy {
+                        name: TyKind::Func(func).to_string(),
+                        generics: Box::new([]),
+                    });
+                }
+                Ty::FnPtr(FnPtrTy {
+                    args: func.args.into_vec().into_iter().map(|arg| Self::from_ast_ty(arg.kind)).collect(),
+                    ret: Box::new(func.ret.map_or(Ty::Empty, |ret| Self::from_ast_ty(ret.kind))),
+                })
+            }
         }
     }
 
-}
+    /// converts the type back into its ast form which is what the trait solver works with,
+    /// types which can't be written down in the source return `None`
+    pub fn to_ast_ty(&self) -> Option<ast::Ty> {
+        let owned = |name: String, generics: Box<[TyOrConstVal]>| Some(ast::Ty {
+            kind: TyKind::Owned(Box::new(ast::OwnedTy { name, generics })),
+        });
+        match self {
+            Ty::Enum(enum_ty) => owned(enum_ty.name.clone(), Box::new([])),
+            Ty::Struct(strukt) => owned(strukt.name.clone(), strukt.generics.iter().map(|generic| generic.to_ast_ty().map(TyOrConstVal::Ty)).collect::<Option<_>>()?),
+            Ty::Union(union_ty) => owned(union_ty.name.clone(), Box::new([])),
+            Ty::Primitive(primitive) => owned(primitive.to_string(), Box::new([])),
+            Ty::Unresolved(unresolved) => owned(unresolved.name.clone(), unresolved.generics.clone()),
+            Ty::Ref(rf) => Some(ast::Ty {
+                kind: TyKind::Ref(Box::new(ast::RefTy {
+                    lt: None,
+                    mutability: rf.mutability,
+                    ty: Box::new(rf.ty.to_ast_ty()?),
+                })),
+            }),
+            Ty::Array(array) => Some(ast::Ty {
+                kind: TyKind::Array(Box::new(ast::ArrayTy {
+                    ty: array.elem_ty.to_ast_ty()?,
+                    amount: array.len.map(len_literal),
+                })),
+            }),
+            Ty::Dyn(dyn_ty) => Some(ast::Ty {
+                kind: TyKind::Dyn(Box::new(ast::DynTy {
+                    tait: dyn_ty.tait.clone(),
+                })),
+            }),
+            Ty::Empty | Ty::Tuple(_) | Ty::FnPtr(_) | Ty::Closure(_) | Ty::Infer(_) | Ty::Error => None,
+        }
+    }
+
+    fn is_inferred(&self) -> bool {
+        matches!(self, Ty::Unresolved(unresolved) if unresolved.name == "_") || matches!(self, Ty::Infer(_))
+    }
+
+    /// returns the args and the return type of callable types
+    pub fn fn_sig(&self) -> Option<(&[Ty], &Ty)> {
+        match self {
+            Ty::FnPtr(func) => Some((&func.args, &func.ret)),
+            Ty::Closure(closure) => Some((&closure.args, &closure.ret)),
+            _ => None,
+        }
+    }
+
+    /// checks whether this type can be used where a `bound` like `FnMut(u32) -> u32` is expected
+    pub fn satisfies_fn_bound(&self, bound: &ast::FuncTy) -> bool {
+        let kind = match self {
+            // function pointers implement all of the function traits
+            Ty::FnPtr(_) => FuncTyKind::Fn,
+            Ty::Closure(closure) => closure.fn_kind(),
+            _ => return false,
+        };
+        let compatible_kind = match bound.kind {
+            FuncTyKind::Ptr => matches!(self, Ty::FnPtr(_)) || matches!(self, Ty::Closure(closure) if closure.captures.is_empty()),
+            FuncTyKind::Fn => kind == FuncTyKind::Fn,
+            FuncTyKind::FnMut => kind == FuncTyKind::Fn || kind == FuncTyKind::FnMut,
+            FuncTyKind::FnOnce => true,
+        };
+        let bound = Ty::FnPtr(FnPtrTy {
+            args: bound.args.iter().map(|arg| Ty::from_ast_ty(arg.kind.clone())).collect(),
+            ret: Box::new(bound.ret.clone().map_or(Ty::Empty, |ret| Ty::from_ast_ty(ret.kind))),
+        });
+        compatible_kind && self.could_be(&bound)
+    }
 
-#[derive(Copy, Clone, Debug, PartialEq)]
-pub enum TyScaffolding {
-    Struct,
-    Enum,
-    Union,
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -626,9 +1883,36 @@ pub struct EnumVariant {
 pub struct StructTy {
     pub vis: Visibility,
     pub name: String,
+    /// the args of the struct's type params, e.g. `[u8]` for `Wrapper<u8>`,
+    /// the struct's definition has the params themselves as its args
+    pub generics: Box<[Ty]>,
     pub fields: Box<[StructField]>,
 }
 
+impl StructTy {
+
+    /// replaces the type params of the struct's definition by `args`, missing args are treated as errors
+    pub fn with_args(&self, args: Vec<Ty>) -> StructTy {
+        let params = self.generics.iter().zip(args.into_iter().chain(std::iter::repeat(Ty::Error)))
+            .filter_map(|(param, arg)| match param {
+                Ty::Unresolved(param) => Some((param.name.clone(), arg)),
+                _ => None,
+            })
+            .collect::<HashMap<_, _>>();
+        StructTy {
+            vis: self.vis,
+            name: self.name.clone(),
+            generics: self.generics.iter().map(|param| instantiate(param, &params)).collect(),
+            fields: self.fields.iter().map(|field| StructField {
+                vis: field.vis,
+                name: field.name.clone(),
+                ty: instantiate(&field.ty, &params),
+            }).collect(),
+        }
+    }
+
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub struct StructField {
     pub vis: Visibility,
@@ -648,11 +1932,17 @@ pub struct TupleTy {
     pub fields: Vec<StructField>,
 }
 
+/// a trait object like `dyn Shape`
+#[derive(Debug, Clone, PartialEq)]
+pub struct DynTy {
+    pub tait: ast::Ty,
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub struct ArrayTy {
     pub elem_ty: Box<Ty>,
-    // pub len: Option<usize>,
-    // FIXME: how do we deal with length? do we store it in here or ignore it for now?
+    /// the evaluated length, `None` if it isn't known (e.g. because its evaluation failed)
+    pub len: Option<usize>,
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -666,8 +1956,36 @@ pub enum PrimitiveTy {
     SizedFloat(SizedFloatTy),
 }
 
+pub const PRIMITIVE_TY_NAMES: [&str; 17] = [
+    "bool", "char", "str", "usize", "isize", "u8", "u16", "u32", "u64", "u128", "i8", "i16", "i32", "i64", "i128", "f32", "f64",
+];
+
 impl PrimitiveTy {
 
+    pub fn from_name(name: &str) -> Option<Self> {
+        let sized_int = |unsigned, exp| Some(PrimitiveTy::SizedInt(SizedIntTy { unsigned, exp }));
+        match name {
+            "bool" => Some(PrimitiveTy::Bool),
+            "char" => Some(PrimitiveTy::Char),
+            "str" => Some(PrimitiveTy::Str),
+            "usize" => Some(PrimitiveTy::MachineSizedInt(MachineSizedIntTy { unsigned: true })),
+            "isize" => Some(PrimitiveTy::MachineSizedInt(MachineSizedIntTy { unsigned: false })),
+            "u8" => sized_int(true, 0),
+            "u16" => sized_int(true, 1),
+            "u32" => sized_int(true, 2),
+            "u64" => sized_int(true, 3),
+            "u128" => sized_int(true, 4),
+            "i8" => sized_int(false, 0),
+            "i16" => sized_int(false, 1),
+            "i32" => sized_int(false, 2),
+            "i64" => sized_int(false, 3),
+            "i128" => sized_int(false, 4),
+            "f32" => Some(PrimitiveTy::SizedFloat(SizedFloatTy { unsigned: false, exp: 0 })),
+            "f64" => Some(PrimitiveTy::SizedFloat(SizedFloatTy { unsigned: false, exp: 1 })),
+            _ => None,
+        }
+    }
+
     pub fn to_string(&self) -> String {
         match self {
             PrimitiveTy::Bool => "bool".to_string(),
@@ -702,24 +2020,24 @@ pub struct MachineSizedIntTy {
 #[derive(Debug, Clone, PartialEq)]
 pub struct SizedIntTy {
     pub unsigned: bool,
-    pub exp: usize, // the size of the ty as an exponent of 2, the bits can be calculated as f(x) = 8 * (2 << x)
+    pub exp: usize, // the size of the ty as an exponent of 2, the bits can be calculated as f(x) = 8 << x
 }
 
 impl SizedIntTy {
     pub fn bits(&self) -> usize {
-        8 * (2 << self.exp)
+        8 << self.exp
     }
 }
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct SizedFloatTy {
     pub unsigned: bool,
-    pub exp: usize, // the size of the ty as an exponent of 2, the bits can be calculated as f(x) = 32 * (2 << x)
+    pub exp: usize, // the size of the ty as an exponent of 2, the bits can be calculated as f(x) = 32 << x
 }
 
 impl SizedFloatTy {
     pub fn bits(&self) -> usize {
-        32 * (2 << self.exp)
+        32 << self.exp
     }
 }
 
@@ -749,8 +2067,48 @@ impl Lifetime {
 
 }
 
+#[derive(Debug, Clone, PartialEq)]
+pub struct FnPtrTy {
+    pub args: Box<[Ty]>,
+    pub ret: Box<Ty>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ClosureTy {
+    pub capture: CaptureBy,
+    pub args: Box<[Ty]>,
+    pub ret: Box<Ty>,
+    pub captures: Box<[Capture]>,
+}
+
+impl ClosureTy {
+
+    /// the most permissive function trait this closure implements
+    pub fn fn_kind(&self) -> FuncTyKind {
+        // FIXME: detect closures which move captured values out of their body (those are only FnOnce)
+        if self.captures.iter().any(|capture| capture.mutated) {
+            FuncTyKind::FnMut
+        } else {
+            FuncTyKind::Fn
+        }
+    }
+
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub struct UnresolvedTy {
     pub name: String,
     pub generics: Box<[TyOrConstVal]>,
 }
+
+impl UnresolvedTy {
+
+    /// a placeholder for types which should be inferred (`_`)
+    pub fn inferred() -> Self {
+        Self {
+            name: "_".to_string(),
+            generics: Box::new([]),
+        }
+    }
+
+}
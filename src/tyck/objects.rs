@@ -0,0 +1,200 @@
+use crate::parser::ast::{self, FunctionHeader, TyKind, TraitDef};
+use crate::parser::attrs::Mutability;
+use crate::traitsolver::mentions;
+use crate::tyck::infer::Origin;
+use crate::tyck::{DynTy, Ty, TyCtx, DEFAULT_PATH};
+use std::fmt::{Display, Formatter};
+
+// trait objects like `&dyn Shape` erase the type behind them, calls of the trait's methods are dispatched
+// through a vtable which is created whenever a concrete type gets cast to a trait object:
+// `fn draw(shape: &dyn Shape)` called with a `&Circle` requires `Circle: Shape` and results in the vtable of `Circle as Shape`.
+// as the type behind the object is unknown, only object safe traits can be made into objects, that is traits whose
+// methods can be called without knowing `Self`: every method needs a receiver (`self`, `&self` or `&mut self`),
+// mustn't be generic (there would be no single entry in the vtable) and mustn't mention `Self` anywhere else
+// see: https://doc.rust-lang.org/reference/items/traits.html#object-safety
+
+/// the reasons for which a trait can't be made into an object
+pub enum ObjectSafetyViolation {
+    /// `trait Shape: Sized`
+    SizedSelf,
+    /// `fn scale<T>(&self, factor: T)`
+    GenericMethod(String),
+    /// `fn new() -> u32`
+    NoReceiver(String),
+    /// `fn merge(&self, other: Self)` or `fn copy(&self) -> Self`
+    SelfInSignature(String),
+    /// `const SIDES: u32;`
+    AssocConst(String),
+}
+
+impl Display for ObjectSafetyViolation {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ObjectSafetyViolation::SizedSelf => f.write_str("it requires `Self: Sized`"),
+            ObjectSafetyViolation::GenericMethod(method) => write!(f, "method `{}` has generic type parameters", method),
+            ObjectSafetyViolation::NoReceiver(method) => write!(f, "associated function `{}` has no `self` parameter", method),
+            ObjectSafetyViolation::SelfInSignature(method) => write!(f, "method `{}` references the `Self` type outside of its receiver", method),
+            ObjectSafetyViolation::AssocConst(name) => write!(f, "it contains the associated const `{}`", name),
+        }
+    }
+}
+
+/// the layout of the vtable of `ty` cast to `dyn tait`, the backends call the methods through their index in `entries`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Vtable {
+    pub ty: String,
+    pub tait: String,
+    pub entries: Vec<VtableEntry>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum VtableEntry {
+    DropInPlace,
+    Size,
+    Align,
+    /// the method `name` of `tait` which is either implemented by the impl for the type or the trait's default
+    Method {
+        tait: String,
+        name: String,
+        default: bool,
+    },
+}
+
+/// checks the trait of a trait object, it has to be known and object safe
+pub fn check_dyn_ty(ctx: &mut TyCtx, dyn_ty: &ast::DynTy) {
+    let name = dyn_ty.tait.kind.simple_ty_name();
+    if ctx.env.resolve_trait(&DEFAULT_PATH.to_string(), &name).is_none() && !ctx.traits.is_builtin_trait(&name) {
+        let mut diagnostic = ctx.diagnostics.diagnostic(name.clone());
+        diagnostic.error(format!("unknown trait `{}`", name));
+        diagnostic.build();
+        return;
+    }
+    ctx.check_generic_args(&dyn_ty.tait);
+    let violations = object_safety_violations(ctx, &dyn_ty.tait);
+    if !violations.is_empty() {
+        let mut diagnostic = ctx.diagnostics.diagnostic(format!("dyn {}", dyn_ty.tait.to_string()));
+        diagnostic.error(format!("the trait `{}` cannot be made into an object", name));
+        for (tait, violation) in violations {
+            diagnostic.note(format!("...because {} (in `{}`)", violation, tait));
+        }
+        diagnostic.build();
+    }
+}
+
+/// the reasons why `tait` and its supertraits can't be made into an object together with the trait they are caused by
+pub fn object_safety_violations(ctx: &TyCtx, tait: &ast::Ty) -> Vec<(String, ObjectSafetyViolation)> {
+    let sized = ctx.traits.lang_item("sized").cloned();
+    let mut violations = vec![];
+    for bound in ctx.traits.elaborate(tait) {
+        let name = bound.kind.simple_ty_name();
+        if Some(&name) == sized.as_ref() {
+            violations.push((tait.kind.simple_ty_name(), ObjectSafetyViolation::SizedSelf));
+            continue;
+        }
+        let def = match ctx.env.resolve_trait(&DEFAULT_PATH.to_string(), &name) {
+            Some(def) => def,
+            None => continue,
+        };
+        violations.extend(trait_violations(def).into_iter().map(|violation| (name.clone(), violation)));
+    }
+    violations
+}
+
+fn trait_violations(def: &TraitDef) -> Vec<ObjectSafetyViolation> {
+    let mut violations = vec![];
+    let self_ty = "Self".to_string();
+    for method in &*def.methods {
+        let header = &method.header;
+        let name = header.name.clone();
+        if !header.generics.is_empty() {
+            violations.push(ObjectSafetyViolation::GenericMethod(name));
+        } else if receiver(header).is_none() {
+            violations.push(ObjectSafetyViolation::NoReceiver(name));
+        } else if header.args[1..].iter().any(|(_, ty)| mentions(ty, &self_ty)) || header.ret.iter().any(|ret| mentions(ret, &self_ty)) {
+            violations.push(ObjectSafetyViolation::SelfInSignature(name));
+        }
+    }
+    for assoc_const in &*def.assoc_consts {
+        violations.push(ObjectSafetyViolation::AssocConst(assoc_const.name.clone()));
+    }
+    violations
+}
+
+/// the mutability of the method's receiver, `self` by value counts as `Mut`
+fn receiver(header: &FunctionHeader) -> Option<Mutability> {
+    let (name, ty) = header.args.first()?;
+    if name != "self" {
+        return None;
+    }
+    let is_self = |ty: &ast::Ty| matches!(ty.kind.get_owned(), Some(owned) if owned.name == "Self" && owned.generics.is_empty());
+    match &ty.kind {
+        TyKind::Ref(rf) if is_self(&rf.ty) => Some(rf.mutability),
+        _ if is_self(ty) => Some(Mutability::Mut),
+        _ => None,
+    }
+}
+
+/// checks the cast of a value of type `ty` to the trait object `target` and records the vtable of the cast,
+/// `origin` describes the cast, e.g. "cast from `&Circle` to `&dyn Shape` for the argument 1 of the call to `draw`"
+pub fn upcast(ctx: &mut TyCtx, ty: &Ty, target: &DynTy, origin: Origin) {
+    if !ctx.ty_implements(ty, &target.tait) {
+        let proof = ty.to_ast_ty().and_then(|ast_ty| ctx.explain(&ast_ty, &target.tait));
+        ctx.report_unsatisfied_bound(&ty.to_string(), &target.tait, &origin, proof);
+        return;
+    }
+    // generic params and other trait objects don't need a vtable of their own,
+    // the ones of the types they are instantiated with are used instead
+    if matches!(ty, Ty::Unresolved(_) | Ty::Dyn(_) | Ty::Infer(_) | Ty::Error) {
+        return;
+    }
+    let vtable = build_vtable(ctx, ty, &target.tait);
+    if !ctx.vtables.contains(&vtable) {
+        ctx.vtables.push(vtable);
+    }
+}
+
+/// lays out the vtable of `ty` cast to `dyn tait`, the methods of the supertraits come first,
+/// so the vtable of a trait starts with the one of its first supertrait
+fn build_vtable(ctx: &TyCtx, ty: &Ty, tait: &ast::Ty) -> Vtable {
+    let mut entries = vec![VtableEntry::DropInPlace, VtableEntry::Size, VtableEntry::Align];
+    // impls are looked up by the name of the type without its generic args
+    let ty_name = match ty {
+        Ty::Struct(strukt) => strukt.name.clone(),
+        ty => ty.to_string(),
+    };
+    let impls = ctx.env.resolve_impls(&DEFAULT_PATH.to_string(), &ty_name);
+    push_methods(ctx, impls, &tait.kind.simple_ty_name(), &mut vec![], &mut entries);
+    Vtable {
+        ty: ty.to_string(),
+        tait: tait.to_string(),
+        entries,
+    }
+}
+
+/// appends the methods of the trait named `name` after the ones of its supertraits, depth-first and in the order the
+/// supertraits are declared in, e.g. `trait Sprite: Shape + Colored` results in the vtable of `Shape` followed by the
+/// methods of `Colored` and `Sprite`, the traits in `visited` were laid out already (by another supertrait)
+fn push_methods(ctx: &TyCtx, impls: Option<&Vec<ast::AdtImpl>>, name: &String, visited: &mut Vec<String>, entries: &mut Vec<VtableEntry>) {
+    if visited.contains(name) {
+        return;
+    }
+    visited.push(name.clone());
+    let def = match ctx.env.resolve_trait(&DEFAULT_PATH.to_string(), name) {
+        Some(def) => def,
+        None => return,
+    };
+    for super_trait in &*def.req_sub_traits {
+        push_methods(ctx, impls, &super_trait.kind.simple_ty_name(), visited, entries);
+    }
+    let adt_impl = impls.into_iter().flatten()
+        .find(|adt_impl| matches!(&adt_impl.impl_trait, Some(impl_trait) if &impl_trait.kind.simple_ty_name() == name));
+    for method in &*def.methods {
+        let implemented = adt_impl.is_some_and(|adt_impl| adt_impl.methods.iter()
+            .any(|item| matches!(item, ast::ItemKind::FunctionDef(func) if func.header.name == method.header.name)));
+        entries.push(VtableEntry::Method {
+            tait: name.clone(),
+            name: method.header.name.clone(),
+            default: !implemented && method.default.is_some(),
+        });
+    }
+}
@@ -0,0 +1,171 @@
+use std::collections::HashMap;
+use crate::lexer::token::BinOp;
+use crate::parser::ast::{self, BinaryExprNode, TyKind, TyOrConstVal};
+use crate::tyck::infer::{InferKind, Origin};
+use crate::tyck::{tyck_node, PrimitiveTy, Ty, TyCtx};
+
+// operators on primitives are built into the language, while operators on other types are resolved
+// to the impls of the trait which is marked as the operator's lang item:
+// #[lang = "add"]
+// trait Add<Rhs> { type Output; fn add(self, rhs: Rhs) -> Self::Output; }
+// impl Add<Point> for Point { type Output = Point; ... }
+// here `a + b` with `a: Point` requires `b: Point` and results in a `Point`
+
+/// the lang item of the trait which overloads `op`, `&&`, `||` and `=` can't be overloaded
+fn lang_item(op: BinOp) -> Option<&'static str> {
+    Some(match op {
+        BinOp::Add => "add",
+        BinOp::Sub => "sub",
+        BinOp::Mul => "mul",
+        BinOp::Div => "div",
+        BinOp::Mod => "rem",
+        BinOp::AddEq => "add_assign",
+        BinOp::SubEq => "sub_assign",
+        BinOp::MulEq => "mul_assign",
+        BinOp::DivEq => "div_assign",
+        BinOp::AndEq => "bitand_assign",
+        BinOp::OrEq => "bitor_assign",
+        BinOp::EqEq | BinOp::NEq => "eq",
+        BinOp::Lt | BinOp::Gt | BinOp::Le | BinOp::Ge => "partial_ord",
+        BinOp::AndAnd | BinOp::OrOr | BinOp::Eq => return None,
+    })
+}
+
+/// the type `op` results in independently of its operands (if any), arithmetic operators result in `Output` instead
+fn fixed_result_ty(op: BinOp) -> Option<Ty> {
+    if op.is_comparison() || matches!(op, BinOp::AndAnd | BinOp::OrOr) {
+        Some(Ty::Primitive(PrimitiveTy::Bool))
+    } else if op.is_assign() {
+        Some(Ty::Empty)
+    } else {
+        None
+    }
+}
+
+/// whether `op` is built into the language for a lhs of type `ty`
+fn is_builtin(op: BinOp, ty: &Ty) -> bool {
+    match ty {
+        Ty::Primitive(primitive) => {
+            let int = matches!(primitive, PrimitiveTy::MachineSizedInt(_) | PrimitiveTy::SizedInt(_) | PrimitiveTy::UnsizedInt);
+            let numeric = int || matches!(primitive, PrimitiveTy::SizedFloat(_));
+            match op {
+                _ if op.is_comparison() => true,
+                BinOp::AndEq | BinOp::OrEq => int || primitive == &PrimitiveTy::Bool,
+                _ => numeric,
+            }
+        }
+        Ty::Infer(var) => match var.kind {
+            // FIXME: the type isn't known yet, we should defer the operator until it is
+            InferKind::Ty | InferKind::Int => true,
+            InferKind::Float => !matches!(op, BinOp::AndEq | BinOp::OrEq),
+        },
+        // e.g. comparing two `&str`s
+        Ty::Ref(rf) => op.is_comparison() && is_builtin(op, &rf.ty),
+        // errors were already reported
+        Ty::Error => true,
+        _ => false,
+    }
+}
+
+pub(crate) fn tyck_bin_op(ctx: &mut TyCtx, expr: &BinaryExprNode) -> Ty {
+    let lhs = tyck_node(ctx, &expr.lhs);
+    let rhs = tyck_node(ctx, &expr.rhs);
+    let origin = Origin(format!("operator `{}`", expr.op.to_string()));
+    match expr.op {
+        BinOp::AndAnd | BinOp::OrOr => {
+            let bool_ty = Ty::Primitive(PrimitiveTy::Bool);
+            ctx.unify(&bool_ty, &lhs, origin.clone());
+            ctx.unify(&bool_ty, &rhs, origin);
+            return bool_ty;
+        }
+        BinOp::Eq => {
+            ctx.unify(&lhs, &rhs, origin);
+            return Ty::Empty;
+        }
+        _ => {}
+    }
+    let lhs_resolved = ctx.infcx.resolve_fully(&lhs);
+    if is_builtin(expr.op, &lhs_resolved) {
+        ctx.unify(&lhs, &rhs, origin);
+        return fixed_result_ty(expr.op).unwrap_or(lhs);
+    }
+    match tyck_overloaded_op(ctx, expr.op, &lhs_resolved, &rhs) {
+        Ok(ret) => ret,
+        Err(note) => {
+            let mut diagnostic = ctx.diagnostics.diagnostic(origin.0);
+            diagnostic.error(format!("cannot apply binary operator `{}` to type `{}`", expr.op.to_string(), lhs_resolved));
+            diagnostic.note(note);
+            diagnostic.build();
+            fixed_result_ty(expr.op).unwrap_or(Ty::Error)
+        }
+    }
+}
+
+/// resolves `lhs op rhs` to the impl of the lang item trait of `op` for `lhs`,
+/// if there is no such impl a note explaining why gets returned
+fn tyck_overloaded_op(ctx: &mut TyCtx, op: BinOp, lhs: &Ty, rhs: &Ty) -> Result<Ty, String> {
+    let lang_item = lang_item(op).unwrap();
+    let tait = match ctx.traits.lang_item(lang_item) {
+        Some(tait) => tait.clone(),
+        None => return Err(format!("there is no trait marked with `#[lang = \"{}\"]` which could overload `{}`", lang_item, op.to_string())),
+    };
+    let origin = Origin(format!("operator `{}` on `{}`", op.to_string(), lhs));
+
+    if let Ty::Unresolved(param) = lhs {
+        if ctx.is_generic_param(&param.name) {
+            // generic params only implement the traits they are bounded by
+            let bound = ctx.param_env().into_iter()
+                .find(|(ty, bound)| ty.kind.simple_ty_name() == param.name && bound.kind.simple_ty_name() == tait)
+                .map(|(_, bound)| bound);
+            let bound = match bound {
+                Some(bound) => bound,
+                None => return Err(format!("`{}` might need a bound of `{}`", param.name, tait)),
+            };
+            if let Some(expected_rhs) = rhs_ty(&bound) {
+                let expected_rhs = ctx.lower_ty(&expected_rhs);
+                ctx.unify(&expected_rhs, rhs, origin);
+            }
+            // FIXME: use the projection `T::Output` once projections of generic params are supported
+            return Ok(fixed_result_ty(op).unwrap_or_else(|| ctx.infcx.fresh(InferKind::Ty, Origin(format!("output of `{}`", tait)))));
+        }
+    }
+
+    let self_ty = match lhs.to_ast_ty() {
+        Some(self_ty) => self_ty,
+        None => return Err(format!("`{}` can't implement `{}`", lhs, tait)),
+    };
+    let self_binding = HashMap::from([("Self".to_string(), self_ty.clone())]);
+    let mut impls = ctx.traits.find_impls(&self_ty, &tait).into_iter().map(|(trait_ref, assoc_tys)| {
+        // a missing `Rhs` defaults to `Self`
+        let expected_rhs = ctx.lower_ty(&rhs_ty(&trait_ref).unwrap_or_else(|| self_ty.clone()).substitute(&self_binding));
+        (trait_ref, expected_rhs, assoc_tys)
+    }).collect::<Vec<_>>();
+    if impls.is_empty() {
+        return Err(format!("`{}` doesn't implement `{}`", lhs, tait));
+    }
+    // pick the impl which fits the rhs if there are multiple ones, e.g. `impl Mul<u32> for Vec2` and `impl Mul<Vec2> for Vec2`
+    let found_rhs = ctx.infcx.resolve_fully(rhs);
+    let idx = impls.iter().position(|(_, expected_rhs, _)| expected_rhs.could_be(&found_rhs)).unwrap_or(0);
+    let (trait_ref, expected_rhs, assoc_tys) = impls.swap_remove(idx);
+    ctx.unify(&expected_rhs, rhs, Origin(format!("operator `{}` implemented by `impl {} for {}`", op.to_string(), trait_ref.to_string(), lhs)));
+
+    if let Some(ret) = fixed_result_ty(op) {
+        return Ok(ret);
+    }
+    Ok(match assoc_tys.get("Output") {
+        Some(output) => ctx.lower_ty(&output.substitute(&self_binding)),
+        // the impl itself reports the missing type
+        None => Ty::Error,
+    })
+}
+
+/// the type of the rhs an operator trait expects, e.g. `u32` for `Mul<u32>`
+fn rhs_ty(tait: &ast::Ty) -> Option<ast::Ty> {
+    match &tait.kind {
+        TyKind::Owned(owned) => owned.generics.iter().find_map(|generic| match generic {
+            TyOrConstVal::Ty(ty) => Some(ty.clone()),
+            TyOrConstVal::ConstVal(_) | TyOrConstVal::Lifetime(_) => None,
+        }),
+        _ => None,
+    }
+}
@@ -0,0 +1,29 @@
+struct Wrapper<T> {
+   val: T,
+}
+
+type Wrapped<T> = Wrapper<T>;
+
+trait Container {
+    type Item: Clone;
+    const LEN: usize;
+
+    fn first() -> Self::Item;
+}
+
+impl Container for Wrapper<u8> {
+    type Item = u8;
+    const LEN: usize = 1;
+
+    fn first() -> u8 {
+        0
+    }
+}
+
+fn get(val: Wrapped<u8>) -> Wrapper<u8>::Item {
+   0
+}
+
+fn unwrap(val: Wrapped<bool>) -> Wrapper<u8>::Item {
+   true
+}
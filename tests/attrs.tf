@@ -0,0 +1,39 @@
+#![allow(deprecated)]
+
+#[derive(Clone, Copy)]
+struct Point {
+    #[deprecated(note = "use y instead")]
+    x: usize,
+    y: usize,
+}
+
+#[inline(always)]
+#[deprecated = "use new_fn instead"]
+fn old_fn() -> usize {
+    #![allow(unused)]
+    #[cfg(feature = "fast")]
+    let val = 1;
+    val
+}
+
+#[test]
+fn calls_old() {
+    old_fn();
+}
+
+#[frobnicate]
+trait Marker {
+    #[inline]
+    fn mark() {
+    }
+}
+
+#[inline]
+struct Misplaced {
+
+}
+
+#[allow = "unused"]
+fn malformed() {
+
+}
@@ -0,0 +1,82 @@
+trait Shape {
+
+}
+
+struct Handle {
+    id: u32,
+}
+
+struct Window {
+    handle: Handle,
+    title: &str,
+}
+
+struct Node {
+    value: u32,
+    next: &Node,
+}
+
+struct Pair<T> {
+    first: T,
+    second: [T; 2],
+}
+
+impl !Send for Handle {
+
+}
+
+impl !Shape for Node {
+
+}
+
+impl Copy for Node {
+
+}
+
+fn spawn<T: Send>(value: T) -> u32 {
+    0
+}
+
+fn share<T: Sync>(value: T) -> u32 {
+    0
+}
+
+fn duplicate<T: Copy>(value: T) -> u32 {
+    0
+}
+
+fn measure<T: Sized>(value: T) -> u32 {
+    0
+}
+
+fn forward<T: Send>(pair: Pair<T>) -> u32 {
+    spawn(pair)
+}
+
+fn main() {
+    let handle = Handle { id: 1 };
+    let window = Window { handle: handle, title: "main" };
+    let node: Node = make_node();
+    let number: u8 = 5;
+    spawn(window);
+    share(window);
+    spawn(node);
+    duplicate(node);
+    duplicate(window);
+    measure(number);
+}
+
+fn borrow(number: &u8, node: &Node) -> u32 {
+    spawn(number);
+    duplicate(number);
+    spawn(node)
+}
+
+fn make_node() -> Node {
+    make_node()
+}
+
+fn send_pair(handles: Pair<Handle>, ids: Pair<u32>) -> u32 {
+    spawn(ids);
+    spawn(handles)
+}
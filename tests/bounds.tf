@@ -0,0 +1,51 @@
+trait Shape {
+
+}
+
+trait Named {
+
+}
+
+trait Drawable {
+
+}
+
+struct Circle {
+    radius: u32,
+}
+
+struct Square {
+    side: u32,
+}
+
+struct Boxed<T> {
+    inner: T,
+}
+
+struct Registry<N: Named> {
+    named: N,
+}
+
+impl Shape for Circle {
+
+}
+
+impl<T: Shape> Named for Boxed<T> {
+
+}
+
+impl<T: Shape> Drawable for T {
+
+}
+
+fn draw<D: Drawable>(drawable: D) {
+
+}
+
+fn valid(circle: Circle, registry: Registry<Boxed<Circle>>) {
+    draw(circle);
+}
+
+fn invalid(square: Square, registry: Registry<Boxed<Square>>) {
+    draw(square);
+}
@@ -0,0 +1,29 @@
+trait Shape {}
+
+struct Square {}
+
+impl Shape for Square {}
+
+fn pick<T: Clone>(a: T, b: T) -> T {
+    a
+}
+
+fn area<S>(shape: S) -> u32 where S: Shape {
+    4
+}
+
+fn main() {
+    let small = pick::<u8>(1, 2);
+    let same: u8 = pick(small, 3);
+    let size = area(Square {});
+}
+
+fn takes(a: u8, b: bool) -> u8 {
+    a
+}
+
+fn misuse() {
+    let few = takes(1);
+    let wrong = takes(1, 2);
+    let unbound = pick::<Square>(Square {}, Square {});
+}
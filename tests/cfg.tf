@@ -0,0 +1,36 @@
+#[cfg(feature = "std")]
+fn with_std() {
+    #[cfg(not(test))]
+    let a = 1;
+    #[cfg(test)]
+    let a = 2;
+}
+
+#[cfg(not(feature = "std"))]
+fn without_std() {
+
+}
+
+struct Config {
+    #[cfg(any(test, feature = "debug"))]
+    verbose: usize,
+    #[cfg(all(feature = "std", not(feature = "debug")))]
+    fast: usize,
+}
+
+#[cfg(feature = "debug")]
+impl Config {
+
+}
+
+impl Config {
+    #[cfg(test)]
+    fn test_only() {
+
+    }
+}
+
+#[cfg(nope(test))]
+fn invalid_predicate() {
+
+}
@@ -0,0 +1,28 @@
+fn apply(f: fn(u32) -> u32, val: u32) -> u32 {
+   f(val)
+}
+
+fn apply_bound<F: FnMut(u32) -> u32>(f: F) -> u32 {
+   f(2)
+}
+
+fn test() -> u32 {
+   let base = 4;
+   let mut counter = 0;
+   let add = |a, b| a + b;
+   let scale = |x: u32| x * base;
+   let offset = move |x: u32| -> u32 { x + base };
+   let count = || counter = counter + 1;
+   add(base, 2)
+}
+
+fn invalid() -> u32 {
+   let run = |x: u32| -> u32 {
+      fn twice(y: u32) -> u32 {
+         y * true
+      }
+      undefined(x);
+      twice(x, x)
+   };
+   run(1)
+}
@@ -0,0 +1,63 @@
+trait Shape {
+
+}
+
+trait Area {
+
+}
+
+trait Encode<T> {
+
+}
+
+struct Circle {
+    radius: u32,
+}
+
+struct Square {
+    side: u32,
+}
+
+struct Wrapper<T> {
+    inner: T,
+}
+
+impl Shape for Circle {
+
+}
+
+impl Shape for Circle {
+
+}
+
+impl<T> Shape for Wrapper<T> {
+
+}
+
+impl Shape for Wrapper<u32> {
+
+}
+
+impl<T: Copy> Area for T {
+
+}
+
+impl Area for u8 {
+
+}
+
+impl Area for Circle {
+
+}
+
+impl Encode<u8> for Square {
+
+}
+
+impl Encode<u16> for Square {
+
+}
+
+impl Default for &str {
+
+}
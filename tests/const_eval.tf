@@ -0,0 +1,37 @@
+struct Rect {
+    width: u32,
+    height: u32,
+}
+
+const SIDE: u32 = 12;
+const AREA: u32 = SIDE * SIDE + 4 / 2;
+const IS_BIG: bool = AREA > 100 && SIDE != 0;
+const LIMIT: u8 = 200 + 100;
+const HALF: u32 = AREA / (SIDE - 12);
+const NEGATIVE: i8 = 300;
+const FIRST: u32 = SECOND + 1;
+const SECOND: u32 = THIRD;
+const THIRD: u32 = FIRST;
+const SQUARE: Rect = Rect { width: SIDE, height: SIDE };
+const WIDE: Rect = Rect { width: 2 * SIDE, ..SQUARE };
+const ZEROS: [u32; 4] = [0; 2 + 2];
+const SMALL: bool = AREA == 100 || 3 <= 2;
+
+fn lengths(grid: [u8; SIDE / 0]) -> u32 {
+    let buf = [0; 3 - 4];
+    AREA
+}
+
+trait Packed {}
+impl Packed for [u8; 2] {}
+impl Packed for [u8; SIDE - 10] {}
+
+fn pack<T: Packed>(val: T) {}
+
+fn packs() {
+    let pair: [u8; 1 + 1] = [1, 2];
+    pack(pair);
+    let triple: [u8; 2] = [1, 2, 3];
+    let other: [u8; 3] = [1, 2, 3];
+    pack(other);
+}
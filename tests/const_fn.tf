@@ -0,0 +1,38 @@
+static COUNTER: u32 = 0;
+const BASE: u32 = 10;
+
+const fn square(x: u32) -> u32 {
+    x * x
+}
+
+const fn area(width: u32, height: u32) -> u32 {
+    let sum = square(width) + height;
+    sum * BASE
+}
+
+const fn halve(x: u32) -> u32 {
+    x / (x - x)
+}
+
+const fn forever(x: u32) -> u32 {
+    forever(x + 1)
+}
+
+fn now() -> u32 {
+    COUNTER
+}
+
+runtime fn random() -> u32 {
+    4
+}
+
+const fn invalid(x: u32) -> u32 {
+    let callback = |y: u32| y + 1;
+    callback(x) + now() + random() + COUNTER
+}
+
+const AREA: u32 = area(3, 4);
+const HALF: u32 = halve(8);
+const FOREVER: u32 = forever(1);
+const NOW: u32 = now();
+const SQUARES: [u32; square(2)] = [square(3); 4];
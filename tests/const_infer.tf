@@ -0,0 +1,45 @@
+static COUNTER: u32 = 0;
+
+fn double(x: u32) -> u32 {
+    x * 2
+}
+
+fn quadruple(x: u32) -> u32 {
+    double(double(x))
+}
+
+fn even(n: u32) -> u32 {
+    odd(n) + 1
+}
+
+fn odd(n: u32) -> u32 {
+    even(n) - 1
+}
+
+fn ticks() -> u32 {
+    COUNTER
+}
+
+fn elapsed(start: u32) -> u32 {
+    ticks() - start
+}
+
+fn report(start: u32) -> u32 {
+    elapsed(start) + double(start)
+}
+
+runtime fn seed() -> u32 {
+    7
+}
+
+fn mix(x: u32) -> u32 {
+    seed() + x
+}
+
+const fn uses_elapsed(x: u32) -> u32 {
+    elapsed(x) + quadruple(x)
+}
+
+const FOUR: u32 = quadruple(1);
+const REPORT: u32 = report(1);
+const MIXED: u32 = mix(2);
@@ -0,0 +1,27 @@
+#[derive(Clone, Copy, Debug, PartialEq, Default)]
+struct Point {
+    x: usize,
+    y: usize,
+}
+
+#[derive(Debug, PartialEq)]
+struct Named<'a> {
+    name: &'a str,
+    point: Point,
+}
+
+#[derive(Clone, Default)]
+struct Wrapper<T> {
+    inner: T,
+    points: [Point; 2],
+}
+
+struct Label {
+    len: usize,
+}
+
+#[derive(Copy)]
+struct Tagged {
+    label: Label,
+    id: usize,
+}
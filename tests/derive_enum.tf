@@ -0,0 +1,20 @@
+#[derive(Clone, Debug)]
+enum Shape {
+    Circle { radius: u32 },
+    Square(u32),
+    Empty,
+}
+
+pub enum Direction<T> {
+    Up(T),
+    Down(T),
+}
+
+#[derive(Clone)]
+struct Canvas {
+    width: u32,
+}
+
+fn copy(canvas: Canvas) -> Canvas {
+    canvas
+}
@@ -0,0 +1,146 @@
+trait Named {
+
+    fn name(&self) -> u32;
+
+}
+
+trait Shape: Named {
+
+    fn area(&self) -> u32;
+
+    fn scale(&mut self, factor: u32) -> u32 {
+        factor
+    }
+
+}
+
+trait Colored: Named {
+
+    fn color(&self) -> u32;
+
+}
+
+trait Sprite: Shape + Colored {
+
+    fn frame(&self) -> u32;
+
+}
+
+trait Factory {
+
+    fn create() -> u32;
+
+    fn convert<T>(&self, value: T) -> u32;
+
+    fn merge(&self, other: Self) -> u32;
+
+}
+
+trait Resizable: Sized {
+
+    fn resize(self, factor: u32) -> u32;
+
+}
+
+#[lang = "owned_box"]
+struct Box<T> {
+    ptr: &T,
+}
+
+struct Circle {
+    radius: u32,
+}
+
+struct Point {
+    x: u32,
+}
+
+impl Named for Circle {
+
+    fn name(&self) -> u32 {
+        0
+    }
+
+}
+
+impl Shape for Circle {
+
+    fn area(&self) -> u32 {
+        1
+    }
+
+}
+
+impl Colored for Circle {
+
+    fn color(&self) -> u32 {
+        2
+    }
+
+}
+
+impl Sprite for Circle {
+
+    fn frame(&self) -> u32 {
+        3
+    }
+
+}
+
+struct Sphere<T> {
+    radius: T,
+}
+
+impl<T> Named for Sphere<T> {
+
+    fn name(&self) -> u32 {
+        2
+    }
+
+}
+
+impl<T> Shape for Sphere<T> {
+
+    fn area(&self) -> u32 {
+        3
+    }
+
+    fn scale(&mut self, factor: u32) -> u32 {
+        4
+    }
+
+}
+
+fn draw(shape: &dyn Shape) -> u32 {
+    0
+}
+
+fn animate(sprite: &dyn Sprite) -> u32 {
+    0
+}
+
+fn label(named: &dyn Named) -> u32 {
+    0
+}
+
+fn store(shape: Box<dyn Shape>) -> u32 {
+    0
+}
+
+fn produce(factory: &dyn Factory, resizable: &dyn Resizable, shape: dyn Shape) -> u32 {
+    0
+}
+
+fn main(circle: &Circle, point: &Point, shape: &dyn Shape) -> u32 {
+    draw(circle);
+    draw(point);
+    label(shape);
+    store(Box { ptr: circle });
+    store(Box { ptr: point });
+    animate(circle);
+    label(circle)
+}
+
+fn draw_sphere(sphere: &Sphere<u32>) -> u32 {
+    draw(sphere)
+}
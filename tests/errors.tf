@@ -0,0 +1,22 @@
+type Pair<T> = T;
+
+fn first(a: Pair<u8, u16>) -> u32 {
+    valeu
+}
+
+fn second(value: u32) -> u32 {
+    let q = undefined(value);
+    let s: Strng = value;
+    let t: u8 = s;
+    value
+}
+
+struct Meters {}
+
+fn third() {
+    impl Meters {
+        fn zero() -> u32 {
+            0
+        }
+    }
+}
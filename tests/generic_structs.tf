@@ -0,0 +1,22 @@
+struct Wrapper<T> {
+    val: T,
+}
+
+fn wrap<T>(val: Wrapper<T>) -> Wrapper<T> {
+    val
+}
+
+fn annotated(flag: bool) -> u8 {
+    let a: Wrapper<u8> = Wrapper { val: flag };
+    let b: Wrapper<bool> = Wrapper { val: flag };
+    let c: Wrapper<u8> = b;
+    0
+}
+
+fn inferred() -> Wrapper<u8> {
+    wrap(Wrapper { val: 1 })
+}
+
+fn mismatched(flag: bool) -> Wrapper<u8> {
+    wrap(Wrapper { val: flag })
+}
@@ -15,9 +15,9 @@ trait CivTransformer<Civ: CivBase + Send + Sync> {
 
 }
 
-// trait Test<'a, 'b: 'a, 'c: 'b + 'a, 'd: 'a + 'b + 'c + 'static + 'd> {
-//
-// }
+trait Test<'a, 'b: 'a, 'c: 'b + 'a, 'd: 'a + 'b + 'c + 'static + 'd> {
+
+}
 
 impl<T: Copy> CivTransformer for T {
 
@@ -0,0 +1,70 @@
+trait Shape {
+
+}
+
+trait Encode {
+
+}
+
+struct Circle {
+    radius: u32,
+}
+
+struct Square {
+    side: u32,
+}
+
+struct List<T> {
+    elem: T,
+}
+
+struct Pair<A, B> {
+    first: A,
+    second: B,
+}
+
+struct Store<E: Encode> {
+    encoded: E,
+}
+
+impl Shape for Circle {
+
+}
+
+impl Encode for List<u8> {
+
+}
+
+impl<T: Shape> Encode for List<List<T>> {
+
+}
+
+impl<T: Encode> Encode for Pair<T, T> {
+
+}
+
+fn valid(bytes: Store<List<u8>>, shapes: Store<List<List<Circle>>>, pairs: Store<Pair<List<u8>, List<u8>>>) {
+
+}
+
+fn invalid(words: Store<List<u16>>, squares: Store<List<List<Square>>>, mixed: Store<Pair<List<u8>, List<Circle>>>) {
+
+}
+
+struct Tagged<T> {
+    value: T,
+}
+
+impl<T, S: Shape> Encode for Tagged<T> {
+
+}
+
+fn encode<E: Encode>(value: E) -> u32 {
+    0
+}
+
+fn calls(bytes: List<u8>, words: List<u16>, tagged: Tagged<Circle>) -> u32 {
+    encode(bytes);
+    encode(words);
+    encode(tagged)
+}
@@ -0,0 +1,16 @@
+fn main() {
+    let x = 5;
+    let y: u8 = x;
+    let scale = 1.5;
+    let half: f32 = scale;
+    let add = |a, b| a + b;
+    let sum = add(x, 2);
+    let all = [sum, 3, 4];
+}
+
+fn mismatch() {
+    let count = 3;
+    let flag: bool = count;
+    let mixed = [count, 0.5];
+    let huge = 340282366920938463463374607431768211456;
+}
@@ -0,0 +1,13 @@
+#[deprecated(note = "use new_fn instead")]
+fn old_fn() -> usize {
+    1
+}
+
+fn new_fn() -> usize {
+    old_fn()
+}
+
+#[allow(deprecated)]
+fn still_old() -> usize {
+    old_fn()
+}
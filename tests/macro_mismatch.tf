@@ -0,0 +1,23 @@
+macro_rules! pair {
+    ($a:expr, $b:expr) => { $a + $b };
+}
+
+fn mismatched() -> u32 {
+    pair!(1)
+}
+
+macro_rules! zip {
+    ($($a:expr),* ; $($b:expr),*) => { 0 $(+ $a * $b)* };
+}
+
+fn uneven() -> u32 {
+    zip!(1, 2; 3)
+}
+
+macro_rules! first {
+    ($($a:expr),*) => { 0 $(+ $a)? };
+}
+
+fn too_many() -> u32 {
+    first!(1, 2)
+}
@@ -0,0 +1,7 @@
+macro_rules! forever {
+    ($e:expr) => { forever!($e + 1) };
+}
+
+fn endless() -> u32 {
+    forever!(0)
+}
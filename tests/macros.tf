@@ -0,0 +1,30 @@
+macro_rules! square {
+    ($x:expr) => { $x * $x };
+}
+
+macro_rules! sum {
+    () => { 0 };
+    ($head:expr $(, $tail:expr)*) => { $head + sum!($($tail),*) };
+}
+
+macro_rules! make_const {
+    ($name:ident, $ty:ty, $val:literal) => { const $name: $ty = $val; };
+}
+
+macro_rules! with_tmp {
+    ($e:expr) => { { let tmp = 2; tmp * $e } };
+}
+
+make_const!(LIMIT, u32, 10);
+
+const SQUARE: u32 = square!(1 + 2);
+
+const SUM: u32 = sum!(1, 2, 3);
+
+fn squared() -> u32 {
+    let tmp = 3;
+    let a = square!(1 + 2);
+    let b = sum!(1, 2, 3);
+    println!("Hello world!");
+    with_tmp!(tmp)
+}
@@ -0,0 +1,42 @@
+#[lang = "add"]
+trait Add<Rhs> {
+    type Output;
+    fn add(lhs: Self, rhs: Rhs) -> Self::Output;
+}
+
+#[lang = "eq"]
+trait PartialEq {
+    fn eq(lhs: &Self, rhs: &Self) -> bool;
+}
+
+struct Meters {
+    val: u32,
+}
+
+impl Add<Meters> for Meters {
+    type Output = Meters;
+    fn add(lhs: Meters, rhs: Meters) -> Meters {
+        rhs
+    }
+}
+
+impl PartialEq for Meters {
+    fn eq(lhs: &Meters, rhs: &Meters) -> bool {
+        true
+    }
+}
+
+fn ops(a: u32, b: Meters, c: Meters, flag: bool) -> bool {
+    let sum = a + 2 * a % 3;
+    let total: Meters = b + c;
+    let mut x = 1.5;
+    x += 2.0;
+    let same = total == c;
+    a <= sum && flag || a != 4 && same
+}
+
+fn errors(a: u32, b: Meters, flag: bool) {
+    let wrong = a && flag;
+    let meters = b + 1;
+    let sum = flag + flag;
+}
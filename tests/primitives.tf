@@ -0,0 +1,19 @@
+struct Wide {
+    signed: i128,
+    unsigned: u128,
+    size: usize,
+    flag: bool,
+    ratio: f32,
+}
+
+static SMALL: u8 = 255;
+
+fn widen(a: u64, b: u128) -> u128 {
+    let c: i8 = 1;
+    let d: f64 = 2.5;
+    b + 1
+}
+
+fn narrow(a: u33, b: boool) -> i8 {
+    1
+}
@@ -0,0 +1,48 @@
+trait Peaceful {
+
+}
+
+trait CivBase {
+
+}
+
+trait Shape {
+
+}
+
+struct Continent {
+    size: u32,
+}
+
+struct Civilization<C> {
+    continent: C,
+}
+
+struct Empire<Civ: CivBase> {
+    civ: Civ,
+}
+
+struct Circle {
+    radius: u32,
+}
+
+impl<Cont: Peaceful> CivBase for Civilization<Cont> {
+
+}
+
+impl Shape for Circle {
+
+}
+
+fn conquer(empire: Empire<Civilization<Continent>>) -> u32 {
+    0
+}
+
+fn draw<S: Shape>(shape: S) -> u32 {
+    0
+}
+
+fn main() {
+    let value: u8 = 5;
+    draw(value);
+}
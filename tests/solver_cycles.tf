@@ -0,0 +1,44 @@
+#![recursion_limit = "16"]
+
+trait A {
+
+}
+
+trait B {
+
+}
+
+trait Grow {
+
+}
+
+struct Wrapper<T> {
+    inner: T,
+}
+
+impl<T: A> B for T {
+
+}
+
+impl<T: B> A for T {
+
+}
+
+impl<T> Grow for T where Wrapper<T>: Grow {
+
+}
+
+fn needs_a<X: A>(x: X) -> u32 {
+    0
+}
+
+fn needs_grow<X: Grow>(x: X) -> u32 {
+    0
+}
+
+fn main() {
+    let value: u8 = 5;
+    needs_a(value);
+    needs_a(value);
+    needs_grow(value);
+}
@@ -0,0 +1,22 @@
+struct Point {
+    x: u32,
+    y: u32,
+    label: u8,
+}
+
+fn shift(x: u32, origin: Point) -> Point {
+    let moved = Point { x, ..origin };
+    Point { x, y: 2, label: 3 }
+}
+
+struct Pair<T> {
+    first: T,
+    second: T,
+}
+
+fn pairs(other: Pair<bool>, flag: bool) -> Pair<u16> {
+    let flags: Pair<bool> = Pair { first: flag, second: 1 };
+    let counts: Pair<bool> = Pair { first: 1, second: 2 };
+    let updated: Pair<u8> = Pair { first: 1, ..other };
+    Pair { first: 1, second: 2 }
+}
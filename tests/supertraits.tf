@@ -0,0 +1,55 @@
+trait Shape {
+
+}
+
+trait Solid: Shape {
+
+}
+
+trait Named {
+
+}
+
+struct Cube {
+    side: u32,
+}
+
+struct Sphere {
+    radius: u32,
+}
+
+struct Boxed<T> {
+    inner: T,
+}
+
+impl Shape for Cube {
+
+}
+
+impl Solid for Cube {
+
+}
+
+impl Solid for Sphere {
+
+}
+
+impl<T: Shape> Shape for Boxed<T> {
+
+}
+
+impl<T: Solid> Solid for Boxed<T> {
+
+}
+
+fn area<S: Shape>(shape: S) -> u32 {
+    0
+}
+
+fn volume<S: Solid>(solid: S) -> u32 {
+    area(solid)
+}
+
+fn named<N: Named>(named: N) -> u32 {
+    area(named)
+}
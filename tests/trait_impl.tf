@@ -0,0 +1,47 @@
+struct Wrapper {
+
+}
+
+trait Base {
+
+}
+
+trait Container: Base {
+    type Item;
+    const LEN: usize;
+
+    fn first(idx: usize) -> Self::Item;
+
+    fn count() -> usize {
+        0
+    }
+}
+
+impl Base for Wrapper {
+
+}
+
+impl Container for Wrapper {
+    type Item = u8;
+    const LEN: usize = 1;
+
+    fn first(idx: usize) -> u8 {
+        0
+    }
+}
+
+struct Broken {
+
+}
+
+impl Container for Broken {
+    const LEN: u8 = 1;
+
+    fn first(idx: u8) -> u8 {
+        0
+    }
+
+    fn last() -> u8 {
+        0
+    }
+}
@@ -0,0 +1,41 @@
+struct Holder<'a, 'b: 'a, T: Clone + 'a> where T: Copy {
+    val: &'a &'b T,
+}
+
+trait Pick<'a> where Self: Clone {
+    fn pick(val: &'a u8) -> &'a u8;
+}
+
+impl<'a, T> Pick<'a> for Holder<'a, 'a, T> where T: Clone + Copy + 'a, 'a: 'static {
+    fn pick(val: &'a u8) -> &'a u8 {
+        val
+    }
+}
+
+fn first<'a, 'b>(left: &'a u8, right: &'b u8) -> &'a u8 where 'b: 'a {
+    left
+}
+
+struct Outlives<'a, 'b> {
+    val: &'a &'b u8,
+}
+
+struct Plain {
+
+}
+
+fn undeclared(val: &'c u8) -> u8 {
+    0
+}
+
+fn elided(left: &u8, right: &u8) -> &u8 {
+    left
+}
+
+fn copied<T>(val: T) -> T where T: Copy {
+    val
+}
+
+fn copy_plain() {
+    copied(Plain {});
+}